@@ -0,0 +1,586 @@
+#![allow(dead_code)]
+
+use std::f64::consts::PI;
+use std::marker::PhantomData;
+
+use super::points::{Point, Point2D, Point3D};
+use super::vector::{add, combine, cross, dot, lerp, norm, scale, sub};
+use crate::utils::roots::newton;
+
+// five point gauss-legendre rule on [-1, 1]
+const GAUSS_NODES: [f64; 5] = [0.0, -0.538_469_310_105_683, 0.538_469_310_105_683, -0.906_179_845_938_664, 0.906_179_845_938_664];
+const GAUSS_WEIGHTS: [f64; 5] = [0.568_888_888_888_889, 0.478_628_670_499_366, 0.478_628_670_499_366, 0.236_926_885_056_189, 0.236_926_885_056_189];
+// subintervals the rule is applied over across the whole parameter range
+const GAUSS_INTERVALS: usize = 32;
+
+/// Points that can be taken apart into and built back from coordinates, so
+/// that curves can do their arithmetic the same way in 2D and 3D.
+pub trait Coordinates: Point + Sized {
+    fn from_coordinates(c: [f64; 3]) -> Self;
+    fn coordinates(&self) -> [f64; 3]; // z is zero in 2D
+}
+
+impl Coordinates for Point2D {
+    fn from_coordinates(c: [f64; 3]) -> Self {
+        Point2D::new(c[0], c[1])
+    }
+
+    fn coordinates(&self) -> [f64; 3] {
+        [self.x(), self.y(), 0.0]
+    }
+}
+
+impl Coordinates for Point3D {
+    fn from_coordinates(c: [f64; 3]) -> Self {
+        Point3D::new(c[0], c[1], c[2])
+    }
+
+    fn coordinates(&self) -> [f64; 3] {
+        [self.x(), self.y(), self.z()]
+    }
+}
+
+/// A curve running from t = 0 at its start to t = 1 at its end.
+pub trait Curve<P: Coordinates> {
+    fn point(&self, t: f64) -> P;
+
+    /// The rate of change of the point with t, as a vector.
+    fn derivative(&self, t: f64) -> P;
+
+    /// The length of the curve from its start up to t.
+    fn arc_length(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        let intervals = ((GAUSS_INTERVALS as f64 * t).ceil() as usize).max(1);
+        let h = t / intervals as f64;
+        (0..intervals).map(|n| {
+            let middle = (n as f64 + 0.5) * h;
+            GAUSS_NODES.iter().zip(GAUSS_WEIGHTS).map(|(x, w)| {
+                w * norm(self.derivative(middle + 0.5 * h * x).coordinates())
+            }).sum::<f64>() * 0.5 * h
+        }).sum()
+    }
+
+    fn length(&self) -> f64 {
+        self.arc_length(1.0)
+    }
+
+    /// The parameter at which the arc length from the start reaches `s`,
+    /// clamped to the ends of the curve.
+    fn parameter_at(&self, s: f64) -> Result<f64, &'static str> {
+        let total = self.length();
+        if s <= 0.0 {
+            return Ok(0.0);
+        }
+        if s >= total {
+            return Ok(1.0);
+        }
+        newton(|t| self.arc_length(t) - s, |t| norm(self.derivative(t).coordinates()), s / total, (0.0, 1.0))
+    }
+
+    /// Samples the curve at fractions of its arc length running from 0 to 1,
+    /// typically taken from a distribution.
+    fn discretise(&self, fractions: &[f64]) -> Result<Vec<P>, &'static str> {
+        let total = self.length();
+        if total <= 0.0 {
+            return Err("curve has no length");
+        }
+        fractions.iter().map(|f| self.parameter_at(f * total).map(|t| self.point(t))).collect()
+    }
+}
+
+/// A straight line between two points.
+pub struct Segment<P: Coordinates> {
+    start: [f64; 3],
+    end: [f64; 3],
+    point: PhantomData<P>,
+}
+
+impl<P: Coordinates> Segment<P> {
+    pub fn new(start: &P, end: &P) -> Self {
+        Segment { start: start.coordinates(), end: end.coordinates(), point: PhantomData }
+    }
+}
+
+impl<P: Coordinates> Curve<P> for Segment<P> {
+    fn point(&self, t: f64) -> P {
+        P::from_coordinates(lerp(self.start, self.end, t))
+    }
+
+    fn derivative(&self, _t: f64) -> P {
+        P::from_coordinates(sub(self.end, self.start))
+    }
+
+    fn arc_length(&self, t: f64) -> f64 {
+        t.clamp(0.0, 1.0) * norm(sub(self.end, self.start))
+    }
+
+    fn parameter_at(&self, s: f64) -> Result<f64, &'static str> {
+        let total = self.length();
+        Ok(if total > 0.0 { (s / total).clamp(0.0, 1.0) } else { 0.0 })
+    }
+}
+
+/// Straight lines through a list of points, with t proportional to arc length.
+pub struct Polyline<P: Coordinates> {
+    points: Vec<[f64; 3]>,
+    cumulative: Vec<f64>, // arc length up to each point
+    point: PhantomData<P>,
+}
+
+impl<P: Coordinates> Polyline<P> {
+    pub fn new(points: &[P]) -> Result<Self, &'static str> {
+        if points.len() < 2 {
+            return Err("polyline requires at least two points");
+        }
+        let points: Vec<[f64; 3]> = points.iter().map(|p| p.coordinates()).collect();
+        let mut cumulative = vec![0.0];
+        for pair in points.windows(2) {
+            cumulative.push(cumulative.last().unwrap() + norm(sub(pair[1], pair[0])));
+        }
+        if *cumulative.last().unwrap() <= 0.0 {
+            return Err("curve has no length");
+        }
+        Ok(Polyline { points, cumulative, point: PhantomData })
+    }
+
+    // the segment holding arc length s, and how far along it s lies
+    fn locate(&self, s: f64) -> (usize, f64) {
+        let last = self.points.len() - 2;
+        let segment = self.cumulative[1..=last].iter().take_while(|c| **c < s).count();
+        let length = self.cumulative[segment + 1] - self.cumulative[segment];
+        let local = if length > 0.0 { (s - self.cumulative[segment]) / length } else { 0.0 };
+        (segment, local)
+    }
+}
+
+impl<P: Coordinates> Curve<P> for Polyline<P> {
+    fn point(&self, t: f64) -> P {
+        let (segment, local) = self.locate(t * self.length());
+        P::from_coordinates(lerp(self.points[segment], self.points[segment + 1], local))
+    }
+
+    // one sided at the corners, taking the segment that t runs into
+    fn derivative(&self, t: f64) -> P {
+        let (segment, _) = self.locate(t * self.length());
+        let direction = sub(self.points[segment + 1], self.points[segment]);
+        let stretch = self.length() / (self.cumulative[segment + 1] - self.cumulative[segment]);
+        P::from_coordinates(scale(direction, stretch))
+    }
+
+    fn arc_length(&self, t: f64) -> f64 {
+        t.clamp(0.0, 1.0) * self.length()
+    }
+
+    fn length(&self) -> f64 {
+        *self.cumulative.last().unwrap()
+    }
+
+    fn parameter_at(&self, s: f64) -> Result<f64, &'static str> {
+        Ok((s / self.length()).clamp(0.0, 1.0))
+    }
+}
+
+/// Part of a circle swept at a constant rate in t.
+pub struct CircularArc<P: Coordinates> {
+    centre: [f64; 3],
+    u: [f64; 3], // unit vector from the centre to the start
+    v: [f64; 3], // unit vector a quarter turn on from u, which a positive sweep turns towards
+    radius: f64,
+    sweep: f64,  // radians, negative for a clockwise 2D arc
+    point: PhantomData<P>,
+}
+
+impl CircularArc<Point2D> {
+    /// An arc about `centre` from one angle to another, in radians measured
+    /// anticlockwise from the x axis. It runs clockwise if `end` is less than `start`.
+    pub fn new_2d(centre: &Point2D, radius: f64, start: f64, end: f64) -> Result<Self, &'static str> {
+        if radius <= 0.0 {
+            return Err("arc radius must be positive");
+        }
+        if end == start {
+            return Err("arc must sweep through a nonzero angle");
+        }
+        Ok(CircularArc {
+            centre: centre.coordinates(),
+            u: [start.cos(), start.sin(), 0.0],
+            v: [-start.sin(), start.cos(), 0.0],
+            radius,
+            sweep: end - start,
+            point: PhantomData,
+        })
+    }
+}
+
+impl<P: Coordinates> CircularArc<P> {
+    /// The arc from `start` to `end` passing through `middle`.
+    pub fn through(start: &P, middle: &P, end: &P) -> Result<Self, &'static str> {
+        let (a, b, c) = (start.coordinates(), middle.coordinates(), end.coordinates());
+        let (ab, ac) = (sub(b, a), sub(c, a));
+        let normal = cross(ab, ac);
+        let area = dot(normal, normal);
+        if area <= 1e-24 * dot(ab, ab) * dot(ac, ac) {
+            return Err("points are collinear, so no arc passes through them");
+        }
+
+        // the circumcentre, then axes in the plane with the start first
+        let offset = scale(
+            add(scale(cross(normal, ab), dot(ac, ac)), scale(cross(ac, normal), dot(ab, ab))),
+            0.5 / area,
+        );
+        let centre = add(a, offset);
+        let radius = norm(offset);
+        let u = scale(sub(a, centre), 1.0 / radius);
+        let v = cross(scale(normal, 1.0 / area.sqrt()), u);
+
+        // a, b and c run anticlockwise about the normal, so c is reached last
+        let to_end = sub(c, centre);
+        let mut sweep = dot(to_end, v).atan2(dot(to_end, u));
+        if sweep <= 0.0 {
+            sweep += 2.0 * PI;
+        }
+        Ok(CircularArc { centre, u, v, radius, sweep, point: PhantomData })
+    }
+
+    pub fn centre(&self) -> P {
+        P::from_coordinates(self.centre)
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl<P: Coordinates> Curve<P> for CircularArc<P> {
+    fn point(&self, t: f64) -> P {
+        let angle = t * self.sweep;
+        let radial = add(scale(self.u, angle.cos()), scale(self.v, angle.sin()));
+        P::from_coordinates(add(self.centre, scale(radial, self.radius)))
+    }
+
+    fn derivative(&self, t: f64) -> P {
+        let angle = t * self.sweep;
+        let tangent = add(scale(self.u, -angle.sin()), scale(self.v, angle.cos()));
+        P::from_coordinates(scale(tangent, self.radius * self.sweep))
+    }
+
+    fn arc_length(&self, t: f64) -> f64 {
+        t.clamp(0.0, 1.0) * self.radius * self.sweep.abs()
+    }
+
+    fn parameter_at(&self, s: f64) -> Result<f64, &'static str> {
+        Ok((s / self.length()).clamp(0.0, 1.0))
+    }
+}
+
+/// A cubic Bézier curve, leaving its first control point towards the second
+/// and arriving at the last from the third.
+pub struct CubicBezier<P: Coordinates> {
+    controls: [[f64; 3]; 4],
+    point: PhantomData<P>,
+}
+
+impl<P: Coordinates> CubicBezier<P> {
+    pub fn new(p0: &P, p1: &P, p2: &P, p3: &P) -> Self {
+        CubicBezier { controls: [p0.coordinates(), p1.coordinates(), p2.coordinates(), p3.coordinates()], point: PhantomData }
+    }
+}
+
+impl<P: Coordinates> Curve<P> for CubicBezier<P> {
+    fn point(&self, t: f64) -> P {
+        let s = 1.0 - t;
+        let weights = [s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t];
+        P::from_coordinates(combine(&self.controls, &weights))
+    }
+
+    fn derivative(&self, t: f64) -> P {
+        let s = 1.0 - t;
+        let [p0, p1, p2, p3] = self.controls;
+        let legs = [sub(p1, p0), sub(p2, p1), sub(p3, p2)];
+        P::from_coordinates(combine(&legs, &[3.0 * s * s, 6.0 * s * t, 3.0 * t * t]))
+    }
+}
+
+/// A B-spline, or a NURBS curve when the control points are weighted. t runs
+/// over the knots from the first to the last at which the curve is defined.
+pub struct BSpline<P: Coordinates> {
+    degree: usize,
+    knots: Vec<f64>,
+    controls: Vec<[f64; 4]>,    // homogeneous: the weighted point, then the weight
+    derivatives: Vec<[f64; 4]>, // control points of the derivative, one degree lower
+    point: PhantomData<P>,
+}
+
+impl<P: Coordinates> BSpline<P> {
+    pub fn new(degree: usize, points: &[P], knots: &[f64]) -> Result<Self, &'static str> {
+        Self::nurbs(degree, points, &vec![1.0; points.len()], knots)
+    }
+
+    /// A B-spline with knots spaced evenly between ends repeated degree + 1
+    /// times, so that it starts and ends on its first and last control points.
+    pub fn clamped(degree: usize, points: &[P]) -> Result<Self, &'static str> {
+        if degree == 0 || points.len() <= degree {
+            return Err("b-spline requires more control points than its degree");
+        }
+        let spans = points.len() - degree;
+        let knots: Vec<f64> = (0..points.len() + degree + 1)
+            .map(|k| (k.saturating_sub(degree).min(spans)) as f64 / spans as f64)
+            .collect();
+        Self::new(degree, points, &knots)
+    }
+
+    pub fn nurbs(degree: usize, points: &[P], weights: &[f64], knots: &[f64]) -> Result<Self, &'static str> {
+        let n = points.len();
+        if degree == 0 || n <= degree {
+            return Err("b-spline requires more control points than its degree");
+        }
+        if weights.len() != n || weights.iter().any(|w| *w <= 0.0) {
+            return Err("b-spline requires a positive weight for each control point");
+        }
+        if knots.len() != n + degree + 1 || knots.windows(2).any(|k| k[1] < k[0]) {
+            return Err("b-spline knots must be non-decreasing, with as many as points plus degree plus one");
+        }
+        if knots[degree] >= knots[n] {
+            return Err("b-spline knots span no interval");
+        }
+
+        let controls: Vec<[f64; 4]> = points.iter().zip(weights).map(|(p, w)| {
+            let [x, y, z] = p.coordinates();
+            [x * w, y * w, z * w, *w]
+        }).collect();
+        let derivatives = (0..n - 1).map(|i| {
+            let span = knots[i + degree + 1] - knots[i + 1];
+            let scale = if span > 0.0 { degree as f64 / span } else { 0.0 };
+            let (a, b) = (controls[i], controls[i + 1]);
+            [scale * (b[0] - a[0]), scale * (b[1] - a[1]), scale * (b[2] - a[2]), scale * (b[3] - a[3])]
+        }).collect();
+        Ok(BSpline { degree, knots: knots.to_vec(), controls, derivatives, point: PhantomData })
+    }
+
+    fn knot_parameter(&self, t: f64) -> f64 {
+        let (start, end) = (self.knots[self.degree], self.knots[self.controls.len()]);
+        start + t.clamp(0.0, 1.0) * (end - start)
+    }
+}
+
+impl<P: Coordinates> Curve<P> for BSpline<P> {
+    fn point(&self, t: f64) -> P {
+        let a = de_boor(self.degree, &self.knots, &self.controls, self.knot_parameter(t));
+        P::from_coordinates([a[0] / a[3], a[1] / a[3], a[2] / a[3]])
+    }
+
+    // the quotient rule on the homogeneous curve, then the chain rule from knots to t
+    fn derivative(&self, t: f64) -> P {
+        let u = self.knot_parameter(t);
+        let a = de_boor(self.degree, &self.knots, &self.controls, u);
+        let da = de_boor(self.degree - 1, &self.knots[1..self.knots.len() - 1], &self.derivatives, u);
+        let scale = (self.knots[self.controls.len()] - self.knots[self.degree]) / a[3];
+        P::from_coordinates([0, 1, 2].map(|c| scale * (da[c] - da[3] * a[c] / a[3])))
+    }
+}
+
+/// A natural cubic spline through a list of points, parameterised by chord
+/// length so that t is close to the fraction of arc length.
+pub struct InterpolatingSpline<P: Coordinates> {
+    params: Vec<f64>,
+    points: Vec<[f64; 3]>,
+    curvatures: Vec<[f64; 3]>, // second derivatives at each point
+    point: PhantomData<P>,
+}
+
+impl<P: Coordinates> InterpolatingSpline<P> {
+    pub fn new(points: &[P]) -> Result<Self, &'static str> {
+        let n = points.len();
+        if n < 2 {
+            return Err("spline requires at least two points");
+        }
+        let points: Vec<[f64; 3]> = points.iter().map(|p| p.coordinates()).collect();
+        let chords: Vec<f64> = points.windows(2).map(|pair| norm(sub(pair[1], pair[0]))).collect();
+        if chords.iter().any(|c| *c <= 0.0) {
+            return Err("spline points must not repeat");
+        }
+        let total: f64 = chords.iter().sum();
+        let mut params = vec![0.0];
+        for chord in &chords {
+            params.push(params.last().unwrap() + chord / total);
+        }
+        params[n - 1] = 1.0;
+
+        // thomas algorithm on the interior points, the ends being free of curvature
+        let h: Vec<f64> = params.windows(2).map(|p| p[1] - p[0]).collect();
+        let mut curvatures = vec![[0.0; 3]; n];
+        if n > 2 {
+            let mut diagonal = Vec::with_capacity(n - 2);
+            let mut rhs: Vec<[f64; 3]> = Vec::with_capacity(n - 2);
+            for i in 1..n - 1 {
+                let slope = |k: usize| scale(sub(points[k + 1], points[k]), 1.0 / h[k]);
+                let mut d = 2.0 * (h[i - 1] + h[i]);
+                let mut r = scale(sub(slope(i), slope(i - 1)), 6.0);
+                if i > 1 {
+                    let factor = h[i - 1] / diagonal[i - 2];
+                    d -= factor * h[i - 1];
+                    r = sub(r, scale(rhs[i - 2], factor));
+                }
+                diagonal.push(d);
+                rhs.push(r);
+            }
+            for i in (1..n - 1).rev() {
+                let next = if i + 1 < n - 1 { scale(curvatures[i + 1], h[i]) } else { [0.0; 3] };
+                curvatures[i] = scale(sub(rhs[i - 1], next), 1.0 / diagonal[i - 1]);
+            }
+        }
+        Ok(InterpolatingSpline { params, points, curvatures, point: PhantomData })
+    }
+
+    // the interval holding t, its width and how far along it t lies from each end
+    fn locate(&self, t: f64) -> (usize, f64, f64, f64) {
+        let t = t.clamp(0.0, 1.0);
+        let last = self.params.len() - 2;
+        let i = self.params[1..=last].iter().take_while(|p| **p < t).count();
+        let h = self.params[i + 1] - self.params[i];
+        (i, h, (self.params[i + 1] - t) / h, (t - self.params[i]) / h)
+    }
+}
+
+impl<P: Coordinates> Curve<P> for InterpolatingSpline<P> {
+    fn point(&self, t: f64) -> P {
+        let (i, h, a, b) = self.locate(t);
+        let (ma, mb) = ((a * a * a - a) * h * h / 6.0, (b * b * b - b) * h * h / 6.0);
+        P::from_coordinates(combine(
+            &[self.points[i], self.points[i + 1], self.curvatures[i], self.curvatures[i + 1]],
+            &[a, b, ma, mb],
+        ))
+    }
+
+    fn derivative(&self, t: f64) -> P {
+        let (i, h, a, b) = self.locate(t);
+        let slope = scale(sub(self.points[i + 1], self.points[i]), 1.0 / h);
+        let bend = combine(&[self.curvatures[i], self.curvatures[i + 1]], &[-(3.0 * a * a - 1.0) * h / 6.0, (3.0 * b * b - 1.0) * h / 6.0]);
+        P::from_coordinates(add(slope, bend))
+    }
+}
+
+// evaluates a b-spline in homogeneous coordinates at knot value u
+fn de_boor(degree: usize, knots: &[f64], controls: &[[f64; 4]], u: f64) -> [f64; 4] {
+    let n = controls.len();
+    let mut span = degree;
+    while span + 1 < n && knots[span + 1] <= u {
+        span += 1;
+    }
+    let mut d: Vec<[f64; 4]> = (0..=degree).map(|j| controls[j + span - degree]).collect();
+    for r in 1..=degree {
+        for j in (r..=degree).rev() {
+            let i = j + span - degree;
+            let width = knots[i + degree + 1 - r] - knots[i];
+            let alpha = if width > 0.0 { (u - knots[i]) / width } else { 0.0 };
+            d[j] = [0, 1, 2, 3].map(|c| (1.0 - alpha) * d[j - 1][c] + alpha * d[j][c]);
+        }
+    }
+    d[degree]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: [f64; 3], b: [f64; 3]) -> bool {
+        norm(sub(a, b)) < 1e-9
+    }
+
+    // a central difference of the curve's points, to check its derivative
+    fn check_derivative<P: Coordinates>(curve: &dyn Curve<P>, t: f64) {
+        let h = 1e-6;
+        let numeric = scale(sub(curve.point(t + h).coordinates(), curve.point(t - h).coordinates()), 0.5 / h);
+        assert!(norm(sub(numeric, curve.derivative(t).coordinates())) < 1e-5 * norm(numeric).max(1.0));
+    }
+
+    #[test]
+    fn test_segment_and_polyline() {
+        let segment = Segment::new(&Point3D::new(0.0, 0.0, 0.0), &Point3D::new(1.0, 2.0, 2.0));
+        assert_eq!(segment.length(), 3.0);
+        assert_eq!(segment.parameter_at(1.5), Ok(0.5));
+        assert!(close(segment.point(0.5).coordinates(), [0.5, 1.0, 1.0]));
+
+        let corners = [Point2D::new(0.0, 0.0), Point2D::new(3.0, 0.0), Point2D::new(3.0, 1.0)];
+        let polyline = Polyline::new(&corners).unwrap();
+        assert_eq!(polyline.length(), 4.0);
+        assert!(close(polyline.point(0.875).coordinates(), [3.0, 0.5, 0.0]));
+        assert!(close(polyline.derivative(0.5).coordinates(), [4.0, 0.0, 0.0]));
+        let points = polyline.discretise(&[0.0, 0.5, 1.0]).unwrap();
+        assert_eq!(points[1], Point2D::new(2.0, 0.0));
+        assert!(Polyline::new(&corners[..1]).is_err());
+    }
+
+    #[test]
+    fn test_circular_arcs() {
+        let quarter = CircularArc::new_2d(&Point2D::new(1.0, 1.0), 2.0, 0.0, PI / 2.0).unwrap();
+        assert!((quarter.length() - PI).abs() < 1e-12);
+        assert!(close(quarter.point(1.0).coordinates(), [1.0, 3.0, 0.0]));
+        check_derivative(&quarter, 0.3);
+        assert!(CircularArc::new_2d(&Point2D::new(1.0, 1.0), 2.0, 1.0, 1.0).is_err());
+
+        // a half circle in a tilted plane, through its top
+        let top = Point3D::new(0.0, 0.5f64.sqrt(), 0.5f64.sqrt());
+        let arc = CircularArc::through(&Point3D::new(1.0, 0.0, 0.0), &top, &Point3D::new(-1.0, 0.0, 0.0)).unwrap();
+        assert!(close(arc.centre().coordinates(), [0.0, 0.0, 0.0]));
+        assert!((arc.length() - PI).abs() < 1e-12);
+        assert!(close(arc.point(0.5).coordinates(), top.coordinates()));
+        check_derivative(&arc, 0.8);
+        assert!(CircularArc::through(&Point2D::new(0.0, 0.0), &Point2D::new(1.0, 1.0), &Point2D::new(2.0, 2.0)).is_err());
+    }
+
+    #[test]
+    fn test_bezier_arc_length_inverts() {
+        let bezier = CubicBezier::new(&Point2D::new(0.0, 0.0), &Point2D::new(0.0, 1.0), &Point2D::new(2.0, 1.0), &Point2D::new(2.0, 0.0));
+        assert_eq!(bezier.point(0.0), Point2D::new(0.0, 0.0));
+        assert!(close(bezier.point(0.5).coordinates(), [1.0, 0.75, 0.0]));
+        check_derivative(&bezier, 0.2);
+
+        // evenly spaced controls on a line give the line, whose length the quadrature gets exactly
+        let straight = CubicBezier::new(&Point2D::new(0.0, 0.0), &Point2D::new(1.0, 0.0), &Point2D::new(2.0, 0.0), &Point2D::new(3.0, 0.0));
+        assert!((straight.length() - 3.0).abs() < 1e-12);
+
+        let total = bezier.length();
+        for s in [0.1, 0.5, 0.9] {
+            let t = bezier.parameter_at(s * total).unwrap();
+            assert!((bezier.arc_length(t) - s * total).abs() < 1e-10);
+        }
+        // the curve is symmetric, so half its length is reached half way along
+        assert!((bezier.parameter_at(0.5 * total).unwrap() - 0.5).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_nurbs_quarter_circle() {
+        let points = [Point2D::new(1.0, 0.0), Point2D::new(1.0, 1.0), Point2D::new(0.0, 1.0)];
+        let circle = BSpline::nurbs(2, &points, &[1.0, 0.5f64.sqrt(), 1.0], &[0.0, 0.0, 0.0, 1.0, 1.0, 1.0]).unwrap();
+        for t in [0.0, 0.2, 0.5, 0.9, 1.0] {
+            assert!((norm(circle.point(t).coordinates()) - 1.0).abs() < 1e-12);
+            check_derivative(&circle, t.clamp(0.01, 0.99));
+        }
+        assert!((circle.length() - PI / 2.0).abs() < 1e-9);
+
+        // a clamped linear b-spline is the polyline through its control points
+        let linear = BSpline::clamped(1, &points).unwrap();
+        assert!(close(linear.point(0.25).coordinates(), [1.0, 0.5, 0.0]));
+        let cubic = BSpline::clamped(3, &[points[0].clone(), points[1].clone(), points[2].clone(), Point2D::new(-1.0, 0.0)]).unwrap();
+        assert!(close(cubic.point(1.0).coordinates(), [-1.0, 0.0, 0.0]));
+        check_derivative(&cubic, 0.4);
+        assert!(BSpline::new(2, &points, &[0.0, 1.0, 0.5, 1.0, 1.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn test_interpolating_spline() {
+        let data: Vec<Point2D> = (0..6).map(|n| Point2D::new(n as f64, (n as f64 * 0.8).sin())).collect();
+        let spline = InterpolatingSpline::new(&data).unwrap();
+        for (n, point) in data.iter().enumerate() {
+            assert!(close(spline.point(spline.params[n]).coordinates(), point.coordinates()));
+        }
+        check_derivative(&spline, 0.37);
+
+        // evenly spaced points on a line stay on it, at the same speed
+        let line: Vec<Point3D> = (0..4).map(|n| Point3D::new(n as f64, 2.0 * n as f64, 0.0)).collect();
+        let straight = InterpolatingSpline::new(&line).unwrap();
+        assert!(close(straight.point(0.5).coordinates(), [1.5, 3.0, 0.0]));
+        assert!((straight.length() - 3.0 * 5f64.sqrt()).abs() < 1e-12);
+        assert!(InterpolatingSpline::new(&[line[0].clone(), line[0].clone()]).is_err());
+    }
+}
@@ -22,7 +22,7 @@ impl<'a> Line<'a, Point3D> {
 
 impl<'a, P: Point> Dimensioned for Line<'a, P> {
     fn is_2d(&self) -> bool {
-        if self.dimensions() == 2 { true } else { false }
+        self.dimensions() == 2
     }
     
     fn dimensions(&self) -> usize {
@@ -56,11 +56,15 @@ impl<'a, P: Point> LineCollection<'a, P> {
     }
 
     pub fn add_line(&mut self, l: Line<'a, P>) -> Result<(), &'static str>{
-        if l.dimensions() == self.dimensions() {
+        if self.is_empty() || l.dimensions() == self.dimensions() {
             self.lines.push(l);
         } else {
             return Err("can't add line of differing dimensions to what was already stored in the line collection");
         }
         Ok(())
     }
+
+    pub fn lines(&self) -> &Vec<Line<'a, P>> {
+        &self.lines
+    }
 }
\ No newline at end of file
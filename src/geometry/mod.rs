@@ -1,5 +1,7 @@
+pub mod curve;
 pub mod line;
 pub mod points;
 pub mod prelude;
+pub mod vector;
 pub mod vertex;
 pub mod vertices;
@@ -4,4 +4,5 @@
 pub use crate::geometry::points::{Point, Point2D, Point3D, Dimensioned, Dimensions};
 pub use crate::geometry::vertex::Vertex;
 pub use crate::geometry::vertices::{Direction, Vertices};
-pub use crate::geometry::line::{Line, LineCollection};
\ No newline at end of file
+pub use crate::geometry::line::{Line, LineCollection};
+pub use crate::geometry::curve::{BSpline, CircularArc, Coordinates, CubicBezier, Curve, InterpolatingSpline, Polyline, Segment};
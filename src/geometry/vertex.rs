@@ -8,6 +8,10 @@ pub struct Vertex<P: Point> {
 }
 
 impl<P: Point> Vertex<P> {
+    pub fn new(id: usize, coords: P) -> Vertex<P> {
+        Vertex { id, coords }
+    }
+
     pub fn get_id(&self) -> usize {
         self.id
     }
@@ -23,6 +27,10 @@ impl<P: Point> Vertex<P> {
     pub fn get_z(&self) -> f64 {
         self.coords.z()
     }
+
+    pub fn set_coords(&mut self, coords: P) {
+        self.coords = coords;
+    }
 }
 
 impl<P: Point> Dimensioned for Vertex<P> {
@@ -37,13 +45,13 @@ impl<P: Point> Dimensioned for Vertex<P> {
 
 impl Vertex<Point2D> {
     pub fn new_2d(id: usize, x: f64, y: f64) -> Vertex<Point2D> {
-        Vertex { id: id, coords: Point2D::new(x, y) }
+        Vertex { id, coords: Point2D::new(x, y) }
     }
 }
 
 impl Vertex<Point3D> {
     pub fn new_3d(id: usize, x: f64, y: f64, z: f64) -> Vertex<Point3D> {
-        Vertex { id: id, coords: Point3D::new(x, y, z) }
+        Vertex { id, coords: Point3D::new(x, y, z) }
     }
 }
 
@@ -1,11 +1,14 @@
 #![allow(dead_code)]
 
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{Write, BufWriter};
+use std::marker::PhantomData;
 
+use super::curve::Coordinates;
 use super::points::{Dimensioned, Point, Dimensions, Point2D, Point3D};
 use super::vertex::Vertex;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     North, // +j
     South, // -j
@@ -15,9 +18,13 @@ pub enum Direction {
     Down,  // -k
 }
 
+// coordinates are stored flat and densely in id order, x and y (and z in 3D)
+// for each vertex in turn, so lookups by id or (i, j, k) don't need to search
+// and the whole grid can be borrowed as one buffer
 pub struct Vertices<P: Point> {
-    vertices: Vec<Vertex<P>>,
+    coords: Vec<f64>,
     dimensions: Dimensions,
+    point: PhantomData<P>,
 }
 
 impl<P: Point> Dimensioned for Vertices<P> {
@@ -33,10 +40,10 @@ impl<P: Point> Dimensioned for Vertices<P> {
 // methods for groups of 2D vertices
 impl Vertices<Point2D> {
     pub fn new_2d(nx: usize, ny: usize) -> Vertices<Point2D> {
-        Vertices { vertices: Vec::new(), dimensions: Dimensions::Two { nx, ny } }
+        Vertices { coords: Vec::with_capacity(2 * nx * ny), dimensions: Dimensions::Two { nx, ny }, point: PhantomData }
     }
 
-    pub fn get_adjacent_vertex(&self, vertex_id: usize, direction: Direction) -> Option<&Vertex<Point2D>> {
+    pub fn get_adjacent_vertex(&self, vertex_id: usize, direction: Direction) -> Option<Vertex<Point2D>> {
         let (i, j) = self.vertex_id_to_ij(vertex_id)?;
         let (nx, ny, _) = self.dimensions.as_tuple();
 
@@ -73,7 +80,8 @@ impl Vertices<Point2D> {
     }
 
     pub fn vertex_ij_to_id(&self, i: usize, j: usize) -> Option<usize> {
-        let (nx, _, _) = self.dimensions.as_tuple();
+        let (nx, ny, _) = self.dimensions.as_tuple();
+        if i >= nx || j >= ny { return None; } // vertex ij is out of bounds
         let vertex_id = (j * nx) + i;
         if self.vertex_exists(vertex_id) {
             Some(vertex_id)
@@ -82,17 +90,20 @@ impl Vertices<Point2D> {
         }
     }
 
+    pub fn get_vertex_ij(&self, i: usize, j: usize) -> Option<Vertex<Point2D>> {
+        self.get_vertex(self.vertex_ij_to_id(i, j)?)
+    }
+
     pub fn populate_uniform(&mut self) {
         let (nx, ny, _) = self.dimensions.as_tuple();
         let dx = 1.0 / (nx - 1) as f64;
         let dy = 1.0 / (ny - 1) as f64;
         
+        // ids run in order by construction, so the coordinates are stored directly
+        self.coords.clear();
         for j in 0..ny {
             for i in 0..nx {
-                let id = i + j * nx;
-                let x = i as f64 * dx;
-                let y = j as f64 * dy;
-                self.add_vertex(Vertex::new_2d(id, x, y));
+                self.coords.extend([i as f64 * dx, j as f64 * dy]);
             }
         }
     }
@@ -101,15 +112,34 @@ impl Vertices<Point2D> {
         let (nx, ny, _) = self.dimensions.as_tuple();
         (nx, ny)
     }
+
+    /// Reads back a file written by `export_csv`. Without a shape, nx and ny
+    /// are taken as the factors of the vertex count whose grid lines run
+    /// smoothest, which finds the true shape unless the grid is folded.
+    pub fn import_csv(filename: &str, shape: Option<(usize, usize)>) -> Result<Self, &'static str> {
+        let points = read_csv(filename, 2)?;
+        let dims = match shape {
+            Some((nx, ny)) => vec![nx, ny],
+            None => infer_shape(&points, 2)?,
+        };
+        if dims.iter().product::<usize>() != points.len() {
+            return Err("vertex count does not match the grid shape");
+        }
+        let mut vertices = Vertices::new_2d(dims[0], dims[1]);
+        for (id, [x, y, _]) in points.into_iter().enumerate() {
+            vertices.add_vertex(Vertex::new_2d(id, x, y))?;
+        }
+        Ok(vertices)
+    }
 }
 
 // methods for groups of 3D vertices
 impl Vertices<Point3D> {
     pub fn new_3d(nx: usize, ny: usize, nz: usize) -> Vertices<Point3D> {
-        Vertices { vertices: Vec::new(), dimensions: Dimensions::Three { nx, ny, nz } }
+        Vertices { coords: Vec::with_capacity(3 * nx * ny * nz), dimensions: Dimensions::Three { nx, ny, nz }, point: PhantomData }
     } 
 
-    pub fn get_adjacent_vertex(&self, vertex_id: usize, direction: Direction) -> Option<&Vertex<Point3D>> {
+    pub fn get_adjacent_vertex(&self, vertex_id: usize, direction: Direction) -> Option<Vertex<Point3D>> {
         let (i, j, k) = self.vertex_id_to_ijk(vertex_id)?;
         let (nx, ny, nz) = self.dimensions.as_tuple();
 
@@ -160,6 +190,10 @@ impl Vertices<Point3D> {
         if self.vertex_exists(vertex_id) { Some(vertex_id) } else { None }
     }
 
+    pub fn get_vertex_ijk(&self, i: usize, j: usize, k: usize) -> Option<Vertex<Point3D>> {
+        self.get_vertex(self.vertex_ijk_to_id(i, j, k)?)
+    }
+
     pub fn populate_uniform(&mut self) {
         let (nx, ny, nz) = self.dimensions.as_tuple();
         let nz = nz.unwrap();
@@ -167,43 +201,96 @@ impl Vertices<Point3D> {
         let dy = 1.0 / (ny - 1) as f64;
         let dz = 1.0 / (nz - 1) as f64;
         
+        self.coords.clear();
         for k in 0..nz {
             for j in 0..ny {
                 for i in 0..nx {
-                    let id = i + j * nx + k * nx * ny;
-                    let x = i as f64 * dx;
-                    let y = j as f64 * dy;
-                    let z = k as f64 * dz;
-                    self.add_vertex(Vertex::new_3d(id, x, y, z));
+                    self.coords.extend([i as f64 * dx, j as f64 * dy, k as f64 * dz]);
                 }
             }
         }
     }
+
+    pub fn nx_ny_nz(&self) -> (usize, usize, usize) {
+        let (nx, ny, nz) = self.dimensions.as_tuple();
+        (nx, ny, nz.unwrap())
+    }
+
+    /// Reads back a file written by `export_csv`, inferring nx, ny and nz as
+    /// in 2D when no shape is given.
+    pub fn import_csv(filename: &str, shape: Option<(usize, usize, usize)>) -> Result<Self, &'static str> {
+        let points = read_csv(filename, 3)?;
+        let dims = match shape {
+            Some((nx, ny, nz)) => vec![nx, ny, nz],
+            None => infer_shape(&points, 3)?,
+        };
+        if dims.iter().product::<usize>() != points.len() {
+            return Err("vertex count does not match the grid shape");
+        }
+        let mut vertices = Vertices::new_3d(dims[0], dims[1], dims[2]);
+        for (id, [x, y, z]) in points.into_iter().enumerate() {
+            vertices.add_vertex(Vertex::new_3d(id, x, y, z))?;
+        }
+        Ok(vertices)
+    }
 }
 
 // shared methods that apply between both 2D and 3D lists of vertices
 impl<P: Point> Vertices<P> {
-    pub fn add_vertex(&mut self, vertex: Vertex<P>) {
-        if vertex.dimensions() ==  self.dimensions() {
-            self.vertices.push(vertex);
+    /// Stores the vertex in the slot given by its id. Vertices must be added in
+    /// id order, i fastest, though an existing vertex may be replaced.
+    pub fn add_vertex(&mut self, vertex: Vertex<P>) -> Result<(), &'static str> {
+        if vertex.dimensions() != self.dimensions() {
+            return Err("vertex dimensions do not match the grid");
+        }
+        let id = vertex.get_id();
+        if id >= self.dimensions.total_points() {
+            return Err("vertex id is outside the grid");
         }
+        let n = self.dimensions();
+        let xyz = [vertex.get_x(), vertex.get_y(), if n == 3 { vertex.get_z() } else { 0.0 }];
+        if id < self.len() {
+            self.coords[id * n..(id + 1) * n].copy_from_slice(&xyz[..n]);
+        } else if id == self.len() {
+            self.coords.extend_from_slice(&xyz[..n]);
+        } else {
+            return Err("vertices must be added in id order");
+        }
+        Ok(())
     }
 
-    pub fn get_vertex(&self, vertex_id: usize) -> Option<&Vertex<P>> {
-        self.vertices.iter()
-            .find(|&v| v.get_id() == vertex_id)
+    /// The number of vertices added so far.
+    pub fn len(&self) -> usize {
+        self.coords.len() / self.dimensions()
     }
-    
-    pub fn vertex_exists(&self, vertex_id: usize) -> bool {
-        if self.get_vertex(vertex_id).is_some() {
-            true
-        } else {
-            false
+
+    pub fn is_empty(&self) -> bool {
+        self.coords.is_empty()
+    }
+
+    /// The number of vertices along each axis, i first.
+    pub fn shape(&self) -> Vec<usize> {
+        match self.dimensions.as_tuple() {
+            (nx, ny, None) => vec![nx, ny],
+            (nx, ny, Some(nz)) => vec![nx, ny, nz],
         }
     }
 
-    pub fn vertices(&self) -> &Vec<Vertex<P>> {
-        &self.vertices
+    pub fn vertex_exists(&self, vertex_id: usize) -> bool {
+        vertex_id < self.len()
+    }
+
+    /// Coordinates in one buffer in id order, x and y (and z in 3D) for each
+    /// vertex in turn.
+    pub fn coordinates(&self) -> &[f64] {
+        &self.coords
+    }
+
+    /// The coordinates of one vertex, z being zero in 2D.
+    pub fn point(&self, vertex_id: usize) -> Option<[f64; 3]> {
+        let n = self.dimensions();
+        let c = self.coords.get(vertex_id * n..(vertex_id + 1) * n)?;
+        Some([c[0], c[1], if n == 3 { c[2] } else { 0.0 }])
     }
 
     pub fn export_csv(&self, filename: &str) -> Result<(), &'static str> {
@@ -213,24 +300,11 @@ impl<P: Point> Vertices<P> {
         writeln!(writer, "id,x,y{}", if self.is_2d() { "" } else { ",z" })
             .map_err(|_| "failed to write header")?;
 
-        for vertex in &self.vertices {
+        for (id, c) in self.coords.chunks(self.dimensions()).enumerate() {
             if self.is_2d() {
-                writeln!(
-                    writer,
-                    "{},{},{}",
-                    vertex.get_id(),
-                    vertex.get_x(),
-                    vertex.get_y(),
-                )
+                writeln!(writer, "{},{},{}", id, c[0], c[1])
             } else {
-                writeln!(
-                    writer,
-                    "{},{},{},{}",
-                    vertex.get_id(),
-                    vertex.get_x(),
-                    vertex.get_y(),
-                    vertex.get_z(),
-                )
+                writeln!(writer, "{},{},{},{}", id, c[0], c[1], c[2])
             }.map_err(|_| "failed to write vertex data")?;
         }
 
@@ -238,9 +312,121 @@ impl<P: Point> Vertices<P> {
     }
 }
 
+// methods that build points back out of the stored coordinates
+impl<P: Coordinates> Vertices<P> {
+    pub fn get_vertex(&self, vertex_id: usize) -> Option<Vertex<P>> {
+        Some(Vertex::new(vertex_id, P::from_coordinates(self.point(vertex_id)?)))
+    }
+
+    /// Each vertex in id order, built from the stored coordinates.
+    pub fn vertices(&self) -> impl Iterator<Item = Vertex<P>> + '_ {
+        (0..self.len()).filter_map(|id| self.get_vertex(id))
+    }
+
+    /// Moves an existing vertex.
+    pub fn set_coords(&mut self, vertex_id: usize, coords: P) -> Result<(), &'static str> {
+        if !self.vertex_exists(vertex_id) {
+            return Err("vertex does not exist");
+        }
+        self.add_vertex(Vertex::new(vertex_id, coords))
+    }
+}
+
+// the points of an `export_csv` file in id order, z being zero in 2D; rows may
+// come in any order but every id from zero up must appear exactly once
+fn read_csv(filename: &str, dimensions: usize) -> Result<Vec<[f64; 3]>, &'static str> {
+    let contents = fs::read_to_string(filename).map_err(|_| "failed to read file")?;
+    let mut lines = contents.lines().filter(|l| !l.trim().is_empty());
+    match (lines.next().map(str::trim), dimensions) {
+        (Some("id,x,y"), 2) | (Some("id,x,y,z"), 3) => {}
+        (Some("id,x,y,z"), _) => return Err("csv has z coordinates, so must be read as 3D"),
+        (Some("id,x,y"), _) => return Err("csv has no z coordinates, so must be read as 2D"),
+        _ => return Err("csv header must be id,x,y or id,x,y,z"),
+    }
+
+    let mut rows = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != dimensions + 1 {
+            return Err("csv row has the wrong number of columns");
+        }
+        let id: usize = fields[0].parse().map_err(|_| "csv vertex id is not a whole number")?;
+        let mut point = [0.0; 3];
+        for (c, field) in fields[1..].iter().enumerate() {
+            point[c] = field.parse().map_err(|_| "csv coordinate is not a number")?;
+        }
+        rows.push((id, point));
+    }
+    if rows.is_empty() {
+        return Err("csv has no vertices");
+    }
+
+    rows.sort_by_key(|(id, _)| *id);
+    for (n, (id, _)) in rows.iter().enumerate() {
+        if *id < n {
+            return Err("csv has a duplicate vertex id");
+        }
+        if *id > n {
+            return Err("csv is missing a vertex id");
+        }
+    }
+    Ok(rows.into_iter().map(|(_, point)| point).collect())
+}
+
+// the shape, of at least two vertices along each axis, whose grid lines bend least
+fn infer_shape(points: &[[f64; 3]], dimensions: usize) -> Result<Vec<usize>, &'static str> {
+    let total = points.len();
+    let factors: Vec<usize> = (2..=total / 2).filter(|n| total.is_multiple_of(*n)).collect();
+    let shapes: Vec<Vec<usize>> = if dimensions == 2 {
+        factors.iter().map(|nx| vec![*nx, total / nx]).collect()
+    } else {
+        factors.iter()
+            .flat_map(|nx| factors.iter().map(move |ny| (*nx, *ny)))
+            .filter(|(nx, ny)| total.is_multiple_of(nx * ny) && total / (nx * ny) >= 2)
+            .map(|(nx, ny)| vec![nx, ny, total / (nx * ny)])
+            .collect()
+    };
+
+    let mut scored: Vec<(f64, Vec<usize>)> = shapes.into_iter().map(|dims| (bending(points, &dims), dims)).collect();
+    scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+    match scored.as_slice() {
+        [] => Err("vertex count can't form a grid, give its shape"),
+        [(best, _), (next, _), ..] if next - best <= 1e-9 => {
+            Err("grid shape is ambiguous, give its shape")
+        }
+        [(_, dims), ..] => Ok(dims.clone()),
+    }
+}
+
+// the mean turn of the grid lines at each vertex, from zero where a line runs
+// straight on to one where it doubles back, as lines through a wrong shape do
+// where they jump from the end of one true grid line to the start of the next
+fn bending(points: &[[f64; 3]], dims: &[usize]) -> f64 {
+    let length = |v: [f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    let (mut total, mut count) = (0.0, 0);
+    let mut stride = 1;
+    for n in dims {
+        for id in 0..points.len() {
+            let i = (id / stride) % n;
+            if i == 0 || i + 1 == *n {
+                continue;
+            }
+            let (a, b, c) = (points[id - stride], points[id], points[id + stride]);
+            let span = length([b[0] - a[0], b[1] - a[1], b[2] - a[2]]) + length([c[0] - b[0], c[1] - b[1], c[2] - b[2]]);
+            if span > 0.0 {
+                total += length([a[0] - 2.0 * b[0] + c[0], a[1] - 2.0 * b[1] + c[1], a[2] - 2.0 * b[2] + c[2]]) / span;
+            }
+            count += 1;
+        }
+        stride *= n;
+    }
+    if count == 0 { 0.0 } else { total / count as f64 }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
+    use std::time::Instant;
     use super::*;
 
     #[test]
@@ -255,11 +441,11 @@ mod tests {
     fn test_adding_vertices() {
         let mut vertices_2d = Vertices::new_2d(10, 10);
         let mut vertices_3d = Vertices::new_3d(10, 10, 10);
-        vertices_2d.add_vertex(Vertex::new_2d(0, 1.0, 2.0));
-        vertices_3d.add_vertex(Vertex::new_3d(0, 1.0, 2.0, 3.0));
+        vertices_2d.add_vertex(Vertex::new_2d(0, 1.0, 2.0)).unwrap();
+        vertices_3d.add_vertex(Vertex::new_3d(0, 1.0, 2.0, 3.0)).unwrap();
 
-        assert_eq!(vertices_2d.vertices.len(), 1);
-        assert_eq!(vertices_3d.vertices.len(), 1);
+        assert_eq!(vertices_2d.len(), 1);
+        assert_eq!(vertices_3d.len(), 1);
 
         let v2d = &vertices_2d.get_vertex(0).unwrap();
         assert_eq!(v2d.get_id(), 0);
@@ -276,8 +462,8 @@ mod tests {
     #[test]
     fn test_export_csv() {
         let mut vertices_2d = Vertices::new_2d(2, 2);
-        vertices_2d.add_vertex(Vertex::new_2d(0, 1.0, 2.0));
-        vertices_2d.add_vertex(Vertex::new_2d(1, 3.0, 4.0));
+        vertices_2d.add_vertex(Vertex::new_2d(0, 1.0, 2.0)).unwrap();
+        vertices_2d.add_vertex(Vertex::new_2d(1, 3.0, 4.0)).unwrap();
         
         let filename = "test_vertices_2d.csv";
         assert!(vertices_2d.export_csv(filename).is_ok());
@@ -292,16 +478,156 @@ mod tests {
         fs::remove_file(filename).expect("failed to clean up test file");
     }
 
+    #[test]
+    fn test_import_csv_round_trip() {
+        let mut vertices = Vertices::new_2d(4, 3);
+        vertices.populate_uniform();
+        vertices.export_csv("test_import_2d.csv").unwrap();
+        let read = Vertices::<Point2D>::import_csv("test_import_2d.csv", None).unwrap();
+        assert_eq!(read.nx_ny(), (4, 3));
+        assert_eq!(read.coordinates(), vertices.coordinates());
+        assert!(Vertices::<Point3D>::import_csv("test_import_2d.csv", None).is_err());
+        fs::remove_file("test_import_2d.csv").expect("failed to clean up test file");
+
+        let mut vertices = Vertices::new_3d(2, 3, 4);
+        vertices.populate_uniform();
+        vertices.export_csv("test_import_3d.csv").unwrap();
+        let read = Vertices::<Point3D>::import_csv("test_import_3d.csv", None).unwrap();
+        assert_eq!(read.nx_ny_nz(), (2, 3, 4));
+        assert_eq!(read.coordinates(), vertices.coordinates());
+        let given = Vertices::<Point3D>::import_csv("test_import_3d.csv", Some((2, 3, 4))).unwrap();
+        assert_eq!(given.coordinates(), vertices.coordinates());
+        assert_eq!(
+            Vertices::<Point3D>::import_csv("test_import_3d.csv", Some((2, 3, 3))).err(),
+            Some("vertex count does not match the grid shape")
+        );
+        fs::remove_file("test_import_3d.csv").expect("failed to clean up test file");
+    }
+
+    #[test]
+    fn test_import_csv_validates_ids() {
+        let read = |name: &str, contents: &str| {
+            fs::write(name, contents).unwrap();
+            let result = Vertices::<Point2D>::import_csv(name, Some((2, 2))).map(|v| v.coordinates().to_vec());
+            fs::remove_file(name).expect("failed to clean up test file");
+            result
+        };
+        // rows may come in any order
+        assert_eq!(read("test_shuffled.csv", "id,x,y\n3,1,1\n0,0,0\n2,0,1\n1,1,0\n"), Ok(vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0]));
+        assert_eq!(read("test_duplicate.csv", "id,x,y\n0,0,0\n1,1,0\n1,0,1\n3,1,1\n"), Err("csv has a duplicate vertex id"));
+        assert_eq!(read("test_missing.csv", "id,x,y\n0,0,0\n1,1,0\n2,0,1\n4,1,1\n"), Err("csv is missing a vertex id"));
+        assert_eq!(read("test_header.csv", "x,y\n0,0\n"), Err("csv header must be id,x,y or id,x,y,z"));
+        assert_eq!(read("test_columns.csv", "id,x,y\n0,0\n"), Err("csv row has the wrong number of columns"));
+    }
+
+    #[test]
+    fn test_infer_shape() {
+        // a stretched grid has its shape found from its grid lines alone
+        let points: Vec<[f64; 3]> = (0..15).map(|n| [(n % 5) as f64 * 0.1, (n / 5) as f64 * 3.0, 0.0]).collect();
+        assert_eq!(infer_shape(&points, 2), Ok(vec![5, 3]));
+        assert_eq!(infer_shape(&points[..7], 2), Err("vertex count can't form a grid, give its shape"));
+
+        // as does a curved one
+        let annulus: Vec<[f64; 3]> = (0..24).map(|n| {
+            let (theta, r) = ((n % 6) as f64 * 0.3, 1.0 + (n / 6) as f64 * 0.2);
+            [r * theta.cos(), r * theta.sin(), 0.0]
+        }).collect();
+        assert_eq!(infer_shape(&annulus, 2), Ok(vec![6, 4]));
+
+        // a square grid of equal spacing reads the same either way round
+        let square: Vec<[f64; 3]> = (0..4).map(|n| [(n % 2) as f64, (n / 2) as f64, 0.0]).collect();
+        assert_eq!(infer_shape(&square, 2), Ok(vec![2, 2]));
+    }
+
     #[test]
     fn test_populate_uniform() {
         let mut vertices_2d = Vertices::new_2d(3, 2);
         vertices_2d.populate_uniform();
         vertices_2d.export_csv("2d.csv").expect("erm");
-        assert_eq!(vertices_2d.vertices.len(), 6);
+        assert_eq!(vertices_2d.len(), 6);
 
         let mut vertices_3d = Vertices::new_3d(2, 2, 2);
         vertices_3d.populate_uniform();
         vertices_3d.export_csv("3d.csv").expect("erm");
-        assert_eq!(vertices_3d.vertices.len(), 8);
+        assert_eq!(vertices_3d.len(), 8);
+    }
+
+    #[test]
+    fn test_indexed_lookup() {
+        let mut vertices = Vertices::new_3d(4, 3, 2);
+        vertices.populate_uniform();
+
+        let vertex = vertices.get_vertex_ijk(3, 1, 1).unwrap();
+        assert_eq!(vertex.get_id(), 3 + 4 + 12);
+        assert_eq!((vertex.get_x(), vertex.get_y(), vertex.get_z()), (1.0, 0.5, 1.0));
+        assert!(vertices.get_vertex_ijk(4, 0, 0).is_none());
+        assert!(vertices.get_vertex(24).is_none());
+
+        let mut vertices = Vertices::new_2d(3, 2);
+        vertices.populate_uniform();
+        assert!(vertices.get_vertex_ij(3, 0).is_none());
+        assert_eq!(vertices.get_adjacent_vertex(2, Direction::North).unwrap().get_id(), 5);
+        assert_eq!(vertices.coordinates()[8..10], [0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_out_of_order_vertices_are_rejected() {
+        let mut vertices = Vertices::new_2d(2, 2);
+        assert_eq!(vertices.add_vertex(Vertex::new_2d(1, 1.0, 0.0)).unwrap_err(), "vertices must be added in id order");
+        assert!(vertices.is_empty());
+
+        vertices.add_vertex(Vertex::new_2d(0, 0.0, 0.0)).unwrap();
+        vertices.add_vertex(Vertex::new_2d(0, 0.5, 0.5)).unwrap();
+        assert_eq!(vertices.len(), 1);
+        assert_eq!(vertices.get_vertex(0).unwrap().get_x(), 0.5);
+
+        assert!(vertices.add_vertex(Vertex::new_2d(4, 1.0, 1.0)).is_err());
+    }
+
+    #[test]
+    fn test_set_coords_moves_a_vertex() {
+        let mut vertices = Vertices::new_3d(2, 2, 2);
+        vertices.populate_uniform();
+        vertices.set_coords(7, Point3D::new(2.0, 3.0, 4.0)).unwrap();
+        assert_eq!(vertices.point(7), Some([2.0, 3.0, 4.0]));
+        assert_eq!(vertices.coordinates()[21..], [2.0, 3.0, 4.0]);
+        assert!(vertices.set_coords(8, Point3D::new(0.0, 0.0, 0.0)).is_err());
+    }
+
+    // the quickest of a few runs, so one slow run on a busy machine doesn't count
+    fn quickest(mut run: impl FnMut() -> f64) -> (f64, f64) {
+        let mut best = (f64::MAX, 0.0);
+        for _ in 0..3 {
+            let start = Instant::now();
+            let result = run();
+            best = (best.0.min(start.elapsed().as_secs_f64()), result);
+        }
+        (best.0.max(1e-9), best.1)
+    }
+
+    #[test]
+    fn test_indexed_lookup_beats_a_scan() {
+        let mut vertices = Vertices::new_2d(300, 300);
+        vertices.populate_uniform();
+        let ids: Vec<usize> = (0..200).map(|k| (k * 7919 * 131) % 90_000).collect();
+
+        // finding vertices by scanning for their id, as the first storage did
+        let (scan, scanned) = quickest(|| ids.iter().map(|id| vertices.vertices().find(|v| v.get_id() == *id).unwrap().get_x()).sum());
+        let (indexed, found) = quickest(|| ids.iter().map(|id| vertices.get_vertex(*id).unwrap().get_x()).sum());
+        assert_eq!(scanned, found);
+        assert!(scan / indexed > 10.0, "indexed lookups were only {:.1}x faster", scan / indexed);
+
+        // a sweep over every vertex's neighbours costs about as much per lookup
+        let (sweep, neighbours) = quickest(|| {
+            (0..vertices.len())
+                .map(|id| {
+                    vertices.get_adjacent_vertex(id, Direction::East).is_some() as usize as f64
+                        + vertices.get_adjacent_vertex(id, Direction::North).is_some() as usize as f64
+                })
+                .sum()
+        });
+        assert_eq!(neighbours, 2.0 * 299.0 * 300.0);
+        let per_lookup = (scan / ids.len() as f64) / (sweep / neighbours);
+        assert!(per_lookup > 10.0, "adjacent lookups were only {:.1}x faster than a scan", per_lookup);
     }
 }
\ No newline at end of file
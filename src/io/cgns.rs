@@ -0,0 +1,278 @@
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::geometry::prelude::*;
+#[cfg(feature = "cgns")]
+use crate::io::hdf5::write_hdf5;
+use crate::io::{join, Grid};
+use crate::mesh::block::Blocks;
+use crate::mesh::boundary::{BoundaryCondition, BoundaryType};
+
+// the library version the tree's layout follows
+const CGNS_VERSION: f32 = 4.2;
+
+const COORDINATE_NAMES: [&str; 3] = ["CoordinateX", "CoordinateY", "CoordinateZ"];
+
+/// The data held by a node, typed as in a CGNS file. Arrays are stored in
+/// Fortran order with their shape, so a range is its begin then its end.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CgnsData {
+    None,
+    Char(String),
+    Integer { shape: Vec<usize>, values: Vec<i32> },
+    Real32(Vec<f32>),
+    Real64(Vec<f64>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CgnsNode {
+    pub name: String,
+    pub label: String,
+    pub data: CgnsData,
+    pub children: Vec<CgnsNode>,
+}
+
+impl CgnsNode {
+    pub fn new(name: &str, label: &str, data: CgnsData) -> Self {
+        CgnsNode { name: name.to_string(), label: label.to_string(), data, children: Vec::new() }
+    }
+
+    pub fn child(mut self, child: CgnsNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Finds a node below this one by its path of names, such as `Base/Zone_0/ZoneBC`.
+    pub fn find(&self, path: &str) -> Option<&CgnsNode> {
+        path.split('/').try_fold(self, |node, name| node.children.iter().find(|c| c.name == name))
+    }
+
+    /// Writes the node and everything below it, one node per line indented by depth.
+    pub fn write_listing(&self, writer: &mut impl Write, depth: usize) -> std::io::Result<()> {
+        let indent = "  ".repeat(depth);
+        match &self.data {
+            CgnsData::None => writeln!(writer, "{}{} <{}> MT", indent, self.name, self.label)?,
+            CgnsData::Char(text) => writeln!(writer, "{}{} <{}> C1 \"{}\"", indent, self.name, self.label, text)?,
+            CgnsData::Integer { shape, values } => {
+                writeln!(writer, "{}{} <{}> I4 {:?} {}", indent, self.name, self.label, shape, join(values))?
+            }
+            CgnsData::Real32(values) => writeln!(writer, "{}{} <{}> R4 [{}] {}", indent, self.name, self.label, values.len(), join(values))?,
+            CgnsData::Real64(values) => writeln!(writer, "{}{} <{}> R8 [{}] {}", indent, self.name, self.label, values.len(), join(values))?,
+        }
+        for child in &self.children {
+            child.write_listing(writer, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The SIDS name for a boundary condition.
+pub fn bc_type(condition: BoundaryCondition) -> &'static str {
+    match condition {
+        BoundaryCondition::Wall => "BCWall",
+        BoundaryCondition::Inflow => "BCInflow",
+        BoundaryCondition::Outflow => "BCOutflow",
+        BoundaryCondition::Farfield => "BCFarfield",
+        BoundaryCondition::Symmetry => "BCSymmetryPlane",
+    }
+}
+
+fn zone_name(id: usize) -> String {
+    format!("Zone_{}", id)
+}
+
+// cgns indices are one based and a range is stored as its begin then its end
+fn point_range(name: &str, range: &(Vec<usize>, Vec<usize>)) -> CgnsNode {
+    let values = range.0.iter().chain(&range.1).map(|i| *i as i32 + 1).collect();
+    CgnsNode::new(name, "IndexRange_t", CgnsData::Integer { shape: vec![range.0.len(), 2], values })
+}
+
+impl<P: Point> Blocks<P> {
+    /// The blocks as a CGNS tree with a single base: one structured Zone_t per
+    /// block with its GridCoordinates, a ZoneBC patch for each face given a
+    /// condition and a GridConnectivity1to1 for each face matched by another
+    /// block. Names, labels and data types follow the SIDS, so a file backend
+    /// only has to store the tree node for node.
+    pub fn cgns_tree(&self) -> Result<CgnsNode, &'static str> {
+        if self.is_empty() {
+            return Err("no blocks to write");
+        }
+        let n = self.blocks()[0].vertices.shape().len();
+        let interfaces = self.interfaces();
+
+        let mut base = CgnsNode::new("Base", "CGNSBase_t", CgnsData::Integer { shape: vec![2], values: vec![n as i32; 2] });
+        for block in self.blocks() {
+            let grid = Grid::from_vertices(&block.vertices)?;
+
+            // vertex counts, then cell counts, then the unused boundary vertex counts
+            let sizes = grid.dims.iter().map(|d| *d as i32)
+                .chain(grid.dims.iter().map(|d| *d as i32 - 1))
+                .chain(std::iter::repeat_n(0, n))
+                .collect();
+            let mut zone = CgnsNode::new(&zone_name(block.id), "Zone_t", CgnsData::Integer { shape: vec![n, 3], values: sizes })
+                .child(CgnsNode::new("ZoneType", "ZoneType_t", CgnsData::Char("Structured".to_string())));
+
+            let mut coordinates = CgnsNode::new("GridCoordinates", "GridCoordinates_t", CgnsData::None);
+            for (name, values) in COORDINATE_NAMES.iter().zip(grid.coords) {
+                coordinates = coordinates.child(CgnsNode::new(name, "DataArray_t", CgnsData::Real64(values)));
+            }
+            zone = zone.child(coordinates);
+
+            if !block.conditions.is_empty() {
+                let mut zone_bc = CgnsNode::new("ZoneBC", "ZoneBC_t", CgnsData::None);
+                for face in BoundaryType::faces(n) {
+                    let Some(condition) = block.condition(*face) else { continue };
+                    zone_bc = zone_bc.child(
+                        CgnsNode::new(face.name(), "BC_t", CgnsData::Char(bc_type(condition).to_string()))
+                            .child(CgnsNode::new("GridLocation", "GridLocation_t", CgnsData::Char("Vertex".to_string())))
+                            .child(point_range("PointRange", &face.range(&grid.dims))),
+                    );
+                }
+                zone = zone.child(zone_bc);
+            }
+
+            let matched: Vec<_> = interfaces.iter().filter(|i| i.block == block.id).collect();
+            if !matched.is_empty() {
+                let mut connectivity = CgnsNode::new("ZoneGridConnectivity", "ZoneGridConnectivity_t", CgnsData::None);
+                for (m, interface) in matched.iter().enumerate() {
+                    // sibling names are unique, so later interfaces of a face are numbered
+                    let name = match matched[..m].iter().filter(|i| i.face == interface.face).count() {
+                        0 => interface.face.name().to_string(),
+                        k => format!("{}_{}", interface.face.name(), k + 1),
+                    };
+                    connectivity = connectivity.child(
+                        CgnsNode::new(&name, "GridConnectivity1to1_t", CgnsData::Char(zone_name(interface.donor_block)))
+                            .child(CgnsNode::new("Transform", "int[IndexDimension]", CgnsData::Integer {
+                                shape: vec![n],
+                                values: interface.transform.clone(),
+                            }))
+                            .child(point_range("PointRange", &interface.range))
+                            .child(point_range("PointRangeDonor", &interface.donor_range)),
+                    );
+                }
+                zone = zone.child(connectivity);
+            }
+            base = base.child(zone);
+        }
+
+        Ok(CgnsNode::new("", "CGNSTree_t", CgnsData::None)
+            .child(CgnsNode::new("CGNSLibraryVersion", "CGNSLibraryVersion_t", CgnsData::Real32(vec![CGNS_VERSION])))
+            .child(base))
+    }
+
+    /// Writes the blocks as a CGNS file on HDF5, as `export_cgns_listing` lists them.
+    #[cfg(feature = "cgns")]
+    pub fn export_cgns(&self, filename: &str) -> Result<(), &'static str> {
+        let bytes = write_hdf5(&self.cgns_tree()?)?;
+        std::fs::write(filename, bytes).map_err(|_| "failed to write cgns file")
+    }
+
+    /// Writes the CGNS tree of the blocks as a text listing, one node per line.
+    /// This is not a CGNS file, which `export_cgns` writes with the `cgns`
+    /// feature, but a dump of the tree for checking it and for the golden tests.
+    pub fn export_cgns_listing(&self, filename: &str) -> Result<(), &'static str> {
+        let tree = self.cgns_tree()?;
+        let file = File::create(filename).map_err(|_| "failed to create file")?;
+        let mut writer = BufWriter::new(file);
+        tree.children.iter()
+            .try_for_each(|node| node.write_listing(&mut writer, 0))
+            .and_then(|_| writer.flush())
+            .map_err(|_| "failed to write cgns listing")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::block::{Block, BlockBuilder};
+
+    fn two_blocks() -> Blocks<Point2D> {
+        let mut blocks = Blocks::new();
+        let left = BlockBuilder::new_2d(0)
+            .dimensions(Dimensions::Two { nx: 3, ny: 2 })
+            .corners(Point2D::new(0.0, 0.0), Point2D::new(1.0, 0.0), Point2D::new(1.0, 1.0), Point2D::new(0.0, 1.0))
+            .condition(BoundaryType::South, BoundaryCondition::Wall)
+            .condition(BoundaryType::West, BoundaryCondition::Inflow)
+            .build()
+            .unwrap();
+        let right = BlockBuilder::new_2d(1)
+            .dimensions(Dimensions::Two { nx: 2, ny: 2 })
+            .corners(Point2D::new(1.0, 0.0), Point2D::new(2.0, 0.0), Point2D::new(2.0, 1.0), Point2D::new(1.0, 1.0))
+            .condition(BoundaryType::North, BoundaryCondition::Farfield)
+            .build()
+            .unwrap();
+        blocks.add_block(left).unwrap();
+        blocks.add_block(right).unwrap();
+        blocks
+    }
+
+    #[test]
+    fn test_cgns_listing_matches_golden_file() {
+        let tree = two_blocks().cgns_tree().unwrap();
+        let mut out = Vec::new();
+        for node in &tree.children {
+            node.write_listing(&mut out, 0).unwrap();
+        }
+        assert_eq!(String::from_utf8(out).unwrap(), include_str!("golden/cgns_two_blocks.txt"));
+    }
+
+    #[cfg(feature = "cgns")]
+    #[test]
+    fn test_cgns_file_reads_back() {
+        two_blocks().export_cgns("test_two_blocks.cgns").unwrap();
+        let bytes = std::fs::read("test_two_blocks.cgns").expect("should be able to read the file");
+        std::fs::remove_file("test_two_blocks.cgns").expect("failed to clean up test file");
+        let tree = crate::io::hdf5::read_hdf5(&bytes).unwrap();
+
+        let range = tree.find("Base/Zone_0/ZoneGridConnectivity/east/PointRange").unwrap();
+        assert_eq!(range.data, CgnsData::Integer { shape: vec![2, 2], values: vec![3, 1, 3, 2] });
+        let donor = tree.find("Base/Zone_1/ZoneGridConnectivity/west/PointRangeDonor").unwrap();
+        assert_eq!(donor.data, CgnsData::Integer { shape: vec![2, 2], values: vec![3, 1, 3, 2] });
+        let inflow = tree.find("Base/Zone_0/ZoneBC/west/PointRange").unwrap();
+        assert_eq!(inflow.data, CgnsData::Integer { shape: vec![2, 2], values: vec![1, 1, 1, 2] });
+
+        // the file keeps every node, though children come back in name order
+        fn by_name(mut node: CgnsNode) -> CgnsNode {
+            node.children = node.children.into_iter().map(by_name).collect();
+            node.children.sort_by(|a, b| a.name.cmp(&b.name));
+            node
+        }
+        assert_eq!(tree, by_name(two_blocks().cgns_tree().unwrap()));
+    }
+
+    #[test]
+    fn test_point_ranges_are_one_based() {
+        let tree = two_blocks().cgns_tree().unwrap();
+
+        // the east face of a 3 x 2 zone is i = 3 for j from 1 to 2
+        let range = tree.find("Base/Zone_0/ZoneGridConnectivity/east/PointRange").unwrap();
+        assert_eq!(range.data, CgnsData::Integer { shape: vec![2, 2], values: vec![3, 1, 3, 2] });
+        let donor = tree.find("Base/Zone_0/ZoneGridConnectivity/east/PointRangeDonor").unwrap();
+        assert_eq!(donor.data, CgnsData::Integer { shape: vec![2, 2], values: vec![1, 1, 1, 2] });
+
+        let zone = tree.find("Base/Zone_1").unwrap();
+        assert_eq!(zone.data, CgnsData::Integer { shape: vec![2, 3], values: vec![2, 2, 1, 1, 0, 0] });
+        let wall = tree.find("Base/Zone_0/ZoneBC/south").unwrap();
+        assert_eq!(wall.data, CgnsData::Char("BCWall".to_string()));
+        assert!(tree.find("Base/Zone_1/ZoneBC/south").is_none());
+    }
+
+    #[test]
+    fn test_3d_zone_ranges() {
+        let mut vertices = Vertices::new_3d(2, 3, 4);
+        vertices.populate_uniform();
+        let mut block = Block::new_3d(5, vertices).unwrap();
+        block.set_condition(BoundaryType::Top, BoundaryCondition::Symmetry).unwrap();
+        let mut blocks = Blocks::new();
+        blocks.add_block(block).unwrap();
+
+        let tree = blocks.cgns_tree().unwrap();
+        let top = tree.find("Base/Zone_5/ZoneBC/top").unwrap();
+        assert_eq!(top.data, CgnsData::Char("BCSymmetryPlane".to_string()));
+        assert_eq!(top.children[1].data, CgnsData::Integer { shape: vec![3, 2], values: vec![1, 1, 4, 2, 3, 4] });
+        assert_eq!(tree.find("Base/Zone_5/GridCoordinates").unwrap().children.len(), 3);
+        assert!(tree.find("Base/Zone_5/ZoneGridConnectivity").is_none());
+    }
+}
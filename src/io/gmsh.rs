@@ -0,0 +1,588 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+
+use crate::geometry::prelude::*;
+use crate::io::{cell_faces, cells, join, on_face, BlockView, MergedPoints};
+use crate::mesh::block::Blocks;
+use crate::mesh::nodes::Nodes;
+
+// element type ids of the msh format
+const LINE: usize = 1;
+const QUADRANGLE: usize = 3;
+const HEXAHEDRON: usize = 5;
+
+// the physical group holding every cell
+const DOMAIN: &str = "domain";
+
+impl Nodes<Point2D> {
+    /// Writes the nodes as a Gmsh mesh, one quadrangle per node from its
+    /// corners, and a physical group for each face of the grid.
+    pub fn export_gmsh(&self, vertices: &Vertices<Point2D>, filename: &str) -> Result<(), &'static str> {
+        let corners: Vec<Vec<usize>> = self.nodes.iter().map(|n| n.corners().to_vec()).collect();
+        write_nodes(filename, vertices, &corners)
+    }
+}
+
+impl Nodes<Point3D> {
+    /// Writes the nodes as a Gmsh mesh, one hexahedron per node from its
+    /// corners, and a physical group for each face of the grid.
+    pub fn export_gmsh(&self, vertices: &Vertices<Point3D>, filename: &str) -> Result<(), &'static str> {
+        let corners: Vec<Vec<usize>> = self.nodes.iter().map(|n| n.corners().to_vec()).collect();
+        write_nodes(filename, vertices, &corners)
+    }
+}
+
+impl<P: Point> Blocks<P> {
+    /// Writes the blocks as one Gmsh mesh with a volume entity per block. Faces
+    /// shared by blocks are merged, and the rest become boundary entities whose
+    /// physical groups are named as SU2 markers are.
+    pub fn export_gmsh(&self, filename: &str) -> Result<(), &'static str> {
+        write_file(filename, &BlockView::of_blocks(self))
+    }
+}
+
+impl Vertices<Point2D> {
+    /// Reads the quadrangles of a Gmsh mesh back into a structured grid.
+    pub fn import_gmsh_2d(filename: &str) -> Result<Self, &'static str> {
+        let mesh = read_file(filename)?;
+        let (dims, points) = structure(&mesh, 2)?;
+        let mut vertices = Vertices::new_2d(dims[0], dims[1]);
+        for (id, [x, y, _]) in points.into_iter().enumerate() {
+            vertices.add_vertex(Vertex::new_2d(id, x, y))?;
+        }
+        Ok(vertices)
+    }
+}
+
+impl Vertices<Point3D> {
+    /// Reads the hexahedra of a Gmsh mesh back into a structured grid.
+    pub fn import_gmsh_3d(filename: &str) -> Result<Self, &'static str> {
+        let mesh = read_file(filename)?;
+        let (dims, points) = structure(&mesh, 3)?;
+        let mut vertices = Vertices::new_3d(dims[0], dims[1], dims[2]);
+        for (id, [x, y, z]) in points.into_iter().enumerate() {
+            vertices.add_vertex(Vertex::new_3d(id, x, y, z))?;
+        }
+        Ok(vertices)
+    }
+}
+
+fn write_file<P: Point>(filename: &str, blocks: &[BlockView<P>]) -> Result<(), &'static str> {
+    let file = File::create(filename).map_err(|_| "failed to create file")?;
+    let mut writer = BufWriter::new(file);
+    write_gmsh(&mut writer, blocks)?;
+    writer.flush().map_err(|_| "failed to write msh file")
+}
+
+// each node's cell is placed in the grid by its first corner, so that cells
+// against the edges of the grid still get boundary elements
+fn write_nodes<P: Point>(filename: &str, vertices: &Vertices<P>, corners: &[Vec<usize>]) -> Result<(), &'static str> {
+    let dims = vertices.shape();
+    if corners.iter().flatten().any(|c| !vertices.vertex_exists(*c)) {
+        return Err("nodes do not match the vertices");
+    }
+    let cells: Vec<_> = corners.iter().map(|corners| {
+        let first = corners[0];
+        let index: Vec<usize> = dims.iter().scan(1, |stride, n| {
+            let i = first / *stride % n;
+            *stride *= n;
+            Some(i)
+        }).collect();
+        (index, corners.clone())
+    }).collect();
+
+    let file = File::create(filename).map_err(|_| "failed to create file")?;
+    let mut writer = BufWriter::new(file);
+    write_cells(&mut writer, &[BlockView::of_vertices(vertices)], &[cells])?;
+    writer.flush().map_err(|_| "failed to write msh file")
+}
+
+fn read_file(filename: &str) -> Result<GmshMesh, &'static str> {
+    let text = fs::read_to_string(filename).map_err(|_| "failed to read file")?;
+    read_gmsh(&text)
+}
+
+// the boundary elements of one face of one block, which becomes its own entity
+struct Patch {
+    block: usize,
+    group: usize,
+    elements: Vec<Vec<usize>>,
+}
+
+/// Writes blocks as an ascii MSH 4.1 mesh of quadrangles in 2D or hexahedra
+/// in 3D. Each block is an entity in the "domain" physical group and each
+/// unmatched face a boundary entity in the group of its name.
+pub fn write_gmsh<P: Point>(writer: &mut impl Write, blocks: &[BlockView<P>]) -> Result<(), &'static str> {
+    let block_cells: Vec<Vec<_>> = blocks.iter().map(|b| cells(&b.vertices.shape()).collect()).collect();
+    write_cells(writer, blocks, &block_cells)
+}
+
+// the cells of each block as their index and vertex ids, as `cells` gives them
+fn write_cells<P: Point>(writer: &mut impl Write, blocks: &[BlockView<P>], block_cells: &[Vec<(Vec<usize>, Vec<usize>)>]) -> Result<(), &'static str> {
+    if blocks.is_empty() {
+        return Err("no blocks to write");
+    }
+    let dimensions = blocks[0].vertices.dimensions();
+    let merged = MergedPoints::new(blocks)?;
+
+    let mut groups: Vec<String> = Vec::new();
+    let mut volumes = Vec::new();
+    let mut patches = Vec::new();
+    for (b, block) in blocks.iter().enumerate() {
+        let dims = block.vertices.shape();
+        let mut faces: Vec<_> = cell_faces(dimensions).into_iter().map(|(face, local)| (face, local, Vec::new())).collect();
+
+        let mut elements = Vec::new();
+        for (index, corners) in &block_cells[b] {
+            let corners: Vec<usize> = corners.iter().map(|c| merged.numbering[b][*c]).collect();
+            for (face, local, patch) in faces.iter_mut() {
+                if on_face(*face, index, &dims) && !merged.is_matched(block.id, *face, index) {
+                    patch.push(local.iter().map(|c| corners[*c]).collect());
+                }
+            }
+            elements.push(corners);
+        }
+        volumes.push(elements);
+
+        // faces left without elements, such as faces that wholly meet other
+        // faces or the faces of a partial node set away from its cells, get no
+        // entity or group
+        for (face, _, elements) in faces.into_iter().filter(|(_, _, elements)| !elements.is_empty()) {
+            let name = block.name(face);
+            let group = groups.iter().position(|g| *g == name).unwrap_or_else(|| {
+                groups.push(name);
+                groups.len() - 1
+            });
+            patches.push(Patch { block: b, group, elements });
+        }
+    }
+
+    // each point belongs to the entity of the first block that holds it
+    let mut owner = vec![usize::MAX; merged.points.len()];
+    for (b, numbering) in merged.numbering.iter().enumerate().rev() {
+        for p in numbering {
+            owner[*p] = b;
+        }
+    }
+
+    write_sections(writer, dimensions, &merged.points, &owner, &groups, &volumes, &patches)
+        .map_err(|_| "failed to write msh file")
+}
+
+fn write_sections(
+    writer: &mut impl Write,
+    dimensions: usize,
+    points: &[[f64; 3]],
+    owner: &[usize],
+    groups: &[String],
+    volumes: &[Vec<Vec<usize>>],
+    patches: &[Patch],
+) -> std::io::Result<()> {
+    let (volume, surface) = if dimensions == 2 { (QUADRANGLE, LINE) } else { (HEXAHEDRON, QUADRANGLE) };
+    let domain = groups.len() + 1;
+
+    writeln!(writer, "$MeshFormat\n4.1 0 8\n$EndMeshFormat")?;
+
+    writeln!(writer, "$PhysicalNames\n{}", groups.len() + 1)?;
+    for (g, name) in groups.iter().enumerate() {
+        writeln!(writer, "{} {} \"{}\"", dimensions - 1, g + 1, name)?;
+    }
+    writeln!(writer, "{} {} \"{}\"\n$EndPhysicalNames", dimensions, domain, DOMAIN)?;
+
+    // no points, then curves and surfaces in 2D or surfaces and volumes in 3D;
+    // entity tags are the patch or block position plus one
+    writeln!(writer, "$Entities")?;
+    let mut counts = [0; 4];
+    counts[dimensions - 1] = patches.len();
+    counts[dimensions] = volumes.len();
+    writeln!(writer, "{}", join(&counts))?;
+    for (e, patch) in patches.iter().enumerate() {
+        let bounds = bounding_box(points, patch.elements.iter().flatten());
+        writeln!(writer, "{} {} 1 {} 0", e + 1, join(&bounds), patch.group + 1)?;
+    }
+    for (e, elements) in volumes.iter().enumerate() {
+        let bounds = bounding_box(points, elements.iter().flatten());
+        let bounding: Vec<usize> = (0..patches.len()).filter(|p| patches[*p].block == e).map(|p| p + 1).collect();
+        writeln!(writer, "{} {} 1 {} {} {}", e + 1, join(&bounds), domain, bounding.len(), join(&bounding))?;
+    }
+    writeln!(writer, "$EndEntities")?;
+
+    // node tags are the merged point ids plus one
+    writeln!(writer, "$Nodes\n{} {} 1 {}", volumes.len(), points.len(), points.len())?;
+    for e in 0..volumes.len() {
+        let owned: Vec<usize> = (0..points.len()).filter(|p| owner[*p] == e).collect();
+        writeln!(writer, "{} {} 0 {}", dimensions, e + 1, owned.len())?;
+        for p in &owned {
+            writeln!(writer, "{}", p + 1)?;
+        }
+        for p in &owned {
+            writeln!(writer, "{}", join(&points[*p]))?;
+        }
+    }
+    writeln!(writer, "$EndNodes")?;
+
+    // cells first, so in a single block an element's tag is its node id plus one
+    let total: usize = volumes.iter().map(|v| v.len()).sum::<usize>()
+        + patches.iter().map(|p| p.elements.len()).sum::<usize>();
+    writeln!(writer, "$Elements\n{} {} 1 {}", volumes.len() + patches.len(), total, total)?;
+    let mut tag = 1;
+    let entities = volumes.iter().enumerate().map(|(e, elements)| (dimensions, e, volume, elements))
+        .chain(patches.iter().enumerate().map(|(e, patch)| (dimensions - 1, e, surface, &patch.elements)));
+    for (dimension, e, kind, elements) in entities {
+        writeln!(writer, "{} {} {} {}", dimension, e + 1, kind, elements.len())?;
+        for element in elements {
+            let nodes: Vec<usize> = element.iter().map(|p| p + 1).collect();
+            writeln!(writer, "{} {}", tag, join(&nodes))?;
+            tag += 1;
+        }
+    }
+    writeln!(writer, "$EndElements")
+}
+
+fn bounding_box<'a>(points: &[[f64; 3]], ids: impl Iterator<Item = &'a usize>) -> [f64; 6] {
+    let mut bounds = [f64::INFINITY, f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+    for id in ids {
+        for c in 0..3 {
+            bounds[c] = bounds[c].min(points[*id][c]);
+            bounds[c + 3] = bounds[c + 3].max(points[*id][c]);
+        }
+    }
+    bounds
+}
+
+/// The nodes and elements read from a Gmsh mesh, by tag. Entities and
+/// physical groups are not kept.
+#[derive(Debug, Default)]
+pub struct GmshMesh {
+    pub nodes: HashMap<usize, [f64; 3]>,
+    pub elements: Vec<(usize, Vec<usize>)>, // element type and node tags
+}
+
+/// Reads the nodes and elements of an ascii MSH 4.1 mesh, skipping any other
+/// sections.
+pub fn read_gmsh(text: &str) -> Result<GmshMesh, &'static str> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    let mut mesh = GmshMesh::default();
+    let mut format = false;
+
+    while let Some(line) = lines.next() {
+        match line {
+            "$MeshFormat" => {
+                let header = numbers::<f64>(lines.next())?;
+                if header.len() < 2 || header[0] != 4.1 || header[1] != 0.0 {
+                    return Err("only ascii msh 4.1 files are supported");
+                }
+                format = true;
+            }
+            "$Nodes" => {
+                let header = numbers::<usize>(lines.next())?;
+                for _ in 0..*header.first().ok_or("invalid nodes section")? {
+                    let block = numbers::<usize>(lines.next())?;
+                    let count = *block.get(3).ok_or("invalid nodes section")?;
+                    let tags = (0..count)
+                        .map(|_| numbers::<usize>(lines.next())?.first().copied().ok_or("invalid node tag"))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    for tag in tags {
+                        let coords = numbers::<f64>(lines.next())?;
+                        if coords.len() < 3 {
+                            return Err("invalid node coordinates");
+                        }
+                        mesh.nodes.insert(tag, [coords[0], coords[1], coords[2]]);
+                    }
+                }
+            }
+            "$Elements" => {
+                let header = numbers::<usize>(lines.next())?;
+                for _ in 0..*header.first().ok_or("invalid elements section")? {
+                    let block = numbers::<usize>(lines.next())?;
+                    let (kind, count) = match block[..] {
+                        [_, _, kind, count] => (kind, count),
+                        _ => return Err("invalid elements section"),
+                    };
+                    for _ in 0..count {
+                        let element = numbers::<usize>(lines.next())?;
+                        mesh.elements.push((kind, element.get(1..).unwrap_or_default().to_vec()));
+                    }
+                }
+            }
+            section if section.starts_with('$') && !section.starts_with("$End") => {
+                let end = format!("$End{}", &section[1..]);
+                lines.by_ref().find(|l| *l == end).ok_or("unterminated section")?;
+            }
+            _ => {}
+        }
+    }
+
+    if !format {
+        return Err("missing mesh format section");
+    }
+    Ok(mesh)
+}
+
+fn numbers<T: std::str::FromStr>(line: Option<&str>) -> Result<Vec<T>, &'static str> {
+    line.ok_or("unexpected end of file")?
+        .split_whitespace()
+        .map(|token| token.parse().map_err(|_| "invalid number"))
+        .collect()
+}
+
+// the ways a cell's corners can be listed that keep it the same cell, as
+// the corner each local position takes: cyclic shifts of a quad, and the 24
+// rotations of a hexahedron built from quarter turns about k and i
+fn orientations(dimensions: usize) -> Vec<Vec<usize>> {
+    let turns: Vec<Vec<usize>> = if dimensions == 2 {
+        vec![vec![1, 2, 3, 0]]
+    } else {
+        vec![vec![1, 2, 3, 0, 5, 6, 7, 4], vec![3, 2, 6, 7, 0, 1, 5, 4]]
+    };
+    let mut found = vec![(0..turns[0].len()).collect::<Vec<usize>>()];
+    let mut n = 0;
+    while n < found.len() {
+        for turn in &turns {
+            let next: Vec<usize> = turn.iter().map(|t| found[n][*t]).collect();
+            if !found.contains(&next) {
+                found.push(next);
+            }
+        }
+        n += 1;
+    }
+    found
+}
+
+// pairs of local corners shared by a cell and its neighbour along i, j and
+// k, as (neighbour corner, cell corner)
+fn steps(dimensions: usize) -> Vec<Vec<(usize, usize)>> {
+    if dimensions == 2 {
+        vec![vec![(0, 1), (3, 2)], vec![(0, 3), (1, 2)]]
+    } else {
+        vec![
+            vec![(0, 1), (3, 2), (4, 5), (7, 6)],
+            vec![(0, 3), (1, 2), (4, 7), (5, 6)],
+            vec![(0, 4), (1, 5), (2, 6), (3, 7)],
+        ]
+    }
+}
+
+/// Recovers the structured layout of the quads or hexahedra of a mesh by
+/// walking from a corner cell, giving the vertex counts along each axis and
+/// the coordinates of each vertex, i fastest.
+fn structure(mesh: &GmshMesh, dimensions: usize) -> Result<(Vec<usize>, Vec<[f64; 3]>), &'static str> {
+    let kind = if dimensions == 2 { QUADRANGLE } else { HEXAHEDRON };
+    let corners = 1 << dimensions;
+    let elements: Vec<&Vec<usize>> = mesh.elements.iter().filter(|(k, _)| *k == kind).map(|(_, e)| e).collect();
+    if elements.is_empty() {
+        return Err("mesh has no cells of the requested dimension");
+    }
+    if elements.iter().any(|e| e.len() != corners) {
+        return Err("invalid cell");
+    }
+
+    // cells by each of their faces, a face being its sorted node tags
+    let mut by_face: HashMap<Vec<usize>, Vec<usize>> = HashMap::new();
+    let mut uses: HashMap<usize, usize> = HashMap::new();
+    for (e, element) in elements.iter().enumerate() {
+        for (_, local) in cell_faces(dimensions) {
+            let mut face: Vec<usize> = local.iter().map(|c| element[*c]).collect();
+            face.sort_unstable();
+            by_face.entry(face).or_default().push(e);
+        }
+        for tag in element.iter() {
+            *uses.entry(*tag).or_default() += 1;
+        }
+    }
+
+    let orientations = orientations(dimensions);
+    let steps = steps(dimensions);
+    let orient = |e: usize, fits: &dyn Fn(&[usize]) -> bool| -> Option<Vec<usize>> {
+        orientations.iter()
+            .map(|o| o.iter().map(|c| elements[e][*c]).collect::<Vec<usize>>())
+            .find(|cell| fits(cell))
+    };
+    // the neighbour across a step, oriented to carry on the same axes
+    let next = |e: usize, cell: &[usize], step: &[(usize, usize)]| -> Result<Option<(usize, Vec<usize>)>, &'static str> {
+        let mut face: Vec<usize> = step.iter().map(|(_, c)| cell[*c]).collect();
+        face.sort_unstable();
+        let Some(&n) = by_face.get(&face).and_then(|cells| cells.iter().find(|n| **n != e)) else {
+            return Ok(None);
+        };
+        let oriented = orient(n, &|other| step.iter().all(|(a, b)| other[*a] == cell[*b]))
+            .ok_or("mesh is not structured")?;
+        Ok(Some((n, oriented)))
+    };
+
+    // a corner of the grid is the only vertex used by just one cell
+    let start = elements.iter().enumerate()
+        .flat_map(|(e, element)| element.iter().map(move |tag| (*tag, e)))
+        .filter(|(tag, _)| uses[tag] == 1)
+        .min()
+        .ok_or("mesh is not structured")?;
+    let first = orient(start.1, &|cell| cell[0] == start.0).ok_or("mesh is not structured")?;
+
+    // the number of cells along each axis from the first cell
+    let mut counts = Vec::with_capacity(dimensions);
+    for step in &steps {
+        let (mut e, mut cell, mut count) = (start.1, first.clone(), 1);
+        while let Some((n, oriented)) = next(e, &cell, step)? {
+            (e, cell, count) = (n, oriented, count + 1);
+            if count > elements.len() {
+                return Err("mesh is not structured");
+            }
+        }
+        counts.push(count);
+    }
+    if counts.iter().product::<usize>() != elements.len() {
+        return Err("mesh is not structured");
+    }
+
+    // place every cell, i fastest, by stepping from one already placed
+    let dims: Vec<usize> = counts.iter().map(|c| c + 1).collect();
+    let strides: Vec<usize> = counts.iter().scan(1, |stride, c| {
+        let s = *stride;
+        *stride *= c;
+        Some(s)
+    }).collect();
+    let mut placed: Vec<(usize, Vec<usize>)> = Vec::with_capacity(elements.len());
+    let mut visited = vec![false; elements.len()];
+    let mut tags = vec![None; dims.iter().product()];
+    for (index, grid_corners) in cells(&dims) {
+        let (e, cell) = match index.iter().position(|i| *i > 0) {
+            None => (start.1, first.clone()),
+            Some(axis) => {
+                let (e, cell) = &placed[placed.len() - strides[axis]];
+                next(*e, cell, &steps[axis])?.ok_or("mesh is not structured")?
+            }
+        };
+        if std::mem::replace(&mut visited[e], true) {
+            return Err("mesh is not structured");
+        }
+        for (vertex, tag) in grid_corners.iter().zip(&cell) {
+            match tags[*vertex] {
+                None => tags[*vertex] = Some(*tag),
+                Some(existing) if existing == *tag => {}
+                Some(_) => return Err("mesh is not structured"),
+            }
+        }
+        placed.push((e, cell));
+    }
+
+    let points = tags.iter()
+        .map(|tag| tag.and_then(|t| mesh.nodes.get(&t).copied()).ok_or("missing node"))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((dims, points))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::unit_square;
+    use crate::mesh::block::Block;
+    use crate::mesh::boundary::{BoundaryCondition, BoundaryType};
+    use crate::mesh::nodes::Node;
+
+    fn msh(blocks: &[BlockView<Point2D>]) -> String {
+        let mut out = Vec::new();
+        write_gmsh(&mut out, blocks).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    // the lines of a section, without its markers
+    fn section<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+        let start = text.lines().position(|l| l == format!("${}", name)).unwrap();
+        let end = text.lines().position(|l| l == format!("$End{}", name)).unwrap();
+        text.lines().skip(start + 1).take(end - start - 1).collect()
+    }
+
+    #[test]
+    fn test_2d_sections() {
+        let vertices = unit_square(3, 2);
+        let text = msh(&[BlockView::of_vertices(&vertices)]);
+
+        assert_eq!(section(&text, "MeshFormat"), vec!["4.1 0 8"]);
+        assert_eq!(section(&text, "Entities")[0], "0 4 1 0");
+        assert_eq!(section(&text, "Nodes")[..2], ["1 6 1 6", "2 1 0 6"]);
+
+        // cells first, then the boundary lines with the domain on their left
+        let elements = section(&text, "Elements");
+        assert_eq!(elements[..4], ["5 8 1 8", "2 1 3 2", "1 1 2 5 4", "2 2 3 6 5"]);
+        assert_eq!(elements[4..6], ["1 1 1 1", "3 4 1"]);
+    }
+
+    #[test]
+    fn test_physical_groups_come_from_boundary_definitions() {
+        let vertices = unit_square(2, 2);
+        let mut block = Block::new_2d(0, vertices).unwrap();
+        block.set_condition(BoundaryType::North, BoundaryCondition::Wall).unwrap();
+        block.set_condition(BoundaryType::South, BoundaryCondition::Wall).unwrap();
+        block.set_name(BoundaryType::East, "outlet").unwrap();
+        let text = msh(&[BlockView::of_block(&block)]);
+
+        assert_eq!(section(&text, "PhysicalNames"), vec!["4", "1 1 \"west\"", "1 2 \"outlet\"", "1 3 \"wall\"", "2 4 \"domain\""]);
+        // the north and south curves share the wall group
+        let entities = section(&text, "Entities");
+        assert!(entities[3].starts_with("3 0 0 0 1 0 0 1 3 "));
+        assert!(entities[4].starts_with("4 0 1 0 1 1 0 1 3 "));
+        assert!(entities[5].ends_with(" 1 4 4 1 2 3 4"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let vertices = unit_square(4, 3);
+        let mut nodes = Nodes::new_2d();
+        nodes.populate(&vertices).unwrap();
+        nodes.export_gmsh(&vertices, "test_round_trip.msh").unwrap();
+        let read = Vertices::import_gmsh_2d("test_round_trip.msh").unwrap();
+        fs::remove_file("test_round_trip.msh").expect("failed to clean up test file");
+        assert_eq!(read.shape(), vec![4, 3]);
+        assert_eq!(read.coordinates(), vertices.coordinates());
+
+        let mut vertices = Vertices::new_3d(2, 3, 4);
+        vertices.populate_uniform();
+        let mut nodes = Nodes::new_3d();
+        nodes.populate(&vertices).unwrap();
+        nodes.export_gmsh(&vertices, "test_round_trip_3d.msh").unwrap();
+        let read = Vertices::import_gmsh_3d("test_round_trip_3d.msh").unwrap();
+        fs::remove_file("test_round_trip_3d.msh").expect("failed to clean up test file");
+        assert_eq!(read.shape(), vec![2, 3, 4]);
+        assert_eq!(read.coordinates(), vertices.coordinates());
+
+        // only the cells of the nodes given are written, from their corners
+        let vertices = unit_square(3, 2);
+        let mut nodes = Nodes::new_2d();
+        nodes.nodes.push(Node::new_2d(0, [1, 2, 5, 4], [None; 4]));
+        nodes.export_gmsh(&vertices, "test_nodes.msh").unwrap();
+        let text = fs::read_to_string("test_nodes.msh").expect("should be able to read the file");
+        fs::remove_file("test_nodes.msh").expect("failed to clean up test file");
+        // the cell touches the south, east and north faces, so three lines follow
+        // it, and the west face is left out with no elements to hold
+        assert!(text.contains("$Elements\n4 4 1 4\n2 1 3 1\n1 2 3 6 5\n"));
+        assert!(text.contains("$Entities\n0 3 1 0\n"));
+        assert!(!text.contains("inf") && !text.contains("\"west\""));
+        nodes.nodes.push(Node::new_2d(1, [4, 5, 8, 7], [None; 4]));
+        assert!(nodes.export_gmsh(&vertices, "test_nodes.msh").is_err());
+
+        // cells listed from any corner are turned to line up with their neighbours
+        let text = "$MeshFormat\n4.1 0 8\n$EndMeshFormat\n$Nodes\n1 6 1 6\n2 1 0 6\n1\n2\n3\n4\n5\n6\n\
+            0 0 0\n1 0 0\n2 0 0\n0 1 0\n1 1 0\n2 1 0\n$EndNodes\n\
+            $Elements\n1 2 1 2\n2 1 3 2\n1 5 4 1 2\n2 3 6 5 2\n$EndElements\n";
+        let (dims, points) = structure(&read_gmsh(text).unwrap(), 2).unwrap();
+        assert_eq!(dims, vec![3, 2]);
+        assert_eq!(points[2], [2.0, 0.0, 0.0]);
+        assert_eq!(points[4], [1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_rejects_unstructured_meshes() {
+        // three quads around a shared vertex, which no grid can hold
+        let text = "$MeshFormat\n4.1 0 8\n$EndMeshFormat\n$Nodes\n1 7 1 7\n2 1 0 7\n1\n2\n3\n4\n5\n6\n7\n\
+            0 0 0\n1 0 0\n1 1 0\n0 1 0\n-1 -1 0\n0 -1 0\n2 -1 0\n$EndNodes\n\
+            $Elements\n1 3 1 3\n2 1 3 3\n1 1 2 3 4\n2 5 6 1 4\n3 6 7 2 1\n$EndElements\n";
+        let mesh = read_gmsh(text).unwrap();
+        assert_eq!(mesh.elements.len(), 3);
+        assert_eq!(structure(&mesh, 2).unwrap_err(), "mesh is not structured");
+        assert_eq!(structure(&mesh, 3).unwrap_err(), "mesh has no cells of the requested dimension");
+        assert!(read_gmsh("$MeshFormat\n2.2 0 8\n$EndMeshFormat\n").is_err());
+    }
+}
@@ -0,0 +1,510 @@
+#![allow(dead_code)]
+
+// A CGNS tree stored the way the CGNS library lays it out on HDF5: each node is
+// a group carrying its name, label and data type as attributes, with its data,
+// if any, in a dataset called " data" whose dimensions are reversed, since
+// HDF5 is row major. The file uses only the oldest HDF5 structures (a version
+// 0 superblock, version 1 object headers and symbol table groups), which every
+// HDF5 library reads.
+
+use crate::io::cgns::{CgnsData, CgnsNode};
+
+const SIGNATURE: &[u8; 8] = b"\x89HDF\r\n\x1a\n";
+const SUPERBLOCK_SIZE: usize = 96;
+const UNDEFINED: u64 = u64::MAX;
+// the end of a local heap's free list
+const FREE_NULL: u64 = 1;
+const INTERNAL_K: usize = 16;
+const NAME_LENGTH: usize = 32;
+
+const DATASPACE: u16 = 0x0001;
+const DATATYPE: u16 = 0x0003;
+const FILL_VALUE: u16 = 0x0005;
+const LAYOUT: u16 = 0x0008;
+const ATTRIBUTE: u16 = 0x000c;
+const SYMBOL_TABLE: u16 = 0x0011;
+
+// written at the root as the CGNS library does, so it can tell how data was stored
+const FORMAT: &str = "IEEE_LITTLE_32";
+const HDF5_VERSION: &str = "HDF5 Version 1.8.23";
+
+/// The tree as the bytes of an HDF5 file. The root node stands for the file's
+/// root group, so its own name, label and data are not stored.
+pub fn write_hdf5(tree: &CgnsNode) -> Result<Vec<u8>, &'static str> {
+    // a symbol table node holds twice the leaf k entries, and each group here has one
+    let most = most_children(tree).max(2 + tree.children.len());
+    let mut file = FileWriter { bytes: vec![0; SUPERBLOCK_SIZE], leaf_k: most.div_ceil(2).max(4) };
+
+    let mut entries = vec![
+        (" format".to_string(), file.dataset(&fixed_point(1), &[FORMAT.len() as u64 + 1], &nul_terminated(FORMAT)), None),
+        (" hdf5version".to_string(), file.dataset(&fixed_point(1), &[NAME_LENGTH as u64 + 1], &padded_name(HDF5_VERSION)?), None),
+    ];
+    for child in &tree.children {
+        entries.push(file.node(child)?);
+    }
+    let root = file.group("HDF5 MotherNode", "Root Node of HDF5 File", "MT", entries)?;
+
+    let mut superblock = SIGNATURE.to_vec();
+    superblock.extend([0, 0, 0, 0, 0, 8, 8, 0]);
+    superblock.extend((file.leaf_k as u16).to_le_bytes());
+    superblock.extend((INTERNAL_K as u16).to_le_bytes());
+    superblock.extend(0u32.to_le_bytes());
+    for address in [0, UNDEFINED, file.bytes.len() as u64, UNDEFINED] {
+        superblock.extend(address.to_le_bytes());
+    }
+    superblock.extend(symbol_entry(0, root));
+    file.bytes[..SUPERBLOCK_SIZE].copy_from_slice(&superblock);
+    Ok(file.bytes)
+}
+
+/// Reads a tree written by `write_hdf5`, with each node's children in name
+/// order, as HDF5 keeps them.
+pub fn read_hdf5(bytes: &[u8]) -> Result<CgnsNode, &'static str> {
+    if take(bytes, 0, 8)? != SIGNATURE {
+        return Err("not an hdf5 file");
+    }
+    if take(bytes, 8, 8)? != [0, 0, 0, 0, 0, 8, 8, 0] {
+        return Err("only version 0 superblocks with 8 byte offsets are read");
+    }
+    let (_, links) = read_group(bytes, read_u64(bytes, 64)?)?;
+    let mut tree = CgnsNode::new("", "CGNSTree_t", CgnsData::None);
+    for (link, address) in links {
+        if !link.starts_with(' ') {
+            tree.children.push(read_node(bytes, address)?);
+        }
+    }
+    Ok(tree)
+}
+
+struct FileWriter {
+    bytes: Vec<u8>,
+    leaf_k: usize,
+}
+
+// a named link from a group, with the group's b-tree and heap for a child group
+type Entry = (String, u64, Option<(u64, u64)>);
+
+// a group's attributes as their name and raw data, and its links as their name and address
+type Group = (Vec<(String, Vec<u8>)>, Vec<(String, u64)>);
+
+impl FileWriter {
+    // appends a block at the next 8 byte boundary and gives its address
+    fn allocate(&mut self, block: &[u8]) -> u64 {
+        self.bytes.resize(self.bytes.len().next_multiple_of(8), 0);
+        let address = self.bytes.len() as u64;
+        self.bytes.extend(block);
+        address
+    }
+
+    fn node(&mut self, node: &CgnsNode) -> Result<Entry, &'static str> {
+        let mut entries = Vec::new();
+        let kind = match &node.data {
+            CgnsData::None => "MT",
+            CgnsData::Char(text) => {
+                entries.push((" data".to_string(), self.dataset(&fixed_point(1), &[text.len() as u64], text.as_bytes()), None));
+                "C1"
+            }
+            CgnsData::Integer { shape, values } => {
+                let dims: Vec<u64> = shape.iter().rev().map(|d| *d as u64).collect();
+                let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+                entries.push((" data".to_string(), self.dataset(&fixed_point(4), &dims, &data), None));
+                "I4"
+            }
+            CgnsData::Real32(values) => {
+                let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+                entries.push((" data".to_string(), self.dataset(&floating_point(4), &[values.len() as u64], &data), None));
+                "R4"
+            }
+            CgnsData::Real64(values) => {
+                let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+                entries.push((" data".to_string(), self.dataset(&floating_point(8), &[values.len() as u64], &data), None));
+                "R8"
+            }
+        };
+        for child in &node.children {
+            entries.push(self.node(child)?);
+        }
+        let (header, table) = self.group(&node.name, &node.label, kind, entries)?;
+        Ok((node.name.clone(), header, Some(table)))
+    }
+
+    fn dataset(&mut self, datatype: &[u8], dims: &[u64], data: &[u8]) -> u64 {
+        let address = if data.is_empty() { UNDEFINED } else { self.allocate(data) };
+        let mut layout = vec![3, 1];
+        layout.extend(address.to_le_bytes());
+        layout.extend((data.len() as u64).to_le_bytes());
+        self.allocate(&object_header(&[
+            (DATASPACE, dataspace(dims)),
+            (DATATYPE, datatype.to_vec()),
+            (FILL_VALUE, vec![2, 2, 2, 0]),
+            (LAYOUT, layout),
+        ]))
+    }
+
+    // writes a group's heap of link names, its symbol table node and b-tree,
+    // then its object header, giving the header and the b-tree and heap
+    fn group(&mut self, name: &str, label: &str, kind: &str, mut entries: Vec<Entry>) -> Result<(u64, (u64, u64)), &'static str> {
+        if entries.len() > 2 * self.leaf_k {
+            return Err("group has more children than a symbol table node holds");
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut names = vec![0; 8];
+        let mut offsets = Vec::new();
+        for (link, _, _) in &entries {
+            offsets.push(names.len() as u64);
+            names.extend(nul_terminated(link));
+            names.resize(names.len().next_multiple_of(8), 0);
+        }
+        let mut heap = b"HEAP".to_vec();
+        heap.extend([0; 4]);
+        heap.extend((names.len() as u64).to_le_bytes());
+        heap.extend(FREE_NULL.to_le_bytes());
+        heap.extend((self.bytes.len().next_multiple_of(8) as u64 + 32).to_le_bytes());
+        heap.extend(names);
+        let heap = self.allocate(&heap);
+
+        let mut btree = b"TREE".to_vec();
+        btree.extend([0, 0]);
+        if entries.is_empty() {
+            btree.extend(0u16.to_le_bytes());
+            btree.extend(UNDEFINED.to_le_bytes());
+            btree.extend(UNDEFINED.to_le_bytes());
+        } else {
+            let mut node = b"SNOD".to_vec();
+            node.extend([1, 0]);
+            node.extend((entries.len() as u16).to_le_bytes());
+            for ((_, header, table), offset) in entries.iter().zip(&offsets) {
+                node.extend(match table {
+                    Some(table) => symbol_entry(*offset, (*header, *table)),
+                    None => link_entry(*offset, *header),
+                });
+            }
+            node.resize(8 + 2 * self.leaf_k * 40, 0);
+            let node = self.allocate(&node);
+
+            // one child, holding every link name after the empty one up to the last
+            btree.extend(1u16.to_le_bytes());
+            btree.extend(UNDEFINED.to_le_bytes());
+            btree.extend(UNDEFINED.to_le_bytes());
+            btree.extend(0u64.to_le_bytes());
+            btree.extend(node.to_le_bytes());
+            btree.extend(offsets.last().unwrap().to_le_bytes());
+        }
+        btree.resize(24 + 2 * INTERNAL_K * 8 + (2 * INTERNAL_K + 1) * 8, 0);
+        let btree = self.allocate(&btree);
+
+        let mut table = btree.to_le_bytes().to_vec();
+        table.extend(heap.to_le_bytes());
+        let header = self.allocate(&object_header(&[
+            (SYMBOL_TABLE, table),
+            (ATTRIBUTE, attribute("name", &string(NAME_LENGTH + 1), &dataspace(&[]), &padded_name(name)?)),
+            (ATTRIBUTE, attribute("label", &string(NAME_LENGTH + 1), &dataspace(&[]), &padded_name(label)?)),
+            (ATTRIBUTE, attribute("type", &string(3), &dataspace(&[]), &nul_terminated(kind))),
+            (ATTRIBUTE, attribute("flags", &fixed_point(4), &dataspace(&[1]), &1i32.to_le_bytes())),
+        ]));
+        Ok((header, (btree, heap)))
+    }
+}
+
+fn most_children(node: &CgnsNode) -> usize {
+    node.children.iter().map(most_children).fold(node.children.len() + 1, usize::max)
+}
+
+// a version 1 object header, its messages each padded to 8 bytes
+fn object_header(messages: &[(u16, Vec<u8>)]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (kind, data) in messages {
+        let size = data.len().next_multiple_of(8);
+        body.extend(kind.to_le_bytes());
+        body.extend((size as u16).to_le_bytes());
+        body.extend([0; 4]);
+        body.extend(data);
+        body.resize(body.len().next_multiple_of(8), 0);
+    }
+    let mut header = vec![1, 0];
+    header.extend((messages.len() as u16).to_le_bytes());
+    header.extend(1u32.to_le_bytes());
+    header.extend((body.len() as u32).to_le_bytes());
+    header.extend([0; 4]);
+    header.extend(body);
+    header
+}
+
+// a symbol table entry for a group, caching its b-tree and heap
+fn symbol_entry(name: u64, (header, (btree, heap)): (u64, (u64, u64))) -> Vec<u8> {
+    let mut entry = name.to_le_bytes().to_vec();
+    entry.extend(header.to_le_bytes());
+    entry.extend(1u32.to_le_bytes());
+    entry.extend([0; 4]);
+    entry.extend(btree.to_le_bytes());
+    entry.extend(heap.to_le_bytes());
+    entry
+}
+
+fn link_entry(name: u64, header: u64) -> Vec<u8> {
+    let mut entry = name.to_le_bytes().to_vec();
+    entry.extend(header.to_le_bytes());
+    entry.extend([0; 24]);
+    entry
+}
+
+fn attribute(name: &str, datatype: &[u8], dataspace: &[u8], data: &[u8]) -> Vec<u8> {
+    let name = nul_terminated(name);
+    let mut message = vec![1, 0];
+    for size in [name.len(), datatype.len(), dataspace.len()] {
+        message.extend((size as u16).to_le_bytes());
+    }
+    for field in [&name[..], datatype, dataspace] {
+        message.extend(field);
+        message.resize(message.len().next_multiple_of(8), 0);
+    }
+    message.extend(data);
+    message
+}
+
+// a version 1 dataspace, scalar when it has no dimensions
+fn dataspace(dims: &[u64]) -> Vec<u8> {
+    let mut message = vec![1, dims.len() as u8, 0, 0, 0, 0, 0, 0];
+    for d in dims {
+        message.extend(d.to_le_bytes());
+    }
+    message
+}
+
+// a little endian signed integer of `size` bytes
+fn fixed_point(size: u32) -> Vec<u8> {
+    let mut message = vec![0x10, 0x08, 0, 0];
+    message.extend(size.to_le_bytes());
+    message.extend(0u16.to_le_bytes());
+    message.extend((size as u16 * 8).to_le_bytes());
+    message
+}
+
+// a little endian IEEE float of `size` bytes
+fn floating_point(size: u32) -> Vec<u8> {
+    let (exponent, exponent_bits, mantissa_bits, bias) = if size == 4 { (23, 8, 23, 127u32) } else { (52, 11, 52, 1023) };
+    let mut message = vec![0x11, 0x20, size as u8 * 8 - 1, 0];
+    message.extend(size.to_le_bytes());
+    message.extend(0u16.to_le_bytes());
+    message.extend((size as u16 * 8).to_le_bytes());
+    message.extend([exponent, exponent_bits, 0, mantissa_bits]);
+    message.extend(bias.to_le_bytes());
+    message
+}
+
+// a nul terminated ascii string of `size` bytes
+fn string(size: usize) -> Vec<u8> {
+    let mut message = vec![0x13, 0, 0, 0];
+    message.extend((size as u32).to_le_bytes());
+    message
+}
+
+fn nul_terminated(text: &str) -> Vec<u8> {
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.push(0);
+    bytes
+}
+
+fn padded_name(text: &str) -> Result<Vec<u8>, &'static str> {
+    if text.len() > NAME_LENGTH {
+        return Err("node names and labels are at most 32 characters");
+    }
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.resize(NAME_LENGTH + 1, 0);
+    Ok(bytes)
+}
+
+fn take(bytes: &[u8], at: u64, length: usize) -> Result<&[u8], &'static str> {
+    usize::try_from(at).ok()
+        .and_then(|at| bytes.get(at..at.checked_add(length)?))
+        .ok_or("hdf5 file is truncated")
+}
+
+fn read_u16(bytes: &[u8], at: u64) -> Result<u16, &'static str> {
+    Ok(u16::from_le_bytes(take(bytes, at, 2)?.try_into().unwrap()))
+}
+
+fn read_u64(bytes: &[u8], at: u64) -> Result<u64, &'static str> {
+    Ok(u64::from_le_bytes(take(bytes, at, 8)?.try_into().unwrap()))
+}
+
+// the text of a nul terminated or nul padded string
+fn read_text(bytes: &[u8]) -> Result<String, &'static str> {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8(bytes[..end].to_vec()).map_err(|_| "hdf5 string is not utf-8")
+}
+
+// the messages of a version 1 object header, as their type and data
+fn read_messages(bytes: &[u8], address: u64) -> Result<Vec<(u16, &[u8])>, &'static str> {
+    if take(bytes, address, 1)?[0] != 1 {
+        return Err("only version 1 object headers are read");
+    }
+    let count = read_u16(bytes, address + 2)? as usize;
+    let mut at = address + 16;
+    let mut messages = Vec::with_capacity(count);
+    for _ in 0..count {
+        let size = read_u16(bytes, at + 2)? as usize;
+        messages.push((read_u16(bytes, at)?, take(bytes, at + 8, size)?));
+        at += 8 + size as u64;
+    }
+    Ok(messages)
+}
+
+// a node from the attributes of its group, its " data" dataset and its child groups
+fn read_node(bytes: &[u8], header: u64) -> Result<CgnsNode, &'static str> {
+    let (attributes, links) = read_group(bytes, header)?;
+    let attribute = |key: &str| -> Result<String, &'static str> {
+        attributes.iter().find(|(k, _)| k == key).ok_or("node is missing an attribute").and_then(|(_, v)| read_text(v))
+    };
+
+    let mut node = CgnsNode::new(&attribute("name")?, &attribute("label")?, CgnsData::None);
+    let mut raw = None;
+    for (link, address) in links {
+        if link == " data" {
+            raw = Some(read_dataset(bytes, address)?);
+        } else if !link.starts_with(' ') {
+            node.children.push(read_node(bytes, address)?);
+        }
+    }
+
+    let kind = attribute("type")?;
+    node.data = match (kind.as_str(), raw) {
+        ("MT", _) => CgnsData::None,
+        ("C1", Some((_, data))) => CgnsData::Char(String::from_utf8(data.to_vec()).map_err(|_| "hdf5 string is not utf-8")?),
+        ("I4", Some((dims, data))) => CgnsData::Integer {
+            shape: dims.iter().rev().map(|d| *d as usize).collect(),
+            values: data.chunks_exact(4).map(|b| i32::from_le_bytes(b.try_into().unwrap())).collect(),
+        },
+        ("R4", Some((_, data))) => CgnsData::Real32(data.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect()),
+        ("R8", Some((_, data))) => CgnsData::Real64(data.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap())).collect()),
+        (_, None) => return Err("node has a data type but no data"),
+        _ => return Err("unsupported cgns data type"),
+    };
+    Ok(node)
+}
+
+// a group's attributes, and its links in name order
+fn read_group(bytes: &[u8], header: u64) -> Result<Group, &'static str> {
+    let mut attributes = Vec::new();
+    let mut table = None;
+    for (kind, data) in read_messages(bytes, header)? {
+        match kind {
+            SYMBOL_TABLE => table = Some((read_u64(data, 0)?, read_u64(data, 8)?)),
+            ATTRIBUTE => attributes.push(read_attribute(data)?),
+            _ => {}
+        }
+    }
+    let (btree, heap) = table.ok_or("hdf5 object is not a group")?;
+    if take(bytes, heap, 4)? != b"HEAP" {
+        return Err("hdf5 local heap is missing");
+    }
+    let names = take(bytes, read_u64(bytes, heap + 24)?, read_u64(bytes, heap + 8)? as usize)?;
+    let links = read_links(bytes, btree)?
+        .into_iter()
+        .map(|(name, address)| Ok((read_text(names.get(name as usize..).ok_or("hdf5 link name is outside its heap")?)?, address)))
+        .collect::<Result<_, &'static str>>()?;
+    Ok((attributes, links))
+}
+
+// the heap offset of each link name and the object header it points to
+fn read_links(bytes: &[u8], btree: u64) -> Result<Vec<(u64, u64)>, &'static str> {
+    if take(bytes, btree, 4)? != b"TREE" {
+        return Err("hdf5 group b-tree is missing");
+    }
+    let level = take(bytes, btree + 5, 1)?[0];
+    let used = read_u16(bytes, btree + 6)? as u64;
+    let mut links = Vec::new();
+    for c in 0..used {
+        let child = read_u64(bytes, btree + 32 + 16 * c)?;
+        if level > 0 {
+            links.extend(read_links(bytes, child)?);
+            continue;
+        }
+        if take(bytes, child, 4)? != b"SNOD" {
+            return Err("hdf5 symbol table node is missing");
+        }
+        for e in 0..read_u16(bytes, child + 6)? as u64 {
+            let entry = child + 8 + 40 * e;
+            links.push((read_u64(bytes, entry)?, read_u64(bytes, entry + 8)?));
+        }
+    }
+    Ok(links)
+}
+
+fn read_attribute(message: &[u8]) -> Result<(String, Vec<u8>), &'static str> {
+    let sizes = [read_u16(message, 2)?, read_u16(message, 4)?, read_u16(message, 6)?].map(|s| s as usize);
+    let name = read_text(take(message, 8, sizes[0])?)?;
+    let data = 8 + sizes.iter().map(|s| s.next_multiple_of(8)).sum::<usize>();
+    Ok((name, message.get(data..).ok_or("hdf5 attribute is truncated")?.to_vec()))
+}
+
+// the dimensions and raw bytes of a contiguous dataset
+fn read_dataset(bytes: &[u8], header: u64) -> Result<(Vec<u64>, &[u8]), &'static str> {
+    let mut dims = None;
+    let mut data = None;
+    for (kind, message) in read_messages(bytes, header)? {
+        match kind {
+            DATASPACE => {
+                let rank = take(message, 1, 1)?[0] as u64;
+                dims = Some((0..rank).map(|d| read_u64(message, 8 + 8 * d)).collect::<Result<Vec<_>, _>>()?);
+            }
+            LAYOUT => {
+                if take(message, 0, 2)? != [3, 1] {
+                    return Err("only contiguous datasets are read");
+                }
+                let size = read_u64(message, 10)? as usize;
+                data = Some(if size == 0 { &[][..] } else { take(bytes, read_u64(message, 2)?, size)? });
+            }
+            _ => {}
+        }
+    }
+    Ok((dims.ok_or("hdf5 dataset has no dataspace")?, data.ok_or("hdf5 dataset has no layout")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_superblock_and_root_group() {
+        let tree = CgnsNode::new("", "CGNSTree_t", CgnsData::None)
+            .child(CgnsNode::new("CGNSLibraryVersion", "CGNSLibraryVersion_t", CgnsData::Real32(vec![4.2])));
+        let bytes = write_hdf5(&tree).unwrap();
+        assert_eq!(&bytes[..8], SIGNATURE);
+        assert_eq!(read_u64(&bytes, 40).unwrap(), bytes.len() as u64);
+
+        // the root group holds the library's format and version datasets besides the tree
+        let (attributes, links) = read_group(&bytes, read_u64(&bytes, 64).unwrap()).unwrap();
+        let names: Vec<&str> = links.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec![" format", " hdf5version", "CGNSLibraryVersion"]);
+        let kind = attributes.iter().find(|(name, _)| name == "type").unwrap();
+        assert_eq!(read_text(&kind.1).unwrap(), "MT");
+        assert_eq!(read_hdf5(&bytes).unwrap(), tree);
+    }
+
+    #[test]
+    fn test_wide_groups_and_every_data_type() {
+        let mut base = CgnsNode::new("Base", "CGNSBase_t", CgnsData::Integer { shape: vec![2], values: vec![3, 3] });
+        for n in 0..20 {
+            base = base.child(CgnsNode::new(&format!("Zone_{:02}", n), "Zone_t", CgnsData::Real64(vec![n as f64, -0.5])));
+        }
+        base = base.child(CgnsNode::new("Family", "Family_t", CgnsData::Char("wall".to_string())));
+        let tree = CgnsNode::new("", "CGNSTree_t", CgnsData::None).child(base);
+
+        let read = read_hdf5(&write_hdf5(&tree).unwrap()).unwrap();
+        let base = read.find("Base").unwrap();
+        assert_eq!(base.children.len(), 21);
+        assert_eq!(base.children[0].name, "Family");
+        assert_eq!(read.find("Base/Zone_07").unwrap().data, CgnsData::Real64(vec![7.0, -0.5]));
+        assert_eq!(base.data, CgnsData::Integer { shape: vec![2], values: vec![3, 3] });
+    }
+
+    #[test]
+    fn test_names_longer_than_cgns_allows() {
+        let tree = CgnsNode::new("", "CGNSTree_t", CgnsData::None)
+            .child(CgnsNode::new(&"x".repeat(33), "UserDefinedData_t", CgnsData::None));
+        assert!(write_hdf5(&tree).is_err());
+        assert!(read_hdf5(b"not an hdf5 file at all").is_err());
+    }
+}
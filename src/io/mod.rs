@@ -0,0 +1,323 @@
+#![allow(dead_code)]
+
+pub mod cgns;
+pub mod gmsh;
+#[cfg(feature = "cgns")]
+pub mod hdf5;
+pub mod openfoam;
+pub mod plot3d;
+pub mod su2;
+pub mod tecplot;
+pub mod vtk;
+
+use crate::geometry::prelude::*;
+use crate::mesh::block::{Block, Blocks};
+use crate::mesh::boundary::{BoundaryCondition, BoundaryType};
+use crate::mesh::connectivity::{find_interfaces, Interface};
+use crate::mesh::quality::QualityReport;
+
+// the corners of each face of a quad, ordered so the outward normal is to the right
+pub(crate) const QUAD_FACES: [(BoundaryType, [usize; 2]); 4] = [
+    (BoundaryType::West, [3, 0]),
+    (BoundaryType::East, [1, 2]),
+    (BoundaryType::South, [0, 1]),
+    (BoundaryType::North, [2, 3]),
+];
+
+// the corners of each face of a hexahedron, wound so the normal points out of the cell
+pub(crate) const HEX_FACES: [(BoundaryType, [usize; 4]); 6] = [
+    (BoundaryType::West, [0, 4, 7, 3]),
+    (BoundaryType::East, [1, 2, 6, 5]),
+    (BoundaryType::South, [0, 1, 5, 4]),
+    (BoundaryType::North, [3, 7, 6, 2]),
+    (BoundaryType::Bottom, [0, 3, 2, 1]),
+    (BoundaryType::Top, [4, 5, 6, 7]),
+];
+
+/// The coordinates of one structured block laid out for writing, one array per
+/// component with i fastest, as most grid formats store them.
+#[derive(Debug)]
+pub struct Grid {
+    pub dims: Vec<usize>,       // nx, ny and nz in 3D
+    pub coords: Vec<Vec<f64>>,  // x, y and z in 3D
+}
+
+impl Grid {
+    pub fn from_vertices<P: Point>(vertices: &Vertices<P>) -> Result<Self, &'static str> {
+        let dims = vertices.shape();
+        if vertices.len() != dims.iter().product::<usize>() {
+            return Err("vertices must be fully populated before export");
+        }
+        let packed = vertices.coordinates();
+        let coords = (0..dims.len())
+            .map(|c| packed.iter().skip(c).step_by(dims.len()).copied().collect())
+            .collect();
+        Ok(Grid { dims, coords })
+    }
+
+    pub fn from_2d(vertices: &Vertices<Point2D>) -> Result<Self, &'static str> {
+        Self::from_vertices(vertices)
+    }
+
+    pub fn from_3d(vertices: &Vertices<Point3D>) -> Result<Self, &'static str> {
+        Self::from_vertices(vertices)
+    }
+
+    pub fn points(&self) -> usize {
+        self.dims.iter().product()
+    }
+
+    pub fn to_2d(&self) -> Result<Vertices<Point2D>, &'static str> {
+        if self.dims.len() != 2 || self.coords.len() != 2 {
+            return Err("grid is not 2D");
+        }
+        let mut vertices = Vertices::new_2d(self.dims[0], self.dims[1]);
+        for n in 0..self.points() {
+            vertices.add_vertex(Vertex::new_2d(n, self.coords[0][n], self.coords[1][n]))?;
+        }
+        Ok(vertices)
+    }
+
+    pub fn to_3d(&self) -> Result<Vertices<Point3D>, &'static str> {
+        if self.dims.len() != 3 || self.coords.len() != 3 {
+            return Err("grid is not 3D");
+        }
+        let mut vertices = Vertices::new_3d(self.dims[0], self.dims[1], self.dims[2]);
+        for n in 0..self.points() {
+            vertices.add_vertex(Vertex::new_3d(n, self.coords[0][n], self.coords[1][n], self.coords[2][n]))?;
+        }
+        Ok(vertices)
+    }
+}
+
+/// A named array of values attached to the points or cells of a grid, with the
+/// components of each entry stored together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataArray {
+    pub name: String,
+    pub components: usize,
+    pub values: Vec<f64>,
+}
+
+impl DataArray {
+    pub fn scalars(name: &str, values: Vec<f64>) -> Self {
+        DataArray { name: name.to_string(), components: 1, values }
+    }
+
+    pub fn vectors(name: &str, components: usize, values: Vec<f64>) -> Self {
+        DataArray { name: name.to_string(), components, values }
+    }
+}
+
+/// The point and cell data written alongside a grid, by any of the formats
+/// that carry fields.
+#[derive(Debug, Clone, Default)]
+pub struct Fields {
+    pub point_data: Vec<DataArray>,
+    pub cell_data: Vec<DataArray>,
+}
+
+impl Fields {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn point_data(mut self, array: DataArray) -> Self {
+        self.point_data.push(array);
+        self
+    }
+
+    pub fn cell_data(mut self, arrays: impl IntoIterator<Item = DataArray>) -> Self {
+        self.cell_data.extend(arrays);
+        self
+    }
+
+    /// Checks that every array has an entry for each point or cell of the grid.
+    fn check(&self, grid: &Grid) -> Result<(), &'static str> {
+        let points = grid.points();
+        let cells: usize = grid.dims.iter().map(|d| d.saturating_sub(1).max(1)).product();
+        for array in &self.point_data {
+            if array.components == 0 || array.values.len() != points * array.components {
+                return Err("point data must have one entry per point");
+            }
+        }
+        for array in &self.cell_data {
+            if array.components == 0 || array.values.len() != cells * array.components {
+                return Err("cell data must have one entry per cell");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl QualityReport {
+    /// Each metric as a cell data array, in node order.
+    pub fn cell_data(&self) -> Vec<DataArray> {
+        vec![
+            DataArray::scalars("scaled_jacobian", self.cells.iter().map(|c| c.scaled_jacobian).collect()),
+            DataArray::scalars("skewness", self.cells.iter().map(|c| c.skewness).collect()),
+            DataArray::scalars("aspect_ratio", self.cells.iter().map(|c| c.aspect_ratio).collect()),
+            DataArray::scalars("non_orthogonality", self.cells.iter().map(|c| c.non_orthogonality).collect()),
+            DataArray::scalars("volume_ratio", self.cells.iter().map(|c| c.volume_ratio).collect()),
+        ]
+    }
+}
+
+/// A block as the unstructured writers take it, so that bare vertices are
+/// written the same way as blocks.
+pub struct BlockView<'a, P: Point> {
+    pub id: usize,
+    pub vertices: &'a Vertices<P>,
+    pub conditions: &'a [(BoundaryType, BoundaryCondition)],
+    pub names: &'a [(BoundaryType, String)],
+}
+
+impl<'a, P: Point> BlockView<'a, P> {
+    pub fn of_vertices(vertices: &'a Vertices<P>) -> Self {
+        BlockView { id: 0, vertices, conditions: &[], names: &[] }
+    }
+
+    pub fn of_block(block: &'a Block<P>) -> Self {
+        BlockView { id: block.id, vertices: &block.vertices, conditions: &block.conditions, names: &block.names }
+    }
+
+    pub fn of_blocks(blocks: &'a Blocks<P>) -> Vec<Self> {
+        blocks.blocks().iter().map(Self::of_block).collect()
+    }
+
+    pub fn condition(&self, face: BoundaryType) -> Option<BoundaryCondition> {
+        self.conditions.iter().find(|(f, _)| *f == face).map(|(_, c)| *c)
+    }
+
+    /// The name given to a face, or else the name of its condition, or else
+    /// the name of the face itself.
+    pub fn name(&self, face: BoundaryType) -> String {
+        match self.names.iter().find(|(f, _)| *f == face) {
+            Some((_, name)) => name.clone(),
+            None => self.condition(face).map_or(face.name(), |c| c.name()).to_string(),
+        }
+    }
+}
+
+/// The vertices of several blocks numbered as one set of points. Vertices
+/// where blocks meet, or where a block meets itself across a cut, share a
+/// point, which takes the position of the first of them.
+pub struct MergedPoints {
+    pub numbering: Vec<Vec<usize>>, // the point of each vertex of each block, by vertex id
+    pub points: Vec<[f64; 3]>,      // z is zero in 2D
+    pub interfaces: Vec<Interface>,
+}
+
+impl MergedPoints {
+    pub fn new<P: Point>(blocks: &[BlockView<P>]) -> Result<Self, &'static str> {
+        let grids = blocks.iter().map(|b| Grid::from_vertices(b.vertices)).collect::<Result<Vec<_>, _>>()?;
+        let offsets: Vec<usize> = grids.iter().scan(0, |total, g| {
+            let offset = *total;
+            *total += g.points();
+            Some(offset)
+        }).collect();
+        let total: usize = grids.iter().map(|g| g.points()).sum();
+
+        let views: Vec<_> = blocks.iter().map(|b| (b.id, b.vertices)).collect();
+        let interfaces = find_interfaces(&views);
+        let position = |id: usize| blocks.iter().position(|b| b.id == id).unwrap();
+        let mut parent: Vec<usize> = (0..total).collect();
+        for interface in &interfaces {
+            let (a, b) = (position(interface.block), position(interface.donor_block));
+            for (index, donor) in interface.vertex_pairs() {
+                let p = offsets[a] + flat_index(&grids[a].dims, &index);
+                let q = offsets[b] + flat_index(&grids[b].dims, &donor);
+                union(&mut parent, p, q);
+            }
+        }
+
+        // roots are the lowest id in their set, so each is numbered before the rest of it
+        let mut global = vec![usize::MAX; total];
+        let mut points = Vec::new();
+        let mut numbering = Vec::with_capacity(grids.len());
+        for (grid, offset) in grids.iter().zip(&offsets) {
+            let mut local = Vec::with_capacity(grid.points());
+            for n in 0..grid.points() {
+                let root = find(&mut parent, offset + n);
+                if root == offset + n {
+                    global[root] = points.len();
+                    points.push([grid.coords[0][n], grid.coords[1][n], grid.coords.get(2).map_or(0.0, |z| z[n])]);
+                }
+                local.push(global[root]);
+            }
+            numbering.push(local);
+        }
+        Ok(MergedPoints { numbering, points, interfaces })
+    }
+
+    /// Whether the face of a cell against a face of its block meets another
+    /// face, and so is not a boundary.
+    pub fn is_matched(&self, block: usize, face: BoundaryType, index: &[usize]) -> bool {
+        self.interfaces.iter().any(|i| i.block == block && i.face == face && i.covers(index))
+    }
+}
+
+/// Each cell of a block with `dims` vertices along each axis, i fastest, as
+/// its index and the vertex ids of its corners: anticlockwise from the origin
+/// in 2D, and the k face followed by the k + 1 face in 3D.
+pub(crate) fn cells(dims: &[usize]) -> impl Iterator<Item = (Vec<usize>, Vec<usize>)> + '_ {
+    let (nx, ny) = (dims[0], dims[1]);
+    let nz = dims.get(2).copied().unwrap_or(2);
+    let layers: &[usize] = if dims.len() == 2 { &[0] } else { &[0, 1] };
+    (0..nz - 1).flat_map(move |k| (0..ny - 1).flat_map(move |j| (0..nx - 1).map(move |i| {
+        let corners = layers.iter().flat_map(|dk| {
+            let at = |i: usize, j: usize| i + j * nx + (k + dk) * nx * ny;
+            [at(i, j), at(i + 1, j), at(i + 1, j + 1), at(i, j + 1)]
+        }).collect();
+        let index = if dims.len() == 2 { vec![i, j] } else { vec![i, j, k] };
+        (index, corners)
+    })))
+}
+
+/// The faces of a quad in 2D or a hexahedron in 3D, with their corners.
+pub(crate) fn cell_faces(dimensions: usize) -> Vec<(BoundaryType, Vec<usize>)> {
+    if dimensions == 2 {
+        QUAD_FACES.iter().map(|(face, local)| (*face, local.to_vec())).collect()
+    } else {
+        HEX_FACES.iter().map(|(face, local)| (*face, local.to_vec())).collect()
+    }
+}
+
+/// Whether a cell with the given index lies against a face of its block.
+pub(crate) fn on_face(face: BoundaryType, index: &[usize], dims: &[usize]) -> bool {
+    let axis = face.axis();
+    axis < index.len() && index[axis] == if face.at_max() { dims[axis] - 2 } else { 0 }
+}
+
+/// Values separated by single spaces, as a line of an ascii mesh file.
+pub(crate) fn join<T: ToString>(values: &[T]) -> String {
+    values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+fn flat_index(dims: &[usize], index: &[usize]) -> usize {
+    index.iter().zip(dims).rev().fold(0, |id, (i, n)| id * n + i)
+}
+
+fn find(parent: &mut [usize], mut p: usize) -> usize {
+    while parent[p] != p {
+        parent[p] = parent[parent[p]];
+        p = parent[p];
+    }
+    p
+}
+
+// the lower id becomes the root, so merged points keep the first position
+fn union(parent: &mut [usize], p: usize, q: usize) {
+    let (p, q) = (find(parent, p), find(parent, q));
+    if p != q {
+        parent[p.max(q)] = p.min(q);
+    }
+}
+
+// a uniform grid over the unit square, for the tests of the writers
+#[cfg(test)]
+pub(crate) fn unit_square(nx: usize, ny: usize) -> Vertices<Point2D> {
+    let mut vertices = Vertices::new_2d(nx, ny);
+    vertices.populate_uniform();
+    vertices
+}
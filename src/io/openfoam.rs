@@ -0,0 +1,408 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::geometry::prelude::*;
+use crate::io::{cells, on_face, BlockView, MergedPoints, HEX_FACES};
+use crate::mesh::block::Blocks;
+use crate::mesh::boundary::{BoundaryCondition, BoundaryType};
+
+/// A named group of boundary faces, stored together after the internal faces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patch {
+    pub name: String,
+    pub kind: &'static str,
+    pub start: usize,
+    pub count: usize,
+}
+
+/// Structured blocks as an OpenFOAM polyhedral mesh. Internal faces come first
+/// in upper triangular order, each pointing from its owner to its neighbour,
+/// and boundary faces follow grouped by patch, each pointing out of the mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolyMesh {
+    pub points: Vec<[f64; 3]>,
+    pub faces: Vec<[usize; 4]>,
+    pub owner: Vec<usize>,
+    pub neighbour: Vec<usize>,
+    pub patches: Vec<Patch>,
+    pub cells: usize,
+}
+
+/// The patch type OpenFOAM expects for a boundary condition. Faces without a
+/// condition are written as generic patches.
+pub fn patch_type(condition: Option<BoundaryCondition>) -> &'static str {
+    match condition {
+        Some(BoundaryCondition::Wall) => "wall",
+        Some(BoundaryCondition::Symmetry) => "symmetry",
+        _ => "patch",
+    }
+}
+
+// a face of a cell, on a block boundary until a second cell shares it
+struct CellFace {
+    points: [usize; 4],
+    owner: usize,
+    neighbour: Option<usize>,
+    boundary: Option<(usize, BoundaryType)>,
+}
+
+impl Vertices<Point2D> {
+    /// Writes the grid to `case/constant/polyMesh`, extruded one cell of the
+    /// given thickness in z, with empty front and back patches.
+    pub fn export_openfoam(&self, case: &str, thickness: f64) -> Result<(), &'static str> {
+        PolyMesh::new(&[BlockView::of_vertices(self)], Some(thickness))?.write(case)
+    }
+}
+
+impl Vertices<Point3D> {
+    /// Writes the grid to `case/constant/polyMesh`.
+    pub fn export_openfoam(&self, case: &str) -> Result<(), &'static str> {
+        PolyMesh::new(&[BlockView::of_vertices(self)], None)?.write(case)
+    }
+}
+
+impl Blocks<Point2D> {
+    /// Writes the blocks as one mesh to `case/constant/polyMesh`, extruded one
+    /// cell of the given thickness in z. Faces shared by blocks become internal.
+    pub fn export_openfoam(&self, case: &str, thickness: f64) -> Result<(), &'static str> {
+        PolyMesh::new(&BlockView::of_blocks(self), Some(thickness))?.write(case)
+    }
+}
+
+impl Blocks<Point3D> {
+    /// Writes the blocks as one mesh to `case/constant/polyMesh`. Faces shared
+    /// by blocks become internal.
+    pub fn export_openfoam(&self, case: &str) -> Result<(), &'static str> {
+        PolyMesh::new(&BlockView::of_blocks(self), None)?.write(case)
+    }
+}
+
+impl PolyMesh {
+    /// Builds the mesh from blocks of vertices, extruding 2D blocks by
+    /// `thickness`. Patches take the names of the block faces, so faces of
+    /// different blocks given the same name or condition share a patch, which
+    /// fails if they would give it different patch types.
+    pub fn new<P: Point>(blocks: &[BlockView<P>], thickness: Option<f64>) -> Result<Self, &'static str> {
+        if blocks.is_empty() {
+            return Err("no blocks to write");
+        }
+        let dimensions = blocks[0].vertices.dimensions();
+        match (dimensions, thickness) {
+            (2, Some(thickness)) if thickness <= 0.0 => return Err("extrusion thickness must be positive"),
+            (2, Some(_)) | (3, None) => {}
+            _ => return Err("2D grids need an extrusion thickness and 3D grids must not have one"),
+        }
+
+        // merge the points of faces that meet, whether between blocks or across a cut,
+        // and extrude 2D points into a back layer and a front layer
+        let merged = MergedPoints::new(blocks)?;
+        let layer = merged.points.len();
+        let mut points = merged.points.clone();
+        if let Some(thickness) = thickness {
+            points.extend(merged.points.iter().map(|p| [p[0], p[1], thickness]));
+        }
+
+        // walk the cells in order, so a face seen a second time is internal and
+        // was first seen from its owner, the lower numbered cell
+        let mut seen: HashMap<[usize; 4], usize> = HashMap::new();
+        let mut faces: Vec<CellFace> = Vec::new();
+        let mut count = 0;
+        for (b, block) in blocks.iter().enumerate() {
+            let dims = block.vertices.shape();
+            for (index, corners) in cells(&dims) {
+                let mut corners: Vec<usize> = corners.iter().map(|c| merged.numbering[b][*c]).collect();
+                if thickness.is_some() {
+                    corners.extend(corners.clone().iter().map(|c| c + layer));
+                }
+                for (face, local) in HEX_FACES {
+                    let points = local.map(|c| corners[c]);
+                    let mut key = points;
+                    key.sort_unstable();
+                    if let Some(&first) = seen.get(&key) {
+                        faces[first].neighbour = Some(count);
+                        faces[first].boundary = None;
+                        continue;
+                    }
+                    let on_boundary = on_face(face, &index, &dims) || face.axis() >= dims.len();
+                    seen.insert(key, faces.len());
+                    faces.push(CellFace { points, owner: count, neighbour: None, boundary: on_boundary.then_some((b, face)) });
+                }
+                count += 1;
+            }
+        }
+        if faces.iter().any(|f| f.neighbour.is_none() && f.boundary.is_none()) {
+            return Err("cell face is neither shared nor on a block boundary");
+        }
+
+        // name each patch, with 2D front and back faces gathered into two empty patches
+        let patch_key = |(b, face): (usize, BoundaryType)| -> (String, &'static str) {
+            match (thickness, face) {
+                (Some(_), BoundaryType::Bottom) => ("back".to_string(), "empty"),
+                (Some(_), BoundaryType::Top) => ("front".to_string(), "empty"),
+                _ => (blocks[b].name(face), patch_type(blocks[b].condition(face))),
+            }
+        };
+
+        let mut internal: Vec<_> = faces.iter().filter(|f| f.neighbour.is_some()).collect();
+        internal.sort_by_key(|f| (f.owner, f.neighbour));
+        let mut mesh = PolyMesh { points, faces: Vec::new(), owner: Vec::new(), neighbour: Vec::new(), patches: Vec::new(), cells: count };
+        for face in internal {
+            mesh.faces.push(face.points);
+            mesh.owner.push(face.owner);
+            mesh.neighbour.push(face.neighbour.unwrap());
+        }
+
+        // one patch per name, so every face left on a boundary is written once
+        let mut order: Vec<(String, &'static str)> = Vec::new();
+        for b in 0..blocks.len() {
+            for face in BoundaryType::faces(3) {
+                if !faces.iter().any(|f| f.boundary == Some((b, *face))) {
+                    continue;
+                }
+                let (name, kind) = patch_key((b, *face));
+                match order.iter().find(|(n, _)| *n == name) {
+                    Some((_, k)) if *k != kind => return Err("faces sharing a patch name have different patch types"),
+                    Some(_) => {}
+                    None => order.push((name, kind)),
+                }
+            }
+        }
+        for (name, kind) in order {
+            let start = mesh.faces.len();
+            for face in faces.iter().filter(|f| f.boundary.is_some_and(|at| patch_key(at).0 == name)) {
+                mesh.faces.push(face.points);
+                mesh.owner.push(face.owner);
+            }
+            let count = mesh.faces.len() - start;
+            if count > 0 {
+                mesh.patches.push(Patch { name, kind, start, count });
+            }
+        }
+        Ok(mesh)
+    }
+
+    /// Writes the points, faces, owner, neighbour and boundary files to
+    /// `case/constant/polyMesh`, creating the directories if needed.
+    pub fn write(&self, case: &str) -> Result<(), &'static str> {
+        let directory = Path::new(case).join("constant").join("polyMesh");
+        fs::create_dir_all(&directory).map_err(|_| "failed to create polyMesh directory")?;
+        let note = format!(
+            "nPoints: {} nCells: {} nFaces: {} nInternalFaces: {}",
+            self.points.len(), self.cells, self.faces.len(), self.neighbour.len()
+        );
+
+        write_foam_file(&directory, "points", "vectorField", None, |w| {
+            write_list(w, &self.points, |w, p| writeln!(w, "({} {} {})", p[0], p[1], p[2]))
+        })?;
+        write_foam_file(&directory, "faces", "faceList", None, |w| {
+            write_list(w, &self.faces, |w, f| writeln!(w, "4({} {} {} {})", f[0], f[1], f[2], f[3]))
+        })?;
+        write_foam_file(&directory, "owner", "labelList", Some(&note), |w| {
+            write_list(w, &self.owner, |w, c| writeln!(w, "{}", c))
+        })?;
+        write_foam_file(&directory, "neighbour", "labelList", Some(&note), |w| {
+            write_list(w, &self.neighbour, |w, c| writeln!(w, "{}", c))
+        })?;
+        write_foam_file(&directory, "boundary", "polyBoundaryMesh", None, |w| {
+            write_list(w, &self.patches, |w, patch| {
+                writeln!(w, "    {}", patch.name)?;
+                writeln!(w, "    {{")?;
+                writeln!(w, "        type            {};", patch.kind)?;
+                if patch.kind != "patch" {
+                    writeln!(w, "        inGroups        List<word> 1({});", patch.kind)?;
+                }
+                writeln!(w, "        nFaces          {};", patch.count)?;
+                writeln!(w, "        startFace       {};", patch.start)?;
+                writeln!(w, "    }}")
+            })
+        })
+    }
+}
+
+fn write_foam_file(
+    directory: &Path,
+    object: &str,
+    class: &str,
+    note: Option<&str>,
+    body: impl FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+) -> Result<(), &'static str> {
+    let file = File::create(directory.join(object)).map_err(|_| "failed to create file")?;
+    let mut writer = BufWriter::new(file);
+    write_header(&mut writer, object, class, note)
+        .and_then(|_| body(&mut writer))
+        .and_then(|_| writer.flush())
+        .map_err(|_| "failed to write polyMesh file")
+}
+
+fn write_header(writer: &mut impl Write, object: &str, class: &str, note: Option<&str>) -> std::io::Result<()> {
+    writeln!(writer, "FoamFile")?;
+    writeln!(writer, "{{")?;
+    writeln!(writer, "    version     2.0;")?;
+    writeln!(writer, "    format      ascii;")?;
+    writeln!(writer, "    class       {};", class)?;
+    if let Some(note) = note {
+        writeln!(writer, "    note        \"{}\";", note)?;
+    }
+    writeln!(writer, "    location    \"constant/polyMesh\";")?;
+    writeln!(writer, "    object      {};", object)?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)
+}
+
+fn write_list<T, W: Write>(writer: &mut W, items: &[T], mut item: impl FnMut(&mut W, &T) -> std::io::Result<()>) -> std::io::Result<()> {
+    writeln!(writer, "{}", items.len())?;
+    writeln!(writer, "(")?;
+    for entry in items {
+        item(writer, entry)?;
+    }
+    writeln!(writer, ")")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::unit_square;
+    use crate::mesh::block::{Block, BlockBuilder};
+
+    // the sum of each cell's outward area vectors, which closes only when the
+    // faces are wound consistently with their owners and neighbours
+    fn closure(mesh: &PolyMesh) -> f64 {
+        let mut sums = vec![[0.0; 3]; mesh.cells];
+        for (n, face) in mesh.faces.iter().enumerate() {
+            let p = face.map(|id| mesh.points[id]);
+            let d1 = [p[2][0] - p[0][0], p[2][1] - p[0][1], p[2][2] - p[0][2]];
+            let d2 = [p[3][0] - p[1][0], p[3][1] - p[1][1], p[3][2] - p[1][2]];
+            let s = [d1[1] * d2[2] - d1[2] * d2[1], d1[2] * d2[0] - d1[0] * d2[2], d1[0] * d2[1] - d1[1] * d2[0]];
+            for c in 0..3 {
+                sums[mesh.owner[n]][c] += s[c];
+                if let Some(neighbour) = mesh.neighbour.get(n) {
+                    sums[*neighbour][c] -= s[c];
+                }
+            }
+        }
+        sums.iter().flatten().fold(0.0, |worst: f64, s| worst.max(s.abs()))
+    }
+
+    #[test]
+    fn test_extruded_2d_mesh_counts_and_patches() {
+        let mesh = PolyMesh::new(&[BlockView::of_vertices(&unit_square(4, 3))], Some(0.1)).unwrap();
+        assert_eq!((mesh.points.len(), mesh.cells), (24, 6));
+        // two faces between columns in each of two rows, and three between the rows
+        assert_eq!(mesh.neighbour.len(), 7);
+        assert_eq!(mesh.faces.len(), 7 + 2 * 2 + 2 * 3 + 2 * 6);
+
+        let names: Vec<&str> = mesh.patches.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["west", "east", "south", "north", "back", "front"]);
+        assert_eq!(mesh.patches[4].kind, "empty");
+        assert_eq!(mesh.patches[5].start + mesh.patches[5].count, mesh.faces.len());
+        assert!(closure(&mesh) < 1e-12);
+
+        // internal faces are sorted by owner then neighbour
+        let pairs: Vec<_> = mesh.owner.iter().zip(&mesh.neighbour).collect();
+        assert!(pairs.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_blocks_merge_across_interfaces() {
+        let left = BlockBuilder::new_2d(0)
+            .dimensions(Dimensions::Two { nx: 3, ny: 3 })
+            .corners(Point2D::new(0.0, 0.0), Point2D::new(1.0, 0.0), Point2D::new(1.0, 1.0), Point2D::new(0.0, 1.0))
+            .condition(BoundaryType::South, BoundaryCondition::Wall)
+            .build()
+            .unwrap();
+        // the right block is turned a quarter, so its north face meets the left block's east face
+        let right = BlockBuilder::new_2d(1)
+            .dimensions(Dimensions::Two { nx: 3, ny: 3 })
+            .corners(Point2D::new(2.0, 0.0), Point2D::new(2.0, 1.0), Point2D::new(1.0, 1.0), Point2D::new(1.0, 0.0))
+            .build()
+            .unwrap();
+        let mut blocks = Blocks::new();
+        blocks.add_block(left).unwrap();
+        blocks.add_block(right).unwrap();
+
+        let mesh = PolyMesh::new(&BlockView::of_blocks(&blocks), Some(1.0)).unwrap();
+        assert_eq!(mesh.points.len(), 2 * 15);
+        assert_eq!(mesh.neighbour.len(), 2 * 4 + 2);
+        assert!(closure(&mesh) < 1e-12);
+
+        let wall = mesh.patches.iter().find(|p| p.name == "wall").unwrap();
+        assert_eq!((wall.kind, wall.count), ("wall", 2));
+        // the unnamed west faces of both blocks share a patch
+        let west = mesh.patches.iter().find(|p| p.name == "west").unwrap();
+        assert_eq!((west.kind, west.count), ("patch", 4));
+        // the left block's east face is internal, so only the right block's is left
+        assert_eq!(mesh.patches.iter().find(|p| p.name == "east").unwrap().count, 2);
+    }
+
+    #[test]
+    fn test_o_grid_cut_becomes_internal() {
+        let (nx, ny, nz) = (9, 3, 2);
+        let mut vertices = Vertices::new_3d(nx, ny, nz);
+        for k in 0..nz {
+            for j in 0..ny {
+                for i in 0..nx {
+                    let theta = 2.0 * std::f64::consts::PI * i as f64 / (nx - 1) as f64;
+                    let r = 1.0 + j as f64;
+                    vertices.add_vertex(Vertex::new_3d(i + j * nx + k * nx * ny, r * theta.cos(), r * theta.sin(), k as f64)).unwrap();
+                }
+            }
+        }
+        let mesh = PolyMesh::new(&[BlockView::of_vertices(&vertices)], None).unwrap();
+        assert_eq!(mesh.points.len(), 8 * 3 * 2);
+        assert!(mesh.patches.iter().all(|p| p.name != "west" && p.name != "east"));
+        assert!(closure(&mesh) < 1e-12);
+        assert!(PolyMesh::new(&[BlockView::of_vertices(&vertices)], Some(1.0)).is_err());
+    }
+
+    #[test]
+    fn test_faces_sharing_a_name_share_one_patch() {
+        let named = |north: BoundaryCondition, south: Option<BoundaryCondition>, name: &str| {
+            let mut block = Block::new_2d(0, unit_square(3, 3)).unwrap();
+            block.set_condition(BoundaryType::North, north).unwrap();
+            if let Some(south) = south {
+                block.set_condition(BoundaryType::South, south).unwrap();
+            }
+            block.set_name(BoundaryType::South, name).unwrap();
+            let mut blocks = Blocks::new();
+            blocks.add_block(block).unwrap();
+            PolyMesh::new(&BlockView::of_blocks(&blocks), Some(1.0))
+        };
+
+        let mesh = named(BoundaryCondition::Wall, Some(BoundaryCondition::Wall), "wall").unwrap();
+        assert_eq!(mesh.faces.len(), 4 + 2 * 4 + 2 * 4);
+        let walls: Vec<_> = mesh.patches.iter().filter(|p| p.name == "wall").collect();
+        assert_eq!(walls.len(), 1);
+        assert_eq!((walls[0].kind, walls[0].count), ("wall", 4));
+
+        // the same name for a wall and a plain patch, or for a side and the empty front
+        assert!(named(BoundaryCondition::Wall, None, "wall").is_err());
+        assert!(named(BoundaryCondition::Wall, None, "front").is_err());
+    }
+
+    #[test]
+    fn test_export_openfoam_writes_poly_mesh() {
+        let mut block = Block::new_2d(0, unit_square(3, 2)).unwrap();
+        block.set_condition(BoundaryType::North, BoundaryCondition::Symmetry).unwrap();
+        block.set_name(BoundaryType::North, "inlet_wall").unwrap();
+        block.set_condition(BoundaryType::South, BoundaryCondition::Wall).unwrap();
+        let mut blocks = Blocks::new();
+        blocks.add_block(block).unwrap();
+        blocks.export_openfoam("test_foam_case", 0.5).unwrap();
+
+        let directory = Path::new("test_foam_case/constant/polyMesh");
+        let boundary = fs::read_to_string(directory.join("boundary")).expect("should be able to read the file");
+        assert!(boundary.contains("    inlet_wall\n    {\n        type            symmetry;"));
+        assert!(boundary.contains("    wall\n    {\n        type            wall;"));
+        assert!(boundary.contains("type            empty;"));
+        let owner = fs::read_to_string(directory.join("owner")).expect("should be able to read the file");
+        assert!(owner.contains("note        \"nPoints: 12 nCells: 2 nFaces: 11 nInternalFaces: 1\";"));
+        for file in ["points", "faces", "neighbour"] {
+            assert!(directory.join(file).exists());
+        }
+        fs::remove_dir_all("test_foam_case").expect("failed to clean up test files");
+    }
+}
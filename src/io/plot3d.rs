@@ -0,0 +1,595 @@
+#![allow(dead_code)]
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+
+use crate::geometry::prelude::*;
+use crate::io::{join, Grid};
+use crate::mesh::block::Blocks;
+
+// values per line in ascii files
+const ASCII_COLUMNS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    Ascii,
+    Binary,       // a plain byte stream
+    #[default]
+    Unformatted,  // fortran sequential, each record wrapped in length markers
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    #[default]
+    Whole,  // all x, then all y, then all z for each block
+    Planes, // x, y and z of each k plane in turn
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteOrder {
+    #[default]
+    Little,
+    Big,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    Single,
+    #[default]
+    Double,
+}
+
+/// How a Plot3D grid file is written. Two dimensional grids are written as 2D
+/// Plot3D, with two dimensions per block and no z, which ParaView and Tecplot
+/// both read when told the file is 2D.
+#[derive(Debug, Clone)]
+pub struct Plot3DOptions {
+    pub encoding: Encoding,
+    pub layout: Layout,
+    pub byte_order: ByteOrder,
+    pub precision: Precision,
+    pub multi_block: bool, // write the block count, required for more than one block
+}
+
+impl Default for Plot3DOptions {
+    fn default() -> Self {
+        Plot3DOptions {
+            encoding: Encoding::Unformatted,
+            layout: Layout::Whole,
+            byte_order: ByteOrder::Little,
+            precision: Precision::Double,
+            multi_block: true,
+        }
+    }
+}
+
+impl Vertices<Point2D> {
+    pub fn export_plot3d(&self, filename: &str, options: &Plot3DOptions) -> Result<(), &'static str> {
+        write_file(filename, &[Grid::from_2d(self)?], options)
+    }
+
+    /// Reads every block of a 2D Plot3D file, finding how it was written from
+    /// the file itself and taking `options` only where it reads more than one way.
+    pub fn import_plot3d(filename: &str, options: &Plot3DOptions) -> Result<Vec<Self>, &'static str> {
+        read_file(filename, 2, options)?.iter().map(Grid::to_2d).collect()
+    }
+}
+
+impl Vertices<Point3D> {
+    pub fn export_plot3d(&self, filename: &str, options: &Plot3DOptions) -> Result<(), &'static str> {
+        write_file(filename, &[Grid::from_3d(self)?], options)
+    }
+
+    /// Reads every block of a 3D Plot3D file, finding how it was written from
+    /// the file itself and taking `options` only where it reads more than one way.
+    pub fn import_plot3d(filename: &str, options: &Plot3DOptions) -> Result<Vec<Self>, &'static str> {
+        read_file(filename, 3, options)?.iter().map(Grid::to_3d).collect()
+    }
+}
+
+impl Blocks<Point2D> {
+    pub fn export_plot3d(&self, filename: &str, options: &Plot3DOptions) -> Result<(), &'static str> {
+        let grids = self.blocks().iter().map(|b| Grid::from_2d(&b.vertices)).collect::<Result<Vec<_>, _>>()?;
+        write_file(filename, &grids, options)
+    }
+}
+
+impl Blocks<Point3D> {
+    pub fn export_plot3d(&self, filename: &str, options: &Plot3DOptions) -> Result<(), &'static str> {
+        let grids = self.blocks().iter().map(|b| Grid::from_3d(&b.vertices)).collect::<Result<Vec<_>, _>>()?;
+        write_file(filename, &grids, options)
+    }
+}
+
+fn write_file(filename: &str, grids: &[Grid], options: &Plot3DOptions) -> Result<(), &'static str> {
+    let file = File::create(filename).map_err(|_| "failed to create file")?;
+    let mut writer = BufWriter::new(file);
+    write_plot3d(&mut writer, grids, options)?;
+    writer.flush().map_err(|_| "failed to write plot3d file")
+}
+
+/// Writes the grids as one Plot3D file. All grids must share a dimension.
+pub fn write_plot3d(writer: &mut impl Write, grids: &[Grid], options: &Plot3DOptions) -> Result<(), &'static str> {
+    if grids.is_empty() {
+        return Err("no grids to write");
+    }
+    if grids.iter().any(|g| g.dims.len() != grids[0].dims.len()) {
+        return Err("can't write 2D and 3D grids to the same plot3d file");
+    }
+    if grids.len() > 1 && !options.multi_block {
+        return Err("more than one block requires a multi-block plot3d file");
+    }
+
+    let records = records(grids, options);
+    match options.encoding {
+        Encoding::Ascii => write_ascii(writer, &records),
+        Encoding::Binary | Encoding::Unformatted => write_binary(writer, &records, options),
+    }
+    .map_err(|_| "failed to write plot3d file")
+}
+
+enum Record {
+    Integers(Vec<i32>),
+    Reals(Vec<f64>),
+}
+
+// the file as the fortran records it would be written in
+fn records(grids: &[Grid], options: &Plot3DOptions) -> Vec<Record> {
+    let mut records = Vec::new();
+    if options.multi_block {
+        records.push(Record::Integers(vec![grids.len() as i32]));
+    }
+    records.push(Record::Integers(grids.iter().flat_map(|g| g.dims.iter().map(|d| *d as i32)).collect()));
+
+    for grid in grids {
+        match options.layout {
+            Layout::Planes if grid.dims.len() == 3 => {
+                let plane = grid.dims[0] * grid.dims[1];
+                for k in 0..grid.dims[2] {
+                    let range = k * plane..(k + 1) * plane;
+                    records.push(Record::Reals(grid.coords.iter().flat_map(|c| c[range.clone()].iter().copied()).collect()));
+                }
+            }
+            // a 2D grid is a single plane, so both layouts agree
+            _ => records.push(Record::Reals(grid.coords.iter().flatten().copied().collect())),
+        }
+    }
+    records
+}
+
+fn write_ascii(writer: &mut impl Write, records: &[Record]) -> std::io::Result<()> {
+    for record in records {
+        match record {
+            Record::Integers(values) => writeln!(writer, "{}", join(values))?,
+            Record::Reals(values) => {
+                for chunk in values.chunks(ASCII_COLUMNS) {
+                    let line: Vec<String> = chunk.iter().map(|v| format!("{:.16e}", v)).collect();
+                    writeln!(writer, "{}", line.join(" "))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_binary(writer: &mut impl Write, records: &[Record], options: &Plot3DOptions) -> std::io::Result<()> {
+    let big = options.byte_order == ByteOrder::Big;
+    let int = |v: i32| if big { v.to_be_bytes() } else { v.to_le_bytes() };
+
+    for record in records {
+        let mut bytes = Vec::new();
+        match record {
+            Record::Integers(values) => values.iter().for_each(|v| bytes.extend(int(*v))),
+            Record::Reals(values) => match options.precision {
+                Precision::Single => values.iter().for_each(|v| {
+                    let v = *v as f32;
+                    bytes.extend(if big { v.to_be_bytes() } else { v.to_le_bytes() })
+                }),
+                Precision::Double => values.iter().for_each(|v| {
+                    bytes.extend(if big { v.to_be_bytes() } else { v.to_le_bytes() })
+                }),
+            },
+        }
+
+        if options.encoding == Encoding::Unformatted {
+            let marker = int(bytes.len() as i32);
+            writer.write_all(&marker)?;
+            writer.write_all(&bytes)?;
+            writer.write_all(&marker)?;
+        } else {
+            writer.write_all(&bytes)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_file(filename: &str, dimensions: usize, options: &Plot3DOptions) -> Result<Vec<Grid>, &'static str> {
+    let bytes = fs::read(filename).map_err(|_| "failed to read file")?;
+    read_plot3d(&bytes, dimensions, &detect_plot3d(&bytes, dimensions, options)?)
+}
+
+/// Finds how a Plot3D file of the given dimension was written by reading it
+/// every way it could have been. Unformatted files give themselves away in
+/// their record markers, 4 before a block count or 4 per dimension before a
+/// single block's sizes, in either byte order, and in the lengths of the
+/// records of reals after them. Binary and ascii files only read cleanly when
+/// their counts fit the size of the file. Where the file still reads more than
+/// one way, such as the layout of a binary or ascii 3D file, the options that
+/// agree most with `fallback` are kept.
+pub fn detect_plot3d(bytes: &[u8], dimensions: usize, fallback: &Plot3DOptions) -> Result<Plot3DOptions, &'static str> {
+    let mut candidates = Vec::new();
+    for encoding in [Encoding::Unformatted, Encoding::Binary, Encoding::Ascii] {
+        // ascii files have no byte order or precision, and a 2D grid is a single
+        // plane so both layouts read the same
+        let (byte_orders, precisions) = match encoding {
+            Encoding::Ascii => (vec![fallback.byte_order], vec![fallback.precision]),
+            _ => (vec![ByteOrder::Little, ByteOrder::Big], vec![Precision::Double, Precision::Single]),
+        };
+        let layouts = if dimensions == 3 { vec![Layout::Whole, Layout::Planes] } else { vec![fallback.layout] };
+        for byte_order in &byte_orders {
+            for precision in &precisions {
+                for layout in &layouts {
+                    for multi_block in [true, false] {
+                        let options = Plot3DOptions { encoding, layout: *layout, byte_order: *byte_order, precision: *precision, multi_block };
+                        if read_plot3d(bytes, dimensions, &options).is_ok() {
+                            candidates.push(options);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let agreement = |c: &Plot3DOptions| {
+        [c.encoding == fallback.encoding, c.layout == fallback.layout, c.byte_order == fallback.byte_order,
+            c.precision == fallback.precision, c.multi_block == fallback.multi_block]
+            .iter()
+            .filter(|same| **same)
+            .count()
+    };
+    // the first of the best, as max_by_key keeps the last
+    match candidates.into_iter().rev().max_by_key(agreement) {
+        Some(options) => Ok(options),
+        // none read cleanly, so say what is wrong with the file as the options describe it
+        None => read_plot3d(bytes, dimensions, fallback).map(|_| fallback.clone()),
+    }
+}
+
+/// Reads the grids of a Plot3D file of the given dimension, read back in the
+/// same records as `write_plot3d` writes them.
+pub fn read_plot3d(bytes: &[u8], dimensions: usize, options: &Plot3DOptions) -> Result<Vec<Grid>, &'static str> {
+    let mut source = Source::new(bytes, options)?;
+    let blocks = if options.multi_block { source.integers(1)?[0] } else { 1 };
+    if blocks < 1 {
+        return Err("plot3d file has no blocks");
+    }
+    let sizes = source.integers(blocks as usize * dimensions)?;
+    if sizes.iter().any(|n| *n < 1) {
+        return Err("invalid plot3d grid dimensions");
+    }
+
+    let mut grids = Vec::with_capacity(blocks as usize);
+    for dims in sizes.chunks(dimensions) {
+        let dims: Vec<usize> = dims.iter().map(|n| *n as usize).collect();
+        // a wrong byte order or encoding reads as huge dimensions, so check them
+        // against what is left of the file before allocating
+        let points = dims.iter().try_fold(1usize, |total, n| total.checked_mul(*n)).ok_or("plot3d file ends early")?;
+        if points.checked_mul(dimensions).is_none_or(|values| values > source.reals_left()) {
+            return Err("plot3d file ends early");
+        }
+        let mut coords = vec![Vec::with_capacity(points); dimensions];
+        match options.layout {
+            Layout::Planes if dimensions == 3 => {
+                let plane = dims[0] * dims[1];
+                for _ in 0..dims[2] {
+                    let values = source.reals(3 * plane)?;
+                    for (c, component) in values.chunks(plane).enumerate() {
+                        coords[c].extend_from_slice(component);
+                    }
+                }
+            }
+            _ => {
+                let values = source.reals(dimensions * points)?;
+                for (c, component) in values.chunks(points).enumerate() {
+                    coords[c] = component.to_vec();
+                }
+            }
+        }
+        grids.push(Grid { dims, coords });
+    }
+
+    if !source.is_finished() {
+        return Err("unexpected data after the last plot3d block");
+    }
+    Ok(grids)
+}
+
+// the values of a file, read a record at a time
+struct Source<'a> {
+    bytes: &'a [u8],
+    tokens: Vec<&'a str>, // ascii files only
+    at: usize,
+    options: &'a Plot3DOptions,
+}
+
+impl<'a> Source<'a> {
+    fn new(bytes: &'a [u8], options: &'a Plot3DOptions) -> Result<Self, &'static str> {
+        let tokens = match options.encoding {
+            Encoding::Ascii => std::str::from_utf8(bytes).map_err(|_| "ascii plot3d file is not text")?.split_whitespace().collect(),
+            _ => Vec::new(),
+        };
+        Ok(Source { bytes, tokens, at: 0, options })
+    }
+
+    fn integers(&mut self, count: usize) -> Result<Vec<i32>, &'static str> {
+        if self.options.encoding == Encoding::Ascii {
+            return self.tokens(count)?.iter().map(|t| t.parse().map_err(|_| "invalid integer in plot3d file")).collect();
+        }
+        let bytes = self.record(count, 4)?;
+        Ok(bytes.chunks(4).map(|b| self.int(b)).collect())
+    }
+
+    fn reals(&mut self, count: usize) -> Result<Vec<f64>, &'static str> {
+        if self.options.encoding == Encoding::Ascii {
+            // fortran writes exponents with a d as often as an e
+            return self.tokens(count)?.iter()
+                .map(|t| t.replace(['d', 'D'], "e").parse().map_err(|_| "invalid real in plot3d file"))
+                .collect();
+        }
+        let big = self.options.byte_order == ByteOrder::Big;
+        let values = match self.options.precision {
+            Precision::Single => self.record(count, 4)?.chunks(4).map(|b| {
+                let b = b.try_into().unwrap();
+                (if big { f32::from_be_bytes(b) } else { f32::from_le_bytes(b) }) as f64
+            }).collect(),
+            Precision::Double => self.record(count, 8)?.chunks(8).map(|b| {
+                let b = b.try_into().unwrap();
+                if big { f64::from_be_bytes(b) } else { f64::from_le_bytes(b) }
+            }).collect(),
+        };
+        Ok(values)
+    }
+
+    // an upper bound on the reals left to read, ignoring record markers
+    fn reals_left(&self) -> usize {
+        match (self.options.encoding, self.options.precision) {
+            (Encoding::Ascii, _) => self.tokens.len() - self.at,
+            (_, Precision::Single) => (self.bytes.len() - self.at) / 4,
+            (_, Precision::Double) => (self.bytes.len() - self.at) / 8,
+        }
+    }
+
+    fn tokens(&mut self, count: usize) -> Result<&[&'a str], &'static str> {
+        let end = self.at.checked_add(count).filter(|end| *end <= self.tokens.len()).ok_or("plot3d file ends early")?;
+        let tokens = &self.tokens[self.at..end];
+        self.at = end;
+        Ok(tokens)
+    }
+
+    // the bytes of `count` values, checked against the record markers when unformatted
+    fn record(&mut self, count: usize, width: usize) -> Result<&'a [u8], &'static str> {
+        let length = count.checked_mul(width).ok_or("plot3d file ends early")?;
+        if self.options.encoding == Encoding::Unformatted {
+            let marker = self.take(4)?;
+            if self.int(marker) as usize != length {
+                return Err("plot3d record length does not match the grid dimensions");
+            }
+        }
+        let bytes = self.take(length)?;
+        if self.options.encoding == Encoding::Unformatted {
+            let marker = self.take(4)?;
+            if self.int(marker) as usize != length {
+                return Err("plot3d record markers do not match");
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], &'static str> {
+        let end = self.at.checked_add(length).filter(|end| *end <= self.bytes.len()).ok_or("plot3d file ends early")?;
+        let bytes = &self.bytes[self.at..end];
+        self.at = end;
+        Ok(bytes)
+    }
+
+    fn int(&self, bytes: &[u8]) -> i32 {
+        let bytes = bytes.try_into().unwrap();
+        if self.options.byte_order == ByteOrder::Big { i32::from_be_bytes(bytes) } else { i32::from_le_bytes(bytes) }
+    }
+
+    fn is_finished(&self) -> bool {
+        match self.options.encoding {
+            Encoding::Ascii => self.at == self.tokens.len(),
+            _ => self.at == self.bytes.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::unit_square;
+    use std::fs;
+
+    fn unit_cube() -> Vertices<Point3D> {
+        let mut vertices = Vertices::new_3d(2, 2, 2);
+        vertices.populate_uniform();
+        vertices
+    }
+
+    fn i32_at(bytes: &[u8], offset: usize) -> i32 {
+        i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_ascii_single_block_2d() {
+        let options = Plot3DOptions { encoding: Encoding::Ascii, multi_block: false, ..Default::default() };
+        let mut out = Vec::new();
+        write_plot3d(&mut out, &[Grid::from_2d(&unit_square(2, 2)).unwrap()], &options).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "2 2");
+        let values: Vec<f64> = lines[1..].iter().flat_map(|l| l.split_whitespace()).map(|v| v.parse().unwrap()).collect();
+        assert_eq!(values, vec![0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_unformatted_multi_block_records() {
+        let grids = [Grid::from_3d(&unit_cube()).unwrap(), Grid::from_3d(&unit_cube()).unwrap()];
+        let mut out = Vec::new();
+        write_plot3d(&mut out, &grids, &Plot3DOptions::default()).unwrap();
+
+        // block count, then six dimensions, then two blocks of 24 doubles, each wrapped in markers
+        assert_eq!((i32_at(&out, 0), i32_at(&out, 4), i32_at(&out, 8)), (4, 2, 4));
+        assert_eq!((i32_at(&out, 12), i32_at(&out, 16), i32_at(&out, 40)), (24, 2, 24));
+        assert_eq!(i32_at(&out, 44), 192);
+        assert_eq!(out.len(), 12 + 32 + 2 * (8 + 192));
+    }
+
+    #[test]
+    fn test_planes_big_endian_single_precision() {
+        let options = Plot3DOptions {
+            layout: Layout::Planes,
+            byte_order: ByteOrder::Big,
+            precision: Precision::Single,
+            multi_block: false,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_plot3d(&mut out, &[Grid::from_3d(&unit_cube()).unwrap()], &options).unwrap();
+
+        // dims record, then one record of x, y and z per k plane
+        assert_eq!(out.len(), (8 + 12) + 2 * (8 + 48));
+        assert_eq!(i32::from_be_bytes(out[20..24].try_into().unwrap()), 48);
+        // z of the second plane is all ones
+        let z: Vec<f32> = out[20 + 56 + 4 + 32..20 + 56 + 4 + 48].chunks(4).map(|b| f32::from_be_bytes(b.try_into().unwrap())).collect();
+        assert_eq!(z, vec![1.0; 4]);
+    }
+
+    #[test]
+    fn test_plot3d_rejects_bad_combinations() {
+        let grids = [Grid::from_2d(&unit_square(2, 2)).unwrap(), Grid::from_2d(&unit_square(2, 2)).unwrap()];
+        let single = Plot3DOptions { multi_block: false, ..Default::default() };
+        assert!(write_plot3d(&mut Vec::new(), &grids, &single).is_err());
+
+        let mixed = [Grid::from_2d(&unit_square(2, 2)).unwrap(), Grid::from_3d(&unit_cube()).unwrap()];
+        assert!(write_plot3d(&mut Vec::new(), &mixed, &Plot3DOptions::default()).is_err());
+        assert!(Grid::from_2d(&Vertices::new_2d(2, 2)).is_err());
+    }
+
+    #[test]
+    fn test_read_back_every_encoding() {
+        let mut cube = Vertices::new_3d(3, 2, 4);
+        cube.populate_uniform();
+        let grids = [Grid::from_3d(&cube).unwrap(), Grid::from_3d(&unit_cube()).unwrap()];
+        let encodings = [Encoding::Ascii, Encoding::Binary, Encoding::Unformatted];
+        for (encoding, layout) in encodings.into_iter().flat_map(|e| [(e, Layout::Whole), (e, Layout::Planes)]) {
+            let options = Plot3DOptions { encoding, layout, byte_order: ByteOrder::Big, ..Default::default() };
+            let mut out = Vec::new();
+            write_plot3d(&mut out, &grids, &options).unwrap();
+
+            let read = read_plot3d(&out, 3, &options).unwrap();
+            assert_eq!(read.len(), 2);
+            assert_eq!(read[0].dims, vec![3, 2, 4]);
+            assert_eq!(read[0].coords, grids[0].coords);
+            assert_eq!(read[1].coords, grids[1].coords);
+        }
+    }
+
+    #[test]
+    fn test_read_ascii_with_fortran_exponents() {
+        let options = Plot3DOptions { encoding: Encoding::Ascii, multi_block: false, ..Default::default() };
+        let text = "2 2\n0.0D+00 1.0D+00 0.0 1.0\n0.0 0.0 5.0d-01 5.0E-01\n";
+        let grids = read_plot3d(text.as_bytes(), 2, &options).unwrap();
+        let vertices = grids[0].to_2d().unwrap();
+        assert_eq!(vertices.nx_ny(), (2, 2));
+        assert_eq!(vertices.coordinates(), vec![0.0, 0.0, 1.0, 0.0, 0.0, 0.5, 1.0, 0.5]);
+
+        // a short file or one with values left over is not the grid it claims to be
+        assert!(read_plot3d("2 2\n0 1 0 1\n0 0 1".as_bytes(), 2, &options).is_err());
+        assert!(read_plot3d("2 2\n0 1 0 1\n0 0 1 1 7".as_bytes(), 2, &options).is_err());
+    }
+
+    #[test]
+    fn test_read_rejects_mismatched_records() {
+        let options = Plot3DOptions { multi_block: false, ..Default::default() };
+        let mut out = Vec::new();
+        write_plot3d(&mut out, &[Grid::from_2d(&unit_square(2, 2)).unwrap()], &options).unwrap();
+        assert!(read_plot3d(&out, 2, &options).is_ok());
+
+        // read as 3D, the dims record holds two integers where three are expected
+        assert_eq!(read_plot3d(&out, 3, &options).unwrap_err(), "plot3d record length does not match the grid dimensions");
+        let single = Plot3DOptions { precision: Precision::Single, ..options };
+        assert!(read_plot3d(&out, 2, &single).is_err());
+
+        // in the wrong byte order 2 reads as 33554432, far more points than the file holds
+        let binary = Plot3DOptions { encoding: Encoding::Binary, byte_order: ByteOrder::Little, ..options };
+        let mut out = Vec::new();
+        write_plot3d(&mut out, &[Grid::from_3d(&unit_cube()).unwrap()], &binary).unwrap();
+        let swapped = Plot3DOptions { byte_order: ByteOrder::Big, ..binary };
+        assert_eq!(read_plot3d(&out, 3, &swapped).unwrap_err(), "plot3d file ends early");
+        assert_eq!(read_plot3d(&out[..40], 3, &binary).unwrap_err(), "plot3d file ends early");
+    }
+
+    #[test]
+    fn test_detect_how_files_were_written() {
+        let mut cube = Vertices::new_3d(3, 2, 4);
+        cube.populate_uniform();
+        let grids = [Grid::from_3d(&cube).unwrap()];
+        // nothing here matches how the files below are written
+        let wrong = Plot3DOptions {
+            encoding: Encoding::Ascii,
+            layout: Layout::Planes,
+            byte_order: ByteOrder::Big,
+            precision: Precision::Single,
+            multi_block: false,
+        };
+
+        for byte_order in [ByteOrder::Little, ByteOrder::Big] {
+            for precision in [Precision::Single, Precision::Double] {
+                for layout in [Layout::Whole, Layout::Planes] {
+                    for multi_block in [true, false] {
+                        let options = Plot3DOptions { encoding: Encoding::Unformatted, layout, byte_order, precision, multi_block };
+                        let mut out = Vec::new();
+                        write_plot3d(&mut out, &grids, &options).unwrap();
+                        let found = detect_plot3d(&out, 3, &wrong).unwrap();
+                        assert_eq!(
+                            (found.encoding, found.layout, found.byte_order, found.precision, found.multi_block),
+                            (Encoding::Unformatted, layout, byte_order, precision, multi_block)
+                        );
+                    }
+                }
+            }
+        }
+
+        // binary files are found from their size, but their layout is taken as given
+        let binary = Plot3DOptions { encoding: Encoding::Binary, layout: Layout::Whole, byte_order: ByteOrder::Little, precision: Precision::Double, multi_block: true };
+        let mut out = Vec::new();
+        write_plot3d(&mut out, &grids, &binary).unwrap();
+        let found = detect_plot3d(&out, 3, &wrong).unwrap();
+        assert_eq!((found.encoding, found.byte_order, found.precision, found.multi_block), (Encoding::Binary, ByteOrder::Little, Precision::Double, true));
+        assert_eq!(found.layout, Layout::Planes);
+
+        let ascii = Plot3DOptions { encoding: Encoding::Ascii, multi_block: false, ..Default::default() };
+        let mut out = Vec::new();
+        write_plot3d(&mut out, &[Grid::from_2d(&unit_square(3, 2)).unwrap()], &ascii).unwrap();
+        let found = detect_plot3d(&out, 2, &Plot3DOptions::default()).unwrap();
+        assert_eq!((found.encoding, found.multi_block), (Encoding::Ascii, false));
+
+        // a file that reads no way at all reports what is wrong with it as given
+        assert_eq!(detect_plot3d(&out[..20], 2, &ascii).unwrap_err(), "plot3d file ends early");
+    }
+
+    #[test]
+    fn test_export_plot3d_file() {
+        let filename = "test_grid.xyz";
+        unit_cube().export_plot3d(filename, &Plot3DOptions { encoding: Encoding::Binary, ..Default::default() }).unwrap();
+        let bytes = fs::read(filename).expect("should be able to read the file");
+        assert_eq!(bytes.len(), 4 + 12 + 24 * 8);
+
+        let read = Vertices::<Point3D>::import_plot3d(filename, &Plot3DOptions::default()).unwrap();
+        assert_eq!(read[0].coordinates(), unit_cube().coordinates());
+        fs::remove_file(filename).expect("failed to clean up test file");
+    }
+}
@@ -0,0 +1,224 @@
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::geometry::prelude::*;
+use crate::io::{cell_faces, cells, join, on_face, BlockView, MergedPoints};
+use crate::mesh::block::Blocks;
+
+// element type ids, shared with VTK
+const LINE: usize = 3;
+const QUADRILATERAL: usize = 9;
+const HEXAHEDRON: usize = 12;
+
+impl<P: Point> Vertices<P> {
+    /// Writes the grid as an SU2 mesh, with a marker for each face of the grid.
+    pub fn export_su2(&self, filename: &str) -> Result<(), &'static str> {
+        write_file(filename, &[BlockView::of_vertices(self)])
+    }
+}
+
+impl<P: Point> Blocks<P> {
+    /// Writes the blocks as one SU2 mesh. Faces shared by blocks are merged,
+    /// and the rest are gathered into markers by name, so faces of different
+    /// blocks given the same name or condition share a marker.
+    pub fn export_su2(&self, filename: &str) -> Result<(), &'static str> {
+        write_file(filename, &BlockView::of_blocks(self))
+    }
+}
+
+fn write_file<P: Point>(filename: &str, blocks: &[BlockView<P>]) -> Result<(), &'static str> {
+    let file = File::create(filename).map_err(|_| "failed to create file")?;
+    let mut writer = BufWriter::new(file);
+    write_su2(&mut writer, blocks)?;
+    writer.flush().map_err(|_| "failed to write su2 file")
+}
+
+/// Writes blocks as an ascii SU2 mesh of quads in 2D or hexahedra in 3D.
+pub fn write_su2<P: Point>(writer: &mut impl Write, blocks: &[BlockView<P>]) -> Result<(), &'static str> {
+    if blocks.is_empty() {
+        return Err("no blocks to write");
+    }
+    let dimensions = blocks[0].vertices.dimensions();
+    let merged = MergedPoints::new(blocks)?;
+
+    let mut elements = Vec::new();
+    let mut markers: Vec<(String, Vec<Vec<usize>>)> = Vec::new();
+    for (b, block) in blocks.iter().enumerate() {
+        let dims = block.vertices.shape();
+        for (index, corners) in cells(&dims) {
+            let corners: Vec<usize> = corners.iter().map(|c| merged.numbering[b][*c]).collect();
+
+            // boundary elements keep the outward winding of the cell's face
+            for (face, local) in cell_faces(dimensions) {
+                if !on_face(face, &index, &dims) || merged.is_matched(block.id, face, &index) {
+                    continue;
+                }
+                let name = block.name(face);
+                let element = local.iter().map(|c| corners[*c]).collect();
+                match markers.iter_mut().find(|(existing, _)| *existing == name) {
+                    Some((_, marker)) => marker.push(element),
+                    None => markers.push((name, vec![element])),
+                }
+            }
+            elements.push(corners);
+        }
+    }
+
+    write_sections(writer, dimensions, &elements, &merged.points, &markers).map_err(|_| "failed to write su2 file")
+}
+
+fn write_sections(
+    writer: &mut impl Write,
+    dimensions: usize,
+    elements: &[Vec<usize>],
+    points: &[[f64; 3]],
+    markers: &[(String, Vec<Vec<usize>>)],
+) -> std::io::Result<()> {
+    let (volume, surface) = if dimensions == 2 { (QUADRILATERAL, LINE) } else { (HEXAHEDRON, QUADRILATERAL) };
+
+    writeln!(writer, "NDIME= {}", dimensions)?;
+    writeln!(writer, "NELEM= {}", elements.len())?;
+    for (n, element) in elements.iter().enumerate() {
+        writeln!(writer, "{} {} {}", volume, join(element), n)?;
+    }
+
+    writeln!(writer, "NPOIN= {}", points.len())?;
+    for (n, point) in points.iter().enumerate() {
+        writeln!(writer, "{} {}", join(&point[..dimensions]), n)?;
+    }
+
+    writeln!(writer, "NMARK= {}", markers.len())?;
+    for (name, elements) in markers {
+        writeln!(writer, "MARKER_TAG= {}", name)?;
+        writeln!(writer, "MARKER_ELEMS= {}", elements.len())?;
+        for element in elements {
+            writeln!(writer, "{} {}", surface, join(element))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::unit_square;
+    use crate::mesh::block::{Block, BlockBuilder};
+    use crate::mesh::boundary::{BoundaryCondition, BoundaryType};
+    use std::fs;
+
+    fn su2(blocks: &[BlockView<Point2D>]) -> String {
+        let mut out = Vec::new();
+        write_su2(&mut out, blocks).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    // the lines of a section, after its header
+    fn section<'a>(text: &'a str, header: &str) -> Vec<&'a str> {
+        let start = text.lines().position(|l| l.starts_with(header)).unwrap();
+        let count: usize = text.lines().nth(start).unwrap()[header.len()..].trim().parse().unwrap();
+        text.lines().skip(start + 1).take(count).collect()
+    }
+
+    #[test]
+    fn test_2d_sections() {
+        let text = su2(&[BlockView::of_vertices(&unit_square(3, 2))]);
+        assert!(text.starts_with("NDIME= 2\n"));
+        assert_eq!(section(&text, "NELEM="), vec!["9 0 1 4 3 0", "9 1 2 5 4 1"]);
+        assert_eq!(section(&text, "NPOIN=")[5], "1 1 5");
+        assert!(text.contains("NMARK= 4\n"));
+
+        // boundary lines run with the domain on their left
+        let south = text.split("MARKER_TAG= south\n").nth(1).unwrap();
+        assert!(south.starts_with("MARKER_ELEMS= 2\n3 0 1\n3 1 2\n"));
+        let west = text.split("MARKER_TAG= west\n").nth(1).unwrap();
+        assert!(west.starts_with("MARKER_ELEMS= 1\n3 3 0\n"));
+    }
+
+    #[test]
+    fn test_markers_come_from_boundary_definitions() {
+        let left = BlockBuilder::new_2d(0)
+            .dimensions(Dimensions::Two { nx: 3, ny: 2 })
+            .corners(Point2D::new(0.0, 0.0), Point2D::new(1.0, 0.0), Point2D::new(1.0, 1.0), Point2D::new(0.0, 1.0))
+            .name(BoundaryType::North, "inlet_wall")
+            .condition(BoundaryType::North, BoundaryCondition::Wall)
+            .condition(BoundaryType::South, BoundaryCondition::Symmetry)
+            .build()
+            .unwrap();
+        let right = BlockBuilder::new_2d(1)
+            .dimensions(Dimensions::Two { nx: 2, ny: 2 })
+            .corners(Point2D::new(1.0, 0.0), Point2D::new(2.0, 0.0), Point2D::new(2.0, 1.0), Point2D::new(1.0, 1.0))
+            .condition(BoundaryType::East, BoundaryCondition::Outflow)
+            .condition(BoundaryType::South, BoundaryCondition::Symmetry)
+            .build()
+            .unwrap();
+        let mut blocks = Blocks::new();
+        blocks.add_block(left).unwrap();
+        blocks.add_block(right).unwrap();
+        let text = su2(&BlockView::of_blocks(&blocks));
+
+        let tags: Vec<&str> = text.lines().filter_map(|l| l.strip_prefix("MARKER_TAG= ")).collect();
+        assert_eq!(tags, vec!["west", "symmetry", "inlet_wall", "outflow", "north"]);
+        let symmetry = text.split("MARKER_TAG= symmetry\n").nth(1).unwrap();
+        assert!(symmetry.starts_with("MARKER_ELEMS= 3\n"));
+        // the shared edge is merged, leaving 6 + 4 - 2 points
+        assert_eq!(section(&text, "NPOIN=").len(), 8);
+    }
+
+    #[test]
+    fn test_markers_skip_the_matched_part_of_a_face() {
+        // a block sits on the first two of the three cells of a wider block's north face
+        let rectangle = |id: usize, (x0, y0): (f64, f64), width: f64, nx: usize| {
+            BlockBuilder::new_2d(id)
+                .dimensions(Dimensions::Two { nx, ny: 2 })
+                .corners(
+                    Point2D::new(x0, y0),
+                    Point2D::new(x0 + width, y0),
+                    Point2D::new(x0 + width, y0 + 1.0),
+                    Point2D::new(x0, y0 + 1.0),
+                )
+                .build()
+                .unwrap()
+        };
+        let mut blocks = Blocks::new();
+        blocks.add_block(rectangle(0, (0.0, 0.0), 3.0, 4)).unwrap();
+        blocks.add_block(rectangle(1, (0.0, 1.0), 2.0, 3)).unwrap();
+        let text = su2(&BlockView::of_blocks(&blocks));
+
+        let north = text.split("MARKER_TAG= north\n").nth(1).unwrap();
+        assert!(north.starts_with("MARKER_ELEMS= 3\n3 7 6\n"));
+        let south = text.split("MARKER_TAG= south\n").nth(1).unwrap();
+        assert!(south.starts_with("MARKER_ELEMS= 3\n"));
+    }
+
+    #[test]
+    fn test_3d_hexahedra() {
+        let mut vertices = Vertices::new_3d(2, 2, 3);
+        vertices.populate_uniform();
+        let text = {
+            let mut out = Vec::new();
+            write_su2(&mut out, &[BlockView::of_vertices(&vertices)]).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert!(text.starts_with("NDIME= 3\n"));
+        assert_eq!(section(&text, "NELEM=")[1], "12 4 5 7 6 8 9 11 10 1");
+        assert_eq!(section(&text, "NPOIN=")[11], "1 1 1 11");
+        let top = text.split("MARKER_TAG= top\n").nth(1).unwrap();
+        assert!(top.starts_with("MARKER_ELEMS= 1\n9 8 9 11 10\n"));
+        assert!(text.contains("NMARK= 6\n"));
+    }
+
+    #[test]
+    fn test_export_su2_file() {
+        let mut block = Block::new_2d(3, unit_square(2, 2)).unwrap();
+        block.set_name(BoundaryType::East, "outflow").unwrap();
+        let mut blocks = Blocks::new();
+        blocks.add_block(block).unwrap();
+        blocks.export_su2("test_mesh.su2").unwrap();
+
+        let text = fs::read_to_string("test_mesh.su2").expect("should be able to read the file");
+        assert!(text.contains("MARKER_TAG= outflow\nMARKER_ELEMS= 1\n3 1 3\n"));
+        fs::remove_file("test_mesh.su2").expect("failed to clean up test file");
+    }
+}
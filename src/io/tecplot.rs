@@ -0,0 +1,210 @@
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::geometry::prelude::*;
+use crate::io::{join, DataArray, Fields, Grid};
+use crate::mesh::block::Blocks;
+
+const COORDINATE_NAMES: [&str; 3] = ["X", "Y", "Z"];
+
+// values per line, well under tecplot's limit on line length
+const VALUES_PER_LINE: usize = 5;
+
+/// One ordered zone of a Tecplot file: a grid and the fields on its points
+/// and cells.
+pub struct Zone<'a> {
+    pub name: String,
+    pub grid: Grid,
+    pub fields: &'a Fields,
+}
+
+impl<P: Point> Vertices<P> {
+    /// Writes the grid as a single IJ or IJK ordered zone.
+    pub fn export_tecplot(&self, filename: &str, fields: &Fields) -> Result<(), &'static str> {
+        let zone = Zone { name: "block_0".to_string(), grid: Grid::from_vertices(self)?, fields };
+        write_file(filename, &[zone])
+    }
+}
+
+impl<P: Point> Blocks<P> {
+    /// Writes each block as an ordered zone of one file. Fields are given per
+    /// block, or not at all, and every block must carry the same fields.
+    pub fn export_tecplot(&self, filename: &str, fields: &[Fields]) -> Result<(), &'static str> {
+        if !fields.is_empty() && fields.len() != self.len() {
+            return Err("fields must be given for every block or none");
+        }
+        let empty = Fields::new();
+        let zones = self.blocks().iter().enumerate()
+            .map(|(n, block)| Ok(Zone {
+                name: format!("block_{}", block.id),
+                grid: Grid::from_vertices(&block.vertices)?,
+                fields: fields.get(n).unwrap_or(&empty),
+            }))
+            .collect::<Result<Vec<_>, &'static str>>()?;
+        write_file(filename, &zones)
+    }
+}
+
+fn write_file(filename: &str, zones: &[Zone]) -> Result<(), &'static str> {
+    let file = File::create(filename).map_err(|_| "failed to create file")?;
+    let mut writer = BufWriter::new(file);
+    write_tecplot(&mut writer, "mesher", zones)?;
+    writer.flush().map_err(|_| "failed to write tecplot file")
+}
+
+/// Writes zones as an ascii Tecplot file in block packing. Each component of
+/// a vector field is its own variable, and cell fields are cell centred.
+pub fn write_tecplot(writer: &mut impl Write, title: &str, zones: &[Zone]) -> Result<(), &'static str> {
+    let first = zones.first().ok_or("no zones to write")?;
+    let dimensions = first.grid.dims.len();
+    for zone in zones {
+        zone.fields.check(&zone.grid)?;
+        if zone.grid.dims.len() != dimensions
+            || !same_layout(&zone.fields.point_data, &first.fields.point_data)
+            || !same_layout(&zone.fields.cell_data, &first.fields.cell_data)
+        {
+            return Err("every zone must carry the same fields");
+        }
+    }
+    write_zones(writer, title, zones).map_err(|_| "failed to write tecplot file")
+}
+
+fn same_layout(a: &[DataArray], b: &[DataArray]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.name == b.name && a.components == b.components)
+}
+
+// vector components are suffixed with their axis, or their index past three
+fn variable_names(array: &DataArray) -> Vec<String> {
+    if array.components == 1 {
+        return vec![array.name.clone()];
+    }
+    (0..array.components)
+        .map(|c| match array.components {
+            2 | 3 => format!("{}_{}", array.name, ["x", "y", "z"][c]),
+            _ => format!("{}_{}", array.name, c),
+        })
+        .collect()
+}
+
+fn write_zones(writer: &mut impl Write, title: &str, zones: &[Zone]) -> std::io::Result<()> {
+    let first = &zones[0];
+    let dimensions = first.grid.dims.len();
+    let point_names: Vec<String> = first.fields.point_data.iter().flat_map(variable_names).collect();
+    let cell_names: Vec<String> = first.fields.cell_data.iter().flat_map(variable_names).collect();
+    let names: Vec<String> = COORDINATE_NAMES[..dimensions].iter().map(|n| n.to_string())
+        .chain(point_names.iter().cloned())
+        .chain(cell_names.iter().cloned())
+        .collect();
+
+    writeln!(writer, "TITLE = \"{}\"", title)?;
+    let quoted: Vec<String> = names.iter().map(|n| format!("\"{}\"", n)).collect();
+    writeln!(writer, "VARIABLES = {}", quoted.join(" "))?;
+
+    // cell variables follow the coordinates and point variables, numbered from one
+    let centred = dimensions + point_names.len() + 1..=names.len();
+    for zone in zones {
+        let sizes: Vec<String> = zone.grid.dims.iter().zip(["I", "J", "K"]).map(|(n, axis)| format!("{}={}", axis, n)).collect();
+        write!(writer, "ZONE T=\"{}\", {}, DATAPACKING=BLOCK", zone.name, sizes.join(", "))?;
+        if !centred.is_empty() {
+            write!(writer, ", VARLOCATION=([{}-{}]=CELLCENTERED)", centred.start(), centred.end())?;
+        }
+        writeln!(writer)?;
+
+        for values in &zone.grid.coords {
+            write_values(writer, values)?;
+        }
+        for array in zone.fields.point_data.iter().chain(&zone.fields.cell_data) {
+            for c in 0..array.components {
+                let component: Vec<f64> = array.values.iter().skip(c).step_by(array.components).copied().collect();
+                write_values(writer, &component)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_values(writer: &mut impl Write, values: &[f64]) -> std::io::Result<()> {
+    for line in values.chunks(VALUES_PER_LINE) {
+        writeln!(writer, "{}", join(line))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::unit_square;
+    use crate::mesh::block::Block;
+    use crate::mesh::nodes::Nodes;
+    use std::fs;
+
+    fn tecplot(zones: &[Zone]) -> String {
+        let mut out = Vec::new();
+        write_tecplot(&mut out, "test", zones).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_ij_zone_with_point_and_cell_data() {
+        let vertices = unit_square(3, 2);
+        let mut nodes = Nodes::new_2d();
+        nodes.populate(&vertices).unwrap();
+        let report = nodes.quality(&vertices).unwrap();
+        let fields = Fields::new()
+            .point_data(DataArray::scalars("wall_distance", vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0]))
+            .cell_data(report.cell_data());
+        let text = tecplot(&[Zone { name: "block_0".to_string(), grid: Grid::from_2d(&vertices).unwrap(), fields: &fields }]);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[0], "TITLE = \"test\"");
+        assert!(lines[1].starts_with("VARIABLES = \"X\" \"Y\" \"wall_distance\" \"scaled_jacobian\" \"skewness\""));
+        assert_eq!(lines[2], "ZONE T=\"block_0\", I=3, J=2, DATAPACKING=BLOCK, VARLOCATION=([4-8]=CELLCENTERED)");
+        // x wraps after five values, then y, then the distance
+        assert_eq!(lines[3..8], ["0 0.5 1 0 0.5", "1", "0 0 0 1 1", "1", "0 0 0 1 1"]);
+        // two cells per cell variable
+        assert_eq!(lines.len(), 3 + 6 + 5);
+    }
+
+    #[test]
+    fn test_ijk_zone_splits_vectors() {
+        let mut vertices = Vertices::new_3d(2, 2, 2);
+        vertices.populate_uniform();
+        let fields = Fields::new().point_data(DataArray::vectors("velocity", 3, (0..24).map(|v| v as f64).collect()));
+        let text = tecplot(&[Zone { name: "block_0".to_string(), grid: Grid::from_3d(&vertices).unwrap(), fields: &fields }]);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[1], "VARIABLES = \"X\" \"Y\" \"Z\" \"velocity_x\" \"velocity_y\" \"velocity_z\"");
+        assert_eq!(lines[2], "ZONE T=\"block_0\", I=2, J=2, K=2, DATAPACKING=BLOCK");
+        // each component is written on its own, after the three coordinates
+        assert_eq!(lines[3 + 6], "0 3 6 9 12");
+        assert_eq!(lines[3 + 8], "1 4 7 10 13");
+    }
+
+    #[test]
+    fn test_zones_must_share_fields() {
+        let vertices = unit_square(2, 2);
+        let wall = Fields::new().point_data(DataArray::scalars("wall_distance", vec![0.0; 4]));
+        let none = Fields::new();
+        let zone = |fields| Zone { name: "block".to_string(), grid: Grid::from_2d(&vertices).unwrap(), fields };
+        assert_eq!(write_tecplot(&mut Vec::new(), "test", &[zone(&wall), zone(&none)]).unwrap_err(), "every zone must carry the same fields");
+
+        let short = Fields::new().point_data(DataArray::scalars("wall_distance", vec![0.0; 3]));
+        assert!(write_tecplot(&mut Vec::new(), "test", &[zone(&short)]).is_err());
+    }
+
+    #[test]
+    fn test_export_tecplot_writes_every_block() {
+        let mut blocks = Blocks::new();
+        blocks.add_block(Block::new_2d(0, unit_square(2, 2)).unwrap()).unwrap();
+        blocks.add_block(Block::new_2d(7, unit_square(3, 3)).unwrap()).unwrap();
+        blocks.export_tecplot("test_blocks.dat", &[]).unwrap();
+
+        let text = fs::read_to_string("test_blocks.dat").expect("should be able to read the file");
+        assert!(text.contains("ZONE T=\"block_0\", I=2, J=2, DATAPACKING=BLOCK\n"));
+        assert!(text.contains("ZONE T=\"block_7\", I=3, J=3, DATAPACKING=BLOCK\n"));
+        assert!(blocks.export_tecplot("test_blocks.dat", &[Fields::new()]).is_err());
+        fs::remove_file("test_blocks.dat").expect("failed to clean up test file");
+    }
+}
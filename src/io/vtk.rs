@@ -0,0 +1,244 @@
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::geometry::prelude::*;
+use crate::io::{DataArray, Fields, Grid};
+use crate::mesh::block::Blocks;
+use crate::mesh::boundary::BoundaryType;
+
+/// The tag written for points on a face of the block, zero inside it.
+pub fn boundary_tag(face: BoundaryType) -> f64 {
+    match face {
+        BoundaryType::North => 1.0,
+        BoundaryType::South => 2.0,
+        BoundaryType::East => 3.0,
+        BoundaryType::West => 4.0,
+        BoundaryType::Top => 5.0,
+        BoundaryType::Bottom => 6.0,
+    }
+}
+
+/// Tags the points of a grid with the face of the block they lie on. Where
+/// faces meet the j faces win over the i faces, and the k faces over both.
+pub fn boundary_tags(dims: &[usize]) -> DataArray {
+    let (nx, ny) = (dims[0], dims[1]);
+    let nz = dims.get(2).copied().unwrap_or(1);
+    let mut tags = Vec::with_capacity(nx * ny * nz);
+    for k in 0..nz {
+        for j in 0..ny {
+            for i in 0..nx {
+                let mut tag = 0.0;
+                if i == 0 { tag = boundary_tag(BoundaryType::West) }
+                if i == nx - 1 { tag = boundary_tag(BoundaryType::East) }
+                if j == 0 { tag = boundary_tag(BoundaryType::South) }
+                if j == ny - 1 { tag = boundary_tag(BoundaryType::North) }
+                if nz > 1 && k == 0 { tag = boundary_tag(BoundaryType::Bottom) }
+                if nz > 1 && k == nz - 1 { tag = boundary_tag(BoundaryType::Top) }
+                tags.push(tag);
+            }
+        }
+    }
+    DataArray::scalars("boundary", tags)
+}
+
+impl Vertices<Point2D> {
+    pub fn export_vts(&self, filename: &str, fields: &Fields) -> Result<(), &'static str> {
+        write_file(filename, &Grid::from_2d(self)?, fields)
+    }
+}
+
+impl Vertices<Point3D> {
+    pub fn export_vts(&self, filename: &str, fields: &Fields) -> Result<(), &'static str> {
+        write_file(filename, &Grid::from_3d(self)?, fields)
+    }
+}
+
+impl Blocks<Point2D> {
+    /// Writes each block to its own `.vts` file beside `filename` and indexes
+    /// them in `filename` as a `.vtm`. Fields are given per block, or not at all.
+    pub fn export_vtm(&self, filename: &str, fields: &[Fields]) -> Result<(), &'static str> {
+        let grids = self.blocks().iter().map(|b| Grid::from_2d(&b.vertices)).collect::<Result<Vec<_>, _>>()?;
+        let ids: Vec<usize> = self.blocks().iter().map(|b| b.id).collect();
+        write_multi_block(filename, &ids, &grids, fields)
+    }
+}
+
+impl Blocks<Point3D> {
+    /// Writes each block to its own `.vts` file beside `filename` and indexes
+    /// them in `filename` as a `.vtm`. Fields are given per block, or not at all.
+    pub fn export_vtm(&self, filename: &str, fields: &[Fields]) -> Result<(), &'static str> {
+        let grids = self.blocks().iter().map(|b| Grid::from_3d(&b.vertices)).collect::<Result<Vec<_>, _>>()?;
+        let ids: Vec<usize> = self.blocks().iter().map(|b| b.id).collect();
+        write_multi_block(filename, &ids, &grids, fields)
+    }
+}
+
+fn write_file(filename: &str, grid: &Grid, fields: &Fields) -> Result<(), &'static str> {
+    let file = File::create(filename).map_err(|_| "failed to create file")?;
+    let mut writer = BufWriter::new(file);
+    write_vts(&mut writer, grid, fields)?;
+    writer.flush().map_err(|_| "failed to write vtk file")
+}
+
+fn write_multi_block(filename: &str, ids: &[usize], grids: &[Grid], fields: &[Fields]) -> Result<(), &'static str> {
+    if !fields.is_empty() && fields.len() != grids.len() {
+        return Err("fields must be given for every block or none");
+    }
+    let path = Path::new(filename);
+    let stem = path.file_stem().and_then(|s| s.to_str()).ok_or("invalid vtm filename")?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let empty = Fields::new();
+    let mut pieces = Vec::with_capacity(grids.len());
+    for (n, (id, grid)) in ids.iter().zip(grids).enumerate() {
+        let piece = format!("{}_{}.vts", stem, id);
+        let piece_path = directory.join(&piece);
+        write_file(piece_path.to_str().ok_or("invalid vtm filename")?, grid, fields.get(n).unwrap_or(&empty))?;
+        pieces.push((*id, piece));
+    }
+
+    let file = File::create(filename).map_err(|_| "failed to create file")?;
+    let mut writer = BufWriter::new(file);
+    write_vtm(&mut writer, &pieces)
+        .and_then(|_| writer.flush())
+        .map_err(|_| "failed to write vtk file")
+}
+
+/// Writes a grid as an ascii XML StructuredGrid. 2D grids are written as a
+/// single plane at z = 0.
+pub fn write_vts(writer: &mut impl Write, grid: &Grid, fields: &Fields) -> Result<(), &'static str> {
+    fields.check(grid)?;
+    write_structured_grid(writer, grid, fields).map_err(|_| "failed to write vtk file")
+}
+
+fn write_structured_grid(writer: &mut impl Write, grid: &Grid, fields: &Fields) -> std::io::Result<()> {
+    let nz = grid.dims.get(2).copied().unwrap_or(1);
+    let extent = format!("0 {} 0 {} 0 {}", grid.dims[0] - 1, grid.dims[1] - 1, nz - 1);
+
+    writeln!(writer, "<?xml version=\"1.0\"?>")?;
+    writeln!(writer, "<VTKFile type=\"StructuredGrid\" version=\"1.0\" byte_order=\"LittleEndian\">")?;
+    writeln!(writer, "  <StructuredGrid WholeExtent=\"{}\">", extent)?;
+    writeln!(writer, "    <Piece Extent=\"{}\">", extent)?;
+
+    writeln!(writer, "      <PointData>")?;
+    for array in &fields.point_data {
+        write_data_array(writer, &array.name, array.components, &array.values)?;
+    }
+    writeln!(writer, "      </PointData>")?;
+    writeln!(writer, "      <CellData>")?;
+    for array in &fields.cell_data {
+        write_data_array(writer, &array.name, array.components, &array.values)?;
+    }
+    writeln!(writer, "      </CellData>")?;
+
+    // vtk points always have three components
+    let coordinates: Vec<f64> = (0..grid.points())
+        .flat_map(|n| (0..3).map(move |c| grid.coords.get(c).map_or(0.0, |component| component[n])))
+        .collect();
+    writeln!(writer, "      <Points>")?;
+    write_data_array(writer, "Points", 3, &coordinates)?;
+    writeln!(writer, "      </Points>")?;
+
+    writeln!(writer, "    </Piece>")?;
+    writeln!(writer, "  </StructuredGrid>")?;
+    writeln!(writer, "</VTKFile>")
+}
+
+fn write_data_array(writer: &mut impl Write, name: &str, components: usize, values: &[f64]) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "        <DataArray type=\"Float64\" Name=\"{}\" NumberOfComponents=\"{}\" format=\"ascii\">",
+        name, components
+    )?;
+    for entry in values.chunks(components) {
+        let line: Vec<String> = entry.iter().map(|v| v.to_string()).collect();
+        writeln!(writer, "          {}", line.join(" "))?;
+    }
+    writeln!(writer, "        </DataArray>")
+}
+
+fn write_vtm(writer: &mut impl Write, pieces: &[(usize, String)]) -> std::io::Result<()> {
+    writeln!(writer, "<?xml version=\"1.0\"?>")?;
+    writeln!(writer, "<VTKFile type=\"vtkMultiBlockDataSet\" version=\"1.0\" byte_order=\"LittleEndian\">")?;
+    writeln!(writer, "  <vtkMultiBlockDataSet>")?;
+    for (index, (id, piece)) in pieces.iter().enumerate() {
+        writeln!(writer, "    <DataSet index=\"{}\" name=\"block_{}\" file=\"{}\"/>", index, id, piece)?;
+    }
+    writeln!(writer, "  </vtkMultiBlockDataSet>")?;
+    writeln!(writer, "</VTKFile>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::unit_square;
+    use crate::mesh::block::Block;
+    use crate::mesh::nodes::Nodes;
+    use std::fs;
+
+    #[test]
+    fn test_vts_2d_with_quality_cell_data() {
+        let vertices = unit_square(3, 2);
+        let mut nodes = Nodes::new_2d();
+        nodes.populate(&vertices).unwrap();
+        let report = nodes.quality(&vertices).unwrap();
+        let grid = Grid::from_2d(&vertices).unwrap();
+        let fields = Fields::new().point_data(boundary_tags(&grid.dims)).cell_data(report.cell_data());
+
+        let mut out = Vec::new();
+        write_vts(&mut out, &grid, &fields).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.contains("WholeExtent=\"0 2 0 1 0 0\""));
+        assert!(text.contains("Name=\"boundary\""));
+        assert!(text.contains("Name=\"scaled_jacobian\""));
+        // the last point sits at (1, 1) in the z = 0 plane
+        let points = text.split("Name=\"Points\"").nth(1).unwrap();
+        assert!(points.lines().nth(6).unwrap().trim() == "1 1 0");
+    }
+
+    #[test]
+    fn test_vts_rejects_mismatched_arrays() {
+        let grid = Grid::from_2d(&unit_square(3, 3)).unwrap();
+        let points = Fields::new().point_data(DataArray::scalars("p", vec![0.0; 8]));
+        assert!(write_vts(&mut Vec::new(), &grid, &points).is_err());
+
+        let cells = Fields::new().cell_data([DataArray::vectors("v", 2, vec![0.0; 8])]);
+        assert!(write_vts(&mut Vec::new(), &grid, &cells).is_ok());
+        let cells = Fields::new().cell_data([DataArray::vectors("v", 2, vec![0.0; 9])]);
+        assert!(write_vts(&mut Vec::new(), &grid, &cells).is_err());
+    }
+
+    #[test]
+    fn test_boundary_tags_3d() {
+        let tags = boundary_tags(&[3, 3, 3]);
+        assert_eq!(tags.values.len(), 27);
+        assert_eq!(tags.values[13], 0.0); // the centre
+        assert_eq!(tags.values[9 + 3], boundary_tag(BoundaryType::West));
+        assert_eq!(tags.values[9 + 1], boundary_tag(BoundaryType::South));
+        assert_eq!(tags.values[0], boundary_tag(BoundaryType::Bottom));
+        assert_eq!(tags.values[26], boundary_tag(BoundaryType::Top));
+    }
+
+    #[test]
+    fn test_export_vtm_writes_every_block() {
+        let mut blocks = Blocks::new();
+        blocks.add_block(Block::new_2d(0, unit_square(3, 3)).unwrap()).unwrap();
+        blocks.add_block(Block::new_2d(4, unit_square(2, 2)).unwrap()).unwrap();
+        assert!(blocks.export_vtm("test_blocks.vtm", &[Fields::new()]).is_err());
+
+        blocks.export_vtm("test_blocks.vtm", &[]).unwrap();
+        let index = fs::read_to_string("test_blocks.vtm").expect("should be able to read the file");
+        assert!(index.contains("file=\"test_blocks_0.vts\""));
+        assert!(index.contains("name=\"block_4\" file=\"test_blocks_4.vts\""));
+        let piece = fs::read_to_string("test_blocks_4.vts").expect("should be able to read the file");
+        assert!(piece.contains("WholeExtent=\"0 1 0 1 0 0\""));
+
+        for file in ["test_blocks.vtm", "test_blocks_0.vts", "test_blocks_4.vts"] {
+            fs::remove_file(file).expect("failed to clean up test file");
+        }
+    }
+}
@@ -1,60 +1,86 @@
 #![allow(dead_code)]
 
 mod geometry;
+mod io;
 mod mesh;
 mod utils;
 
 use geometry::prelude::*;
+use io::Fields;
+use mesh::block::{Block, Blocks};
+use mesh::boundary::{Boundary2D, BoundaryCondition, BoundaryType};
+use mesh::distribution::{Distribution, TopClusteredTangent};
 use mesh::nodes::Nodes;
+use mesh::wall_spacing::{Freestream, SkinFriction};
 use utils::plotting::plot_nodes_2d;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // sample the inlet wall once, every mesh below shares it
+    let inlet_wall = Boundary2D::function(inlet_contour);
+    let wall = inlet_wall.discretise(
+        BoundaryType::North,
+        &Point2D::new(0.0, inlet_contour(0.0)),
+        &Point2D::new(2.0, inlet_contour(2.0)),  // length in x direction
+        400,
+    )?;
+
+    // wall cell height for a y+ of 1 at the end of the inlet, flying at mach 6 at 30 km
+    let freestream = Freestream::new(1807.0, 0.0184, 1.47e-5);
+    let wall_cell = freestream.wall_spacing(2.0, 1.0, SkinFriction::White)?;
+
     // create uniform mesh
-    // let mut vertices_uniform = Vertices::new_2d(200, 100);
+    // let mut vertices_uniform = Vertices::new_2d(400, 200);
     // create_busemann_mesh_2d(
     //     &mut vertices_uniform,
-    //     2.0,  // length in x direction
-    //     WallDistribution::Uniform,
-    //     inlet_contour,
-    //     None,
+    //     &wall,
+    //     &Uniform,
     // );
     // let mut nodes_uniform = Nodes::new_2d();
     // nodes_uniform.populate(&vertices_uniform).expect("failed to populate uniform nodes");
-    // plot_nodes_2d(&nodes_uniform, "busemann_nodes_uniform.png", false)?;
+    // plot_nodes_2d(&nodes_uniform, &vertices_uniform, "busemann_nodes_uniform.png", false)?;
 
     // create hyperbolic tangent mesh
-    // let mut vertices_tanh = Vertices::new_2d(200, 100);
+    // let mut vertices_tanh = Vertices::new_2d(400, 200);
     // create_busemann_mesh_2d(
     //     &mut vertices_tanh,
-    //     2.0,
-    //     WallDistribution::HyperbolicTangent,
-    //     inlet_contour,
-    //     Some(1.5),  // beta parameter for clustering
+    //     &wall,
+    //     &HyperbolicTangent::new(1.5)?,  // beta parameter for clustering
     // );
     // let mut nodes_tanh = Nodes::new_2d();
     // nodes_tanh.populate(&vertices_tanh).expect("failed to populate tanh nodes");
-    // plot_nodes_2d(&nodes_tanh, "busemann_nodes_tanh.png", false)?;
+    // plot_nodes_2d(&nodes_tanh, &vertices_tanh, "busemann_nodes_tanh.png", false)?;
 
     // create top-clustered mesh
     let mut vertices_top = Vertices::new_2d(400, 200);
     create_busemann_mesh_2d(
         &mut vertices_top,
-        2.0,
-        WallDistribution::TopClusteredTangent,
-        inlet_contour,
-        Some(2.0),  // stronger clustering near top
-    );
+        &wall,
+        &TopClusteredTangent::from_spacing(1.0, 200, wall_cell)?,  // scaled to the tallest column
+    )?;
     let mut nodes_top = Nodes::new_2d();
     nodes_top.populate(&vertices_top).expect("failed to populate top-clustered nodes");
-    plot_nodes_2d(&nodes_top, "busemann_nodes_top.png", false)?;
 
-    Ok(())
-}
+    // catch cells folded by the wall clustering here rather than in the solver
+    let report = nodes_top.quality(&vertices_top)?;
+    println!("{}", report);
+    if report.has_folded_cells() {
+        return Err("top-clustered mesh has folded cells".into());
+    }
+    plot_nodes_2d(&nodes_top, &vertices_top, "busemann_nodes_top.png", false)?;
+    vertices_top.export_vts("busemann_nodes_top.vts", &Fields::new().cell_data(report.cell_data()))?;
+
+    // the wall is the top of the mesh and the centreline the bottom
+    let mut inlet = Blocks::new();
+    let mut block = Block::new_2d(0, vertices_top)?;
+    block.set_name(BoundaryType::North, "inlet_wall")?;
+    block.set_condition(BoundaryType::North, BoundaryCondition::Wall)?;
+    block.set_condition(BoundaryType::South, BoundaryCondition::Symmetry)?;
+    block.set_condition(BoundaryType::West, BoundaryCondition::Inflow)?;
+    block.set_condition(BoundaryType::East, BoundaryCondition::Outflow)?;
+    inlet.add_block(block)?;
+    inlet.export_su2("busemann_top.su2")?;
 
-enum WallDistribution {
-    Uniform,
-    HyperbolicTangent,
-    TopClusteredTangent,
+    Ok(())
 }
 
 fn inlet_contour(x: f64) -> f64 {
@@ -63,67 +89,21 @@ fn inlet_contour(x: f64) -> f64 {
 
 fn create_busemann_mesh_2d(
     vertices: &mut Vertices<Point2D>,
-    lenx: f64,
-    wall_distribution: WallDistribution,
-    inlet_contour: impl Fn(f64) -> f64,
-    beta: Option<f64>,
-) {
+    wall: &[Point2D],
+    distribution: &dyn Distribution,
+) -> Result<(), &'static str> {
     let (nx, ny) = vertices.nx_ny();
-    let dx: f64 = lenx / (nx as f64 - 1.0);
-
-    match wall_distribution {
-        WallDistribution::Uniform => {
-            for j in 0..ny {
-                for i in 0..nx {
-                    let x = i as f64 * dx;
-                    let leny = inlet_contour(x);
-                    let dy = leny / (ny as f64 - 1.0);
-                    let y = j as f64 * dy;
-                    
-                    let vertex_id = i + j * nx;
-                    vertices.add_vertex(Vertex::new_2d(vertex_id, x, y));
-                }
-            }
-        }
-        WallDistribution::HyperbolicTangent => {
-            let beta = beta.unwrap_or(2.0);
-
-            for j in 0..ny {
-                // calculate normalized coordinate eta between 0 and 1
-                let eta = j as f64 / (ny - 1) as f64;
-                
-                for i in 0..nx {
-                    let x = i as f64 * dx;
-                    let leny = inlet_contour(x);
-                    
-                    // apply hyperbolic tangent distribution without doubling the height
-                    let tanh_term = (beta * (2.0 * eta - 1.0)).tanh() / beta.tanh();
-                    let y = leny * (0.5 * (1.0 + tanh_term));  // Scale to [0, leny]
-
-                    let vertex_id = i + j * nx;
-                    vertices.add_vertex(Vertex::new_2d(vertex_id, x, y));
-                }
-            }
-        }
-        WallDistribution::TopClusteredTangent => {
-            let beta = beta.unwrap_or(2.0);
-
-            for j in 0..ny {
-                // calculate normalized coordinate eta between 0 and 1
-                let eta = j as f64 / (ny - 1) as f64;
-                
-                for i in 0..nx {
-                    let x = i as f64 * dx;
-                    let leny = inlet_contour(x);
-                    
-                    // top-boundary-clustered distribution
-                    let tanh_term = (beta * eta).tanh() / beta.tanh();
-                    let y = leny * tanh_term;
+    if wall.len() != nx {
+        return Err("wall must be sampled at one point per vertex column");
+    }
 
-                    let vertex_id = i + j * nx;
-                    vertices.add_vertex(Vertex::new_2d(vertex_id, x, y));
-                }
-            }
+    // every column shares the same distribution, scaled to the local wall height
+    let etas = distribution.sample(ny);
+    for (j, eta) in etas.iter().enumerate() {
+        for (i, point) in wall.iter().enumerate() {
+            let vertex_id = i + j * nx;
+            vertices.add_vertex(Vertex::new_2d(vertex_id, point.x(), point.y() * eta))?;
         }
     }
+    Ok(())
 }
@@ -1,33 +1,502 @@
 #![allow(dead_code)]
 
 use crate::geometry::prelude::*;
+use crate::geometry::vector::lerp;
+use crate::mesh::boundary::{Boundary2D, Boundary3D, BoundaryCondition, BoundaryType};
+use crate::mesh::distribution::{Distribution, Uniform};
 use crate::mesh::nodes::Nodes;
+use crate::mesh::tfi::{coons_patch_3d, Blending, BlockFaces};
 
-type BoundaryFn2D = Box<dyn Fn(f64) -> f64>;        // y = f(x)
-type BoundaryFn3D = Box<dyn Fn(f64) -> (f64, f64)>; // z = f(x, y)
+/// A structured block, owning both its vertices and the cells built on them.
+pub struct Block<P: Point> {
+    pub id: usize,
+    pub vertices: Vertices<P>,
+    pub nodes: Nodes<P>,
+    pub conditions: Vec<(BoundaryType, BoundaryCondition)>,
+    pub names: Vec<(BoundaryType, String)>,
+}
+
+impl<P: Point> Dimensioned for Block<P> {
+    fn is_2d(&self) -> bool {
+        self.vertices.is_2d()
+    }
 
-pub enum BoundaryType {
-    North,  // 2D
-    South,  // 2D
-    East,   // 2D
-    West,   // 2D
-    Top,    // 3D
-    Bottom, // 3D
+    fn dimensions(&self) -> usize {
+        self.vertices.dimensions()
+    }
 }
 
-pub struct Block<'a, P: Point> {
-    pub id: usize, 
-    pub vertices: Vertices<P>,
-    pub nodes: Nodes<'a, P>, 
+impl<P: Point> Block<P> {
+    /// The condition applied on a face, if one has been given.
+    pub fn condition(&self, face: BoundaryType) -> Option<BoundaryCondition> {
+        self.conditions.iter().find(|(f, _)| *f == face).map(|(_, c)| *c)
+    }
+
+    /// Applies a condition on a face, replacing any already given.
+    pub fn set_condition(&mut self, face: BoundaryType, condition: BoundaryCondition) -> Result<(), &'static str> {
+        if !BoundaryType::faces(self.dimensions()).contains(&face) {
+            return Err("top and bottom boundaries only apply to 3D blocks");
+        }
+        self.conditions.retain(|(existing, _)| *existing != face);
+        self.conditions.push((face, condition));
+        Ok(())
+    }
+
+    /// Names a face, such as `inlet_wall`, for the markers and patches of exported meshes.
+    pub fn set_name(&mut self, face: BoundaryType, name: &str) -> Result<(), &'static str> {
+        if !BoundaryType::faces(self.dimensions()).contains(&face) {
+            return Err("top and bottom boundaries only apply to 3D blocks");
+        }
+        self.names.retain(|(existing, _)| *existing != face);
+        self.names.push((face, name.to_string()));
+        Ok(())
+    }
+}
+
+impl Block<Point2D> {
+    /// Wraps fully populated vertices into a block, building its cells.
+    pub fn new_2d(id: usize, vertices: Vertices<Point2D>) -> Result<Self, &'static str> {
+        let mut nodes = Nodes::new_2d();
+        nodes.populate(&vertices)?;
+        Ok(Block { id, vertices, nodes, conditions: Vec::new(), names: Vec::new() })
+    }
 }
 
-pub struct BlockBuilder {
+impl Block<Point3D> {
+    /// Wraps fully populated vertices into a block, building its cells.
+    pub fn new_3d(id: usize, vertices: Vertices<Point3D>) -> Result<Self, &'static str> {
+        let mut nodes = Nodes::new_3d();
+        nodes.populate(&vertices)?;
+        Ok(Block { id, vertices, nodes, conditions: Vec::new(), names: Vec::new() })
+    }
+}
+
+pub struct Blocks<P: Point> {
+    blocks: Vec<Block<P>>,
+}
+
+impl<P: Point> Blocks<P> {
+    pub fn new() -> Self {
+        Blocks { blocks: Vec::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn add_block(&mut self, block: Block<P>) -> Result<(), &'static str> {
+        if self.get_block(block.id).is_some() {
+            return Err("a block with this id already exists");
+        }
+        if !self.is_empty() && block.dimensions() != self.blocks[0].dimensions() {
+            return Err("can't add block of differing dimensions to the collection");
+        }
+        self.blocks.push(block);
+        Ok(())
+    }
+
+    pub fn get_block(&self, id: usize) -> Option<&Block<P>> {
+        self.blocks.iter().find(|b| b.id == id)
+    }
+
+    pub fn blocks(&self) -> &Vec<Block<P>> {
+        &self.blocks
+    }
+}
+
+pub struct BlockBuilder<P: Point> {
     id: usize,
     dimensions: Option<Dimensions>,
+    corners: Option<Vec<P>>,
+    curves: Vec<(BoundaryType, Boundary2D)>,
+    surfaces: Vec<(BoundaryType, Boundary3D)>,
+    distributions: Vec<(BoundaryType, Box<dyn Distribution>)>,
+    columns: Option<Box<dyn Distribution>>,
+    conditions: Vec<(BoundaryType, BoundaryCondition)>,
+    names: Vec<(BoundaryType, String)>,
+    blending: Blending,
+}
+
+impl<P: Point> BlockBuilder<P> {
+    fn new(id: usize) -> Self {
+        BlockBuilder { id, dimensions: None, corners: None, curves: Vec::new(), surfaces: Vec::new(), distributions: Vec::new(), columns: None, conditions: Vec::new(), names: Vec::new(), blending: Blending::Linear }
+    }
+
+    pub fn dimensions(mut self, dimensions: Dimensions) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Applies a physical condition on a face of the block, carried through to
+    /// exported meshes. 3D blocks use all six faces, west and east for i.
+    pub fn condition(mut self, face: BoundaryType, condition: BoundaryCondition) -> Self {
+        self.conditions.retain(|(existing, _)| *existing != face);
+        self.conditions.push((face, condition));
+        self
+    }
+
+    /// Names a face, such as `inlet_wall`, for the markers and patches of exported meshes.
+    pub fn name(mut self, face: BoundaryType, name: &str) -> Self {
+        self.names.retain(|(existing, _)| *existing != face);
+        self.names.push((face, name.to_string()));
+        self
+    }
+
+    fn apply_conditions(&self, block: &mut Block<P>) -> Result<(), &'static str> {
+        for (face, condition) in &self.conditions {
+            block.set_condition(*face, *condition)?;
+        }
+        for (face, name) in &self.names {
+            block.set_name(*face, name)?;
+        }
+        Ok(())
+    }
 }
 
-impl BlockBuilder {
-    fn new(_id: usize) -> Self {
-        todo!()
+impl BlockBuilder<Point2D> {
+    pub fn new_2d(id: usize) -> Self {
+        BlockBuilder::new(id)
+    }
+
+    /// Corners are given anticlockwise from the origin of the block:
+    /// south-west, south-east, north-east, north-west.
+    pub fn corners(mut self, sw: Point2D, se: Point2D, ne: Point2D, nw: Point2D) -> Self {
+        self.corners = Some(vec![sw, se, ne, nw]);
+        self
+    }
+
+    /// Replaces the straight edge between two corners with a boundary curve,
+    /// which must pass through both corners of that face.
+    pub fn boundary(mut self, face: BoundaryType, boundary: Boundary2D) -> Self {
+        self.curves.retain(|(existing, _)| *existing != face);
+        self.curves.push((face, boundary));
+        self
     }
-}
\ No newline at end of file
+
+    /// Spaces the points along one edge with a distribution rather than evenly.
+    /// Distributions run from the south or west corner of the edge.
+    pub fn distribution(mut self, face: BoundaryType, distribution: impl Distribution + 'static) -> Self {
+        self.distributions.retain(|(existing, _)| *existing != face);
+        self.distributions.push((face, Box::new(distribution)));
+        self
+    }
+
+    /// Sets how the edges are blended into the interior of the block.
+    pub fn blending(mut self, blending: Blending) -> Self {
+        self.blending = blending;
+        self
+    }
+
+    pub fn build(self) -> Result<Block<Point2D>, &'static str> {
+        let (nx, ny) = match self.dimensions {
+            Some(Dimensions::Two { nx, ny }) => (nx, ny),
+            Some(Dimensions::Three { .. }) => return Err("2D block requires 2D dimensions"),
+            None => return Err("block dimensions have not been set"),
+        };
+        if nx < 2 || ny < 2 {
+            return Err("block requires at least two vertices in each direction");
+        }
+        let corners = self.corners.as_ref().ok_or("block corners have not been set")?;
+        let (sw, se, ne, nw) = (&corners[0], &corners[1], &corners[2], &corners[3]);
+
+        for face in self.curves.iter().map(|(f, _)| f).chain(self.distributions.iter().map(|(f, _)| f)) {
+            if matches!(face, BoundaryType::Top | BoundaryType::Bottom) {
+                return Err("top and bottom boundaries only apply to 3D blocks");
+            }
+        }
+
+        // discretise each edge, straight between corners unless a curve was given
+        let south = self.edge(BoundaryType::South, sw, se, nx)?;
+        let north = self.edge(BoundaryType::North, nw, ne, nx)?;
+        let west = self.edge(BoundaryType::West, sw, nw, ny)?;
+        let east = self.edge(BoundaryType::East, se, ne, ny)?;
+
+        // fill the interior by transfinite interpolation between the four edges
+        let mut vertices = Vertices::new_2d(nx, ny);
+        vertices.populate_tfi(&south, &north, &west, &east, self.blending)?;
+
+        let mut block = Block::new_2d(self.id, vertices)?;
+        self.apply_conditions(&mut block)?;
+        Ok(block)
+    }
+
+    fn edge(&self, face: BoundaryType, start: &Point2D, end: &Point2D, n: usize) -> Result<Vec<Point2D>, &'static str> {
+        let params = match self.distributions.iter().find(|(f, _)| *f == face) {
+            Some((_, distribution)) => distribution.sample(n),
+            None => Uniform.sample(n),
+        };
+        match self.curves.iter().find(|(f, _)| *f == face) {
+            Some((_, boundary)) => boundary.discretise_with(face, start, end, &params),
+            None => Ok(params
+                .iter()
+                .map(|t| Point2D::new(start.x() + t * (end.x() - start.x()), start.y() + t * (end.y() - start.y())))
+                .collect()),
+        }
+    }
+}
+
+impl BlockBuilder<Point3D> {
+    pub fn new_3d(id: usize) -> Self {
+        BlockBuilder::new(id)
+    }
+
+    /// Corners are given as the bottom face (k = 0) followed by the top face,
+    /// each anticlockwise from the origin: sw, se, ne, nw.
+    pub fn corners(mut self, bottom: [Point3D; 4], top: [Point3D; 4]) -> Self {
+        self.corners = Some(bottom.into_iter().chain(top).collect());
+        self
+    }
+
+    /// Replaces a flat face with a surface, which must pass through the four
+    /// corners of that face. Side faces run u along i or j and v up k; function
+    /// surfaces only apply to the top and bottom faces.
+    pub fn boundary(mut self, face: BoundaryType, boundary: Boundary3D) -> Self {
+        self.surfaces.retain(|(existing, _)| *existing != face);
+        self.surfaces.push((face, boundary));
+        self
+    }
+
+    /// Spaces the points up the straight edges joining the bottom face to the
+    /// top with a distribution rather than evenly.
+    pub fn column_distribution(mut self, distribution: impl Distribution + 'static) -> Self {
+        self.columns = Some(Box::new(distribution));
+        self
+    }
+
+    pub fn build(self) -> Result<Block<Point3D>, &'static str> {
+        let (nx, ny, nz) = match self.dimensions {
+            Some(Dimensions::Three { nx, ny, nz }) => (nx, ny, nz),
+            Some(Dimensions::Two { .. }) => return Err("3D block requires 3D dimensions"),
+            None => return Err("block dimensions have not been set"),
+        };
+        if nx < 2 || ny < 2 || nz < 2 {
+            return Err("block requires at least two vertices in each direction");
+        }
+        let c = self.corners.as_ref().ok_or("block corners have not been set")?;
+        let dims = [nx, ny, nz];
+
+        // sample the surfaces given, each over the two block directions it spans
+        let mut sampled = Vec::new();
+        for (face, corners) in FACE_CORNERS {
+            let Some((_, surface)) = self.surfaces.iter().find(|(f, _)| *f == face) else { continue };
+            if matches!(surface, Boundary3D::Function(_)) && !matches!(face, BoundaryType::Top | BoundaryType::Bottom) {
+                return Err("function surfaces only apply to the top and bottom faces");
+            }
+            let (nu, nv) = (dims[edge_axis(corners[0], corners[1])], dims[edge_axis(corners[0], corners[3])]);
+            sampled.push((face, corners, nu, surface.discretise(corners.map(|k| &c[k]), nu, nv)?));
+        }
+
+        // each edge follows a surface holding it, or else runs straight between its corners
+        let edge = |a: usize, b: usize| -> Vec<Point3D> {
+            for (_, corners, nu, points) in &sampled {
+                let (nu, nv) = (*nu, points.len() / nu);
+                let spans = [
+                    ((corners[0], corners[1]), 0, 1, nu),
+                    ((corners[3], corners[2]), nu * (nv - 1), 1, nu),
+                    ((corners[0], corners[3]), 0, nu, nv),
+                    ((corners[1], corners[2]), nu - 1, nu, nv),
+                ];
+                if let Some((_, start, stride, n)) = spans.iter().find(|(ends, ..)| *ends == (a, b)) {
+                    return (0..*n).map(|m| points[start + m * stride].clone()).collect();
+                }
+            }
+            let axis = edge_axis(a, b);
+            let params = match (&self.columns, axis) {
+                (Some(distribution), 2) => distribution.sample(dims[axis]),
+                _ => Uniform.sample(dims[axis]),
+            };
+            params.iter().map(|t| Point3D::from_coordinates(lerp(c[a].coordinates(), c[b].coordinates(), *t))).collect()
+        };
+
+        // faces without a surface are spanned by their four edges
+        let faces = FACE_CORNERS
+            .iter()
+            .map(|(face, [sw, se, ne, nw])| match sampled.iter().find(|(f, ..)| f == face) {
+                Some((.., points)) => Ok(points.clone()),
+                None => coons_patch_3d(&edge(*sw, *se), &edge(*nw, *ne), &edge(*sw, *nw), &edge(*se, *ne)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // fill the interior by transfinite interpolation between the six faces
+        let mut vertices = Vertices::new_3d(nx, ny, nz);
+        vertices.populate_tfi(&BlockFaces {
+            west: &faces[0],
+            east: &faces[1],
+            south: &faces[2],
+            north: &faces[3],
+            bottom: &faces[4],
+            top: &faces[5],
+        })?;
+
+        let mut block = Block::new_3d(self.id, vertices)?;
+        self.apply_conditions(&mut block)?;
+        Ok(block)
+    }
+}
+
+// the corners of each face at (u, v) = (0, 0), (1, 0), (1, 1), (0, 1), with
+// corners numbered sw, se, ne, nw on the bottom then the top, so u and v run
+// with the block's i, j or k, in the order `BlockFaces` lists them
+const FACE_CORNERS: [(BoundaryType, [usize; 4]); 6] = [
+    (BoundaryType::West, [0, 3, 7, 4]),
+    (BoundaryType::East, [1, 2, 6, 5]),
+    (BoundaryType::South, [0, 1, 5, 4]),
+    (BoundaryType::North, [3, 2, 6, 7]),
+    (BoundaryType::Bottom, [0, 1, 2, 3]),
+    (BoundaryType::Top, [4, 5, 6, 7]),
+];
+
+// the direction, 0 for i up to 2 for k, of the edge joining two corners
+fn edge_axis(a: usize, b: usize) -> usize {
+    let ijk = |c: usize| [matches!(c % 4, 1 | 2), c % 4 >= 2, c >= 4];
+    let (a, b) = (ijk(a), ijk(b));
+    (0..3).find(|d| a[*d] != b[*d]).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::distribution::{Reversed, TopClusteredTangent};
+
+    fn unit_square(nx: usize, ny: usize) -> BlockBuilder<Point2D> {
+        BlockBuilder::new_2d(0)
+            .dimensions(Dimensions::Two { nx, ny })
+            .corners(
+                Point2D::new(0.0, 0.0),
+                Point2D::new(1.0, 0.0),
+                Point2D::new(1.0, 1.0),
+                Point2D::new(0.0, 1.0),
+            )
+    }
+
+    #[test]
+    fn test_build_2d_block_from_corners() {
+        let block = unit_square(3, 3).build().expect("failed to build block");
+        assert_eq!(block.vertices.len(), 9);
+
+        let centre = block.vertices.get_vertex(4).unwrap();
+        assert!((centre.get_x() - 0.5).abs() < 1e-12);
+        assert!((centre.get_y() - 0.5).abs() < 1e-12);
+
+        assert_eq!(block.nodes.len(), 4);
+        let centre = block.nodes.get_node(3).unwrap().corners()[0];
+        assert_eq!(centre, 4);
+    }
+
+    #[test]
+    fn test_build_2d_block_with_curved_north() {
+        let block = BlockBuilder::new_2d(1)
+            .dimensions(Dimensions::Two { nx: 5, ny: 3 })
+            .corners(
+                Point2D::new(0.0, 0.0),
+                Point2D::new(2.0, 0.0),
+                Point2D::new(2.0, 0.6),
+                Point2D::new(0.0, 1.0),
+            )
+            .boundary(BoundaryType::North, Boundary2D::function(|x| 1.0 - 0.1 * x * x))
+            .build()
+            .expect("failed to build block");
+
+        for vertex in block.vertices.vertices().skip(10) {
+            let x = vertex.get_x();
+            assert!((vertex.get_y() - (1.0 - 0.1 * x * x)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_build_rejects_bad_definitions() {
+        assert!(BlockBuilder::new_2d(0).build().is_err());
+        assert!(unit_square(1, 3).build().is_err());
+        assert!(unit_square(3, 3).boundary(BoundaryType::North, Boundary2D::function(|_| 2.0)).build().is_err());
+        assert!(unit_square(3, 3).boundary(BoundaryType::Top, Boundary2D::function(|_| 1.0)).build().is_err());
+        assert!(unit_square(3, 3).condition(BoundaryType::Top, BoundaryCondition::Wall).build().is_err());
+    }
+
+    #[test]
+    fn test_build_3d_block_with_top_surface() {
+        let bottom = [
+            Point3D::new(0.0, 0.0, 0.0),
+            Point3D::new(1.0, 0.0, 0.0),
+            Point3D::new(1.0, 1.0, 0.0),
+            Point3D::new(0.0, 1.0, 0.0),
+        ];
+        let top = [
+            Point3D::new(0.0, 0.0, 1.0),
+            Point3D::new(1.0, 0.0, 1.0),
+            Point3D::new(1.0, 1.0, 1.5),
+            Point3D::new(0.0, 1.0, 1.0),
+        ];
+        let block = BlockBuilder::new_3d(2)
+            .dimensions(Dimensions::Three { nx: 3, ny: 3, nz: 3 })
+            .corners(bottom, top)
+            .boundary(BoundaryType::Top, Boundary3D::function(|x, y| 1.0 + 0.5 * x * y))
+            .build()
+            .expect("failed to build block");
+        assert_eq!(block.vertices.len(), 27);
+        assert_eq!(block.nodes.len(), 8);
+
+        let top_corner = block.vertices.get_vertex(26).unwrap();
+        assert!((top_corner.get_z() - 1.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_build_3d_block_with_side_surface() {
+        use std::f64::consts::PI;
+
+        let unit_cube = || {
+            BlockBuilder::new_3d(0).dimensions(Dimensions::Three { nx: 4, ny: 5, nz: 6 }).corners(
+                [Point3D::new(0.0, 0.0, 0.0), Point3D::new(1.0, 0.0, 0.0), Point3D::new(1.0, 1.0, 0.0), Point3D::new(0.0, 1.0, 0.0)],
+                [Point3D::new(0.0, 0.0, 1.0), Point3D::new(1.0, 0.0, 1.0), Point3D::new(1.0, 1.0, 1.2), Point3D::new(0.0, 1.0, 1.0)],
+            )
+        };
+        // the west face bulges outwards, with u running in y and v up z
+        let bulge = |u: f64, v: f64| Point3D::new(-0.2 * (PI * u).sin() * (PI * v).sin(), u, v);
+        let block = unit_cube()
+            .boundary(BoundaryType::West, Boundary3D::parametric(bulge))
+            .boundary(BoundaryType::Top, Boundary3D::function(|x, y| 1.0 + 0.2 * x * y))
+            .build()
+            .expect("failed to build block");
+
+        for vertex in block.vertices.vertices() {
+            let (i, j, k) = block.vertices.vertex_id_to_ijk(vertex.get_id()).unwrap();
+            if i == 0 {
+                let expected = bulge(j as f64 / 4.0, k as f64 / 5.0);
+                assert!((vertex.get_x() - expected.x()).abs() < 1e-12);
+            }
+            if k == 5 {
+                assert!((vertex.get_z() - (1.0 + 0.2 * vertex.get_x() * vertex.get_y())).abs() < 1e-12);
+            }
+        }
+        assert!(block.nodes.nodes.iter().all(|node| node.volume(&block.vertices).unwrap() > 0.0));
+
+        // z = f(x, y) cannot describe a side face
+        assert!(unit_cube().boundary(BoundaryType::North, Boundary3D::function(|_, _| 0.0)).build().is_err());
+    }
+
+    #[test]
+    fn test_edge_distribution_clusters_points() {
+        let block = unit_square(3, 11)
+            .distribution(BoundaryType::West, Reversed(TopClusteredTangent::new(2.0).unwrap()))
+            .distribution(BoundaryType::East, Reversed(TopClusteredTangent::new(2.0).unwrap()))
+            .build()
+            .expect("failed to build block");
+
+        let first = block.vertices.get_vertex(3).unwrap().get_y();
+        let last = 1.0 - block.vertices.get_vertex(27).unwrap().get_y();
+        assert!(first < 0.1 * 0.5);
+        assert!(last > first);
+        assert!((block.vertices.get_vertex(4).unwrap().get_y() - first).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_blocks_reject_duplicate_ids() {
+        let mut blocks = Blocks::new();
+        assert!(blocks.add_block(unit_square(2, 2).build().unwrap()).is_ok());
+        assert!(blocks.add_block(unit_square(3, 3).build().unwrap()).is_err());
+        assert_eq!(blocks.len(), 1);
+    }
+}
@@ -0,0 +1,317 @@
+#![allow(dead_code)]
+
+use crate::geometry::prelude::*;
+use crate::geometry::vector::{lerp, norm, sub};
+use crate::mesh::distribution::{Distribution, Uniform};
+
+pub type BoundaryFn2D = Box<dyn Fn(f64) -> f64>;            // y = f(x) on north/south, x = f(y) on east/west
+pub type BoundaryFn3D = Box<dyn Fn(f64, f64) -> f64>;       // z = f(x, y)
+pub type ParametricFn2D = Box<dyn Fn(f64) -> Point2D>;      // p = f(t), t in [0, 1]
+pub type ParametricFn3D = Box<dyn Fn(f64, f64) -> Point3D>; // p = f(u, v), u, v in [0, 1]
+
+// how far a boundary may miss the corner points it is meant to pass through
+const CORNER_TOLERANCE: f64 = 1e-8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundaryType {
+    North,  // 2D
+    South,  // 2D
+    East,   // 2D
+    West,   // 2D
+    Top,    // 3D
+    Bottom, // 3D
+}
+
+impl BoundaryType {
+    /// Whether the face runs in the i direction, so that it is given as y = f(x).
+    pub fn runs_along_i(&self) -> bool {
+        matches!(self, BoundaryType::North | BoundaryType::South)
+    }
+
+    /// The index direction normal to the face, 0 for i, 1 for j and 2 for k.
+    /// In 3D, west and east are the i faces and south and north the j faces.
+    pub fn axis(&self) -> usize {
+        match self {
+            BoundaryType::West | BoundaryType::East => 0,
+            BoundaryType::South | BoundaryType::North => 1,
+            BoundaryType::Bottom | BoundaryType::Top => 2,
+        }
+    }
+
+    /// Whether the face lies at the last index along its axis rather than the first.
+    pub fn at_max(&self) -> bool {
+        matches!(self, BoundaryType::East | BoundaryType::North | BoundaryType::Top)
+    }
+
+    /// The faces of a block with this many dimensions, in index order.
+    pub fn faces(dimensions: usize) -> &'static [BoundaryType] {
+        let faces = &[
+            BoundaryType::West, BoundaryType::East,
+            BoundaryType::South, BoundaryType::North,
+            BoundaryType::Bottom, BoundaryType::Top,
+        ];
+        &faces[..2 * dimensions]
+    }
+
+    /// The first and last vertex indices of the face, inclusive and zero based,
+    /// in a block with `dims` vertices along each axis.
+    pub fn range(&self, dims: &[usize]) -> (Vec<usize>, Vec<usize>) {
+        let mut begin = vec![0; dims.len()];
+        let mut end: Vec<usize> = dims.iter().map(|n| n - 1).collect();
+        if self.at_max() {
+            begin[self.axis()] = end[self.axis()];
+        } else {
+            end[self.axis()] = 0;
+        }
+        (begin, end)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoundaryType::North => "north",
+            BoundaryType::South => "south",
+            BoundaryType::East => "east",
+            BoundaryType::West => "west",
+            BoundaryType::Top => "top",
+            BoundaryType::Bottom => "bottom",
+        }
+    }
+}
+
+/// The physical condition applied on a face of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundaryCondition {
+    Wall,
+    Inflow,
+    Outflow,
+    Farfield,
+    Symmetry,
+}
+
+impl BoundaryCondition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoundaryCondition::Wall => "wall",
+            BoundaryCondition::Inflow => "inflow",
+            BoundaryCondition::Outflow => "outflow",
+            BoundaryCondition::Farfield => "farfield",
+            BoundaryCondition::Symmetry => "symmetry",
+        }
+    }
+}
+
+/// The shape of one edge of a 2D block.
+pub enum Boundary2D {
+    Function(BoundaryFn2D),
+    Polyline(Vec<Point2D>),
+    Parametric(ParametricFn2D),
+    Curve(Box<dyn Curve<Point2D>>),
+}
+
+impl Boundary2D {
+    pub fn function(f: impl Fn(f64) -> f64 + 'static) -> Self {
+        Boundary2D::Function(Box::new(f))
+    }
+
+    pub fn polyline(points: Vec<Point2D>) -> Self {
+        Boundary2D::Polyline(points)
+    }
+
+    pub fn parametric(f: impl Fn(f64) -> Point2D + 'static) -> Self {
+        Boundary2D::Parametric(Box::new(f))
+    }
+
+    pub fn curve(curve: impl Curve<Point2D> + 'static) -> Self {
+        Boundary2D::Curve(Box::new(curve))
+    }
+
+    /// Samples `n` points along the boundary running from `start` to `end`.
+    /// Functions are sampled uniformly in their independent coordinate,
+    /// polylines and curves uniformly in arc length and parametric curves
+    /// uniformly in t.
+    pub fn discretise(&self, face: BoundaryType, start: &Point2D, end: &Point2D, n: usize) -> Result<Vec<Point2D>, &'static str> {
+        self.discretise_with(face, start, end, &Uniform.sample(n))
+    }
+
+    /// Samples the boundary at the given parameters, which run from 0 at `start`
+    /// to 1 at `end`, typically taken from a distribution.
+    pub fn discretise_with(&self, face: BoundaryType, start: &Point2D, end: &Point2D, params: &[f64]) -> Result<Vec<Point2D>, &'static str> {
+        let n = params.len();
+        if n < 2 {
+            return Err("boundary requires at least two points");
+        }
+
+        let points: Vec<Point2D> = match self {
+            Boundary2D::Function(f) => {
+                if matches!(face, BoundaryType::Top | BoundaryType::Bottom) {
+                    return Err("top and bottom boundaries only apply to 3D blocks");
+                }
+                params.iter().map(|t| {
+                    let [x, y, _] = lerp(start.coordinates(), end.coordinates(), *t);
+                    if face.runs_along_i() { Point2D::new(x, f(x)) } else { Point2D::new(f(y), y) }
+                }).collect()
+            }
+            Boundary2D::Polyline(points) => Polyline::new(points)?.discretise(params)?,
+            Boundary2D::Parametric(f) => params.iter().map(|t| f(*t)).collect(),
+            Boundary2D::Curve(curve) => curve.discretise(params)?,
+        };
+
+        let ends = [(&points[0], start), (&points[n - 1], end)];
+        if ends.iter().any(|(p, corner)| norm(sub(p.coordinates(), corner.coordinates())) > CORNER_TOLERANCE) {
+            return Err("boundary does not pass through the block corners");
+        }
+        Ok(points)
+    }
+}
+
+/// The shape of a face of a 3D block.
+pub enum Boundary3D {
+    Function(BoundaryFn3D),
+    Parametric(ParametricFn3D),
+    Curves(Box<dyn Curve<Point3D>>, Box<dyn Curve<Point3D>>), // ruled between the v = 0 and v = 1 edges
+}
+
+impl Boundary3D {
+    pub fn function(f: impl Fn(f64, f64) -> f64 + 'static) -> Self {
+        Boundary3D::Function(Box::new(f))
+    }
+
+    pub fn parametric(f: impl Fn(f64, f64) -> Point3D + 'static) -> Self {
+        Boundary3D::Parametric(Box::new(f))
+    }
+
+    /// A ruled surface joining two curves that both run in u, the first along
+    /// v = 0 and the second along v = 1.
+    pub fn curves(first: impl Curve<Point3D> + 'static, second: impl Curve<Point3D> + 'static) -> Self {
+        Boundary3D::Curves(Box::new(first), Box::new(second))
+    }
+
+    /// Samples the surface on an nu x nv grid stored u-fastest. Corners are
+    /// given anticlockwise from (u, v) = (0, 0), functions are sampled over
+    /// the bilinear patch they span in x and y and curves uniformly in arc length.
+    pub fn discretise(&self, corners: [&Point3D; 4], nu: usize, nv: usize) -> Result<Vec<Point3D>, &'static str> {
+        if nu < 2 || nv < 2 {
+            return Err("boundary requires at least two points in each direction");
+        }
+
+        // curves are sampled once, then joined by straight lines in v
+        let (first, second) = match self {
+            Boundary3D::Curves(first, second) => {
+                let fractions = Uniform.sample(nu);
+                (first.discretise(&fractions)?, second.discretise(&fractions)?)
+            }
+            _ => (Vec::new(), Vec::new()),
+        };
+
+        let mut points = Vec::with_capacity(nu * nv);
+        for j in 0..nv {
+            let v = j as f64 / (nv - 1) as f64;
+            for i in 0..nu {
+                let u = i as f64 / (nu - 1) as f64;
+                let point = match self {
+                    Boundary3D::Function(f) => {
+                        let x = bilinear([corners[0].x(), corners[1].x(), corners[2].x(), corners[3].x()], u, v);
+                        let y = bilinear([corners[0].y(), corners[1].y(), corners[2].y(), corners[3].y()], u, v);
+                        Point3D::new(x, y, f(x, y))
+                    }
+                    Boundary3D::Parametric(f) => f(u, v),
+                    Boundary3D::Curves(..) => Point3D::from_coordinates(lerp(first[i].coordinates(), second[i].coordinates(), v)),
+                };
+                points.push(point);
+            }
+        }
+
+        let sampled_corners = [&points[0], &points[nu - 1], &points[nu * nv - 1], &points[nu * (nv - 1)]];
+        for (sampled, corner) in sampled_corners.iter().zip(corners.iter()) {
+            if norm(sub(sampled.coordinates(), corner.coordinates())) > CORNER_TOLERANCE {
+                return Err("boundary does not pass through the block corners");
+            }
+        }
+        Ok(points)
+    }
+}
+
+pub fn bilinear(c: [f64; 4], u: f64, v: f64) -> f64 {
+    (1.0 - u) * (1.0 - v) * c[0] + u * (1.0 - v) * c[1] + u * v * c[2] + (1.0 - u) * v * c[3]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function_boundary() {
+        let boundary = Boundary2D::function(|x| 1.0 - 0.1 * x * x);
+        let start = Point2D::new(0.0, 1.0);
+        let end = Point2D::new(2.0, 0.6);
+        let points = boundary.discretise(BoundaryType::North, &start, &end, 5).unwrap();
+        assert_eq!(points.len(), 5);
+        assert!((points[2].x() - 1.0).abs() < 1e-12);
+        assert!((points[2].y() - 0.9).abs() < 1e-12);
+
+        let wrong_end = Point2D::new(2.0, 1.0);
+        assert!(boundary.discretise(BoundaryType::North, &start, &wrong_end, 5).is_err());
+    }
+
+    #[test]
+    fn test_polyline_boundary_is_resampled_by_arc_length() {
+        let boundary = Boundary2D::polyline(vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(1.0, 0.0),
+            Point2D::new(1.0, 1.0),
+        ]);
+        let points = boundary
+            .discretise(BoundaryType::East, &Point2D::new(0.0, 0.0), &Point2D::new(1.0, 1.0), 5)
+            .unwrap();
+        assert_eq!(points[2], Point2D::new(1.0, 0.0));
+        assert_eq!(points[3], Point2D::new(1.0, 0.5));
+    }
+
+    #[test]
+    fn test_curve_boundary_is_sampled_by_arc_length() {
+        use std::f64::consts::PI;
+
+        // a half circle running clockwise over the top, west to east
+        let arc = CircularArc::new_2d(&Point2D::new(0.0, 0.0), 1.0, PI, 0.0).unwrap();
+        let boundary = Boundary2D::curve(arc);
+        let points = boundary
+            .discretise(BoundaryType::North, &Point2D::new(-1.0, 0.0), &Point2D::new(1.0, 0.0), 5)
+            .unwrap();
+        assert!(norm(sub(points[2].coordinates(), [0.0, 1.0, 0.0])) < 1e-8);
+        assert!(norm(sub(points[1].coordinates(), [-0.5f64.sqrt(), 0.5f64.sqrt(), 0.0])) < 1e-8);
+        assert!(boundary.discretise(BoundaryType::North, &Point2D::new(1.0, 0.0), &Point2D::new(-1.0, 0.0), 5).is_err());
+    }
+
+    #[test]
+    fn test_parametric_surface_boundary() {
+        let corners = [
+            Point3D::new(0.0, 0.0, 1.0),
+            Point3D::new(1.0, 0.0, 1.0),
+            Point3D::new(1.0, 1.0, 2.0),
+            Point3D::new(0.0, 1.0, 2.0),
+        ];
+        let surface = Boundary3D::parametric(|u, v| Point3D::new(u, v, 1.0 + v * v));
+        let points = surface.discretise([&corners[0], &corners[1], &corners[2], &corners[3]], 3, 3).unwrap();
+        assert_eq!(points.len(), 9);
+        assert!((points[4].z() - 1.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ruled_surface_between_curves() {
+        // a quarter cylinder of unit radius lying along y
+        let arc = |y: f64| CircularArc::through(&Point3D::new(-1.0, y, 0.0), &Point3D::new(-0.5f64.sqrt(), y, 0.5f64.sqrt()), &Point3D::new(0.0, y, 1.0)).unwrap();
+        let corners = [Point3D::new(-1.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0), Point3D::new(0.0, 2.0, 1.0), Point3D::new(-1.0, 2.0, 0.0)];
+        let surface = Boundary3D::curves(arc(0.0), arc(2.0));
+        let points = surface.discretise([&corners[0], &corners[1], &corners[2], &corners[3]], 5, 3).unwrap();
+        assert_eq!(points.len(), 15);
+        for point in &points {
+            assert!((point.x().hypot(point.z()) - 1.0).abs() < 1e-9);
+        }
+        // the middle row is halfway along y and halfway round the arc
+        assert!((points[7].y() - 1.0).abs() < 1e-12);
+        assert!((points[7].x() + 0.5f64.sqrt()).abs() < 1e-9);
+
+        let flipped = Boundary3D::curves(arc(2.0), arc(0.0));
+        assert!(flipped.discretise([&corners[0], &corners[1], &corners[2], &corners[3]], 5, 3).is_err());
+    }
+}
@@ -0,0 +1,507 @@
+#![allow(dead_code)]
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::geometry::prelude::*;
+use crate::geometry::vector::{cross, norm, sub};
+use crate::mesh::block::Blocks;
+use crate::mesh::boundary::BoundaryType;
+
+// how far apart two points may be and still be treated as the same point
+const MATCH_TOLERANCE: f64 = 1e-9;
+
+// face vertices by bin, as their grid, index and position
+type Bins = HashMap<[i64; 3], Vec<(usize, Vec<usize>, [f64; 3])>>;
+
+// face points by the donor block and face, orientation and offset that carry them
+type Groups = BTreeMap<(usize, usize, usize, Vec<i64>), HashSet<Vec<usize>>>;
+
+/// A face of a block, or a rectangle of one, that meets a face of another
+/// block, or another face or part of a face of the same block, point for
+/// point. Ranges are zero based and inclusive, and the donor range runs from
+/// the image of `range.0` to that of `range.1`, so its begin may lie above its
+/// end along an axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interface {
+    pub block: usize,
+    pub face: BoundaryType,
+    pub range: (Vec<usize>, Vec<usize>),
+    pub donor_block: usize,
+    pub donor_face: BoundaryType,
+    pub donor_range: (Vec<usize>, Vec<usize>),
+    // the donor axis, one based and signed, that each axis of the block runs along
+    pub transform: Vec<i32>,
+}
+
+impl Interface {
+    /// The index in the donor block of a vertex on the face.
+    pub fn donor_index(&self, index: &[usize]) -> Vec<usize> {
+        let mut donor = self.donor_range.0.clone();
+        for (d, t) in self.transform.iter().enumerate() {
+            let axis = t.unsigned_abs() as usize - 1;
+            let offset = (index[d] - self.range.0[d]) as i64 * t.signum() as i64;
+            donor[axis] = (donor[axis] as i64 + offset) as usize;
+        }
+        donor
+    }
+
+    /// Each vertex of the face paired with the donor vertex it meets, i fastest.
+    pub fn vertex_pairs(&self) -> impl Iterator<Item = (Vec<usize>, Vec<usize>)> + '_ {
+        let (begin, end) = &self.range;
+        let extent: Vec<usize> = begin.iter().zip(end).map(|(b, e)| e - b).collect();
+        face_offsets(&extent).collect::<Vec<_>>().into_iter().map(move |offset| {
+            let index: Vec<usize> = begin.iter().zip(&offset).map(|(b, o)| b + o).collect();
+            let donor = self.donor_index(&index);
+            (index, donor)
+        })
+    }
+
+    /// Whether the cell with the given index, which lies against the face, is
+    /// one of those the interface covers.
+    pub fn covers(&self, index: &[usize]) -> bool {
+        let axis = self.face.axis();
+        (0..index.len()).all(|d| d == axis || (self.range.0[d] <= index[d] && index[d] < self.range.1[d]))
+    }
+}
+
+/// Finds every face of the grids, or rectangle of a face, matched point for
+/// point by another face. A face may meet several faces, each over part of
+/// it, and may meet another part of itself, as a C-grid wake cut does. Each
+/// match is listed from both sides, as solvers expect each block to carry its
+/// own.
+pub fn find_interfaces<P: Point>(grids: &[(usize, &Vertices<P>)]) -> Vec<Interface> {
+    let bins = bin_face_vertices(grids);
+    let mut interfaces = Vec::new();
+    for (g, (id, vertices)) in grids.iter().enumerate() {
+        let dims = vertices.shape();
+        let n = dims.len();
+        let orientations = orientations(n);
+        for face in BoundaryType::faces(n) {
+            // the face points that coincide with donor points, grouped by the
+            // donor face and the orientation and offset that carry them there
+            let mut groups = Groups::new();
+            let (begin, end) = face.range(&dims);
+            let extent: Vec<usize> = (0..n).map(|d| end[d] - begin[d]).collect();
+            for offset in face_offsets(&extent) {
+                let index: Vec<usize> = (0..n).map(|d| begin[d] + offset[d]).collect();
+                let position = vertices.point(flat_index(&dims, &index)).unwrap();
+                for (h, donor) in coincident(&bins, position) {
+                    if h == g && donor == index {
+                        continue;
+                    }
+                    let donor_dims = grids[h].1.shape();
+                    for (f, donor_face) in BoundaryType::faces(n).iter().enumerate() {
+                        if !lies_on(*donor_face, &donor, &donor_dims) {
+                            continue;
+                        }
+                        for (o, (axes, signs)) in orientations.iter().enumerate() {
+                            if !enters(*face, *donor_face, axes, signs) {
+                                continue;
+                            }
+                            let mut shift = vec![0; n];
+                            for d in 0..n {
+                                shift[axes[d]] = donor[axes[d]] as i64 - signs[d] as i64 * index[d] as i64;
+                            }
+                            groups.entry((h, f, o, shift)).or_default().insert(index.clone());
+                        }
+                    }
+                }
+            }
+
+            // each cell of the face goes to the first donor that matches all its corners
+            let mut claimed = HashSet::new();
+            for ((h, f, o, shift), points) in groups {
+                let (axes, signs) = &orientations[o];
+                let image = |index: &[usize]| {
+                    let mut donor = vec![0; n];
+                    for d in 0..n {
+                        donor[axes[d]] = (shift[axes[d]] + signs[d] as i64 * index[d] as i64) as usize;
+                    }
+                    donor
+                };
+                let matched: HashSet<Vec<usize>> = face_cells(*face, &dims)
+                    .filter(|cell| !claimed.contains(cell))
+                    .filter(|cell| {
+                        let corners: Vec<_> = cell_corners(*face, cell).collect();
+                        corners.iter().all(|c| points.contains(c)) && !degenerate(vertices, &dims, &corners)
+                    })
+                    .collect();
+
+                for (lo, hi) in rectangles(*face, &dims, &matched, &mut claimed) {
+                    interfaces.push(Interface {
+                        block: *id,
+                        face: *face,
+                        donor_range: (image(&lo), image(&hi)),
+                        range: (lo, hi),
+                        donor_block: grids[h].0,
+                        donor_face: BoundaryType::faces(n)[f],
+                        transform: (0..n).map(|d| signs[d] * (axes[d] as i32 + 1)).collect(),
+                    });
+                }
+            }
+        }
+    }
+    interfaces
+}
+
+impl<P: Point> Blocks<P> {
+    /// Finds every face, or part of a face, matched point for point by another face.
+    pub fn interfaces(&self) -> Vec<Interface> {
+        let grids: Vec<_> = self.blocks().iter().map(|b| (b.id, &b.vertices)).collect();
+        find_interfaces(&grids)
+    }
+
+    /// The faces of a block that neither have a condition nor meet another face.
+    pub fn unassigned_faces(&self, interfaces: &[Interface]) -> Vec<(usize, BoundaryType)> {
+        let mut faces = Vec::new();
+        for block in self.blocks() {
+            for face in BoundaryType::faces(block.dimensions()) {
+                let matched = interfaces.iter().any(|i| i.block == block.id && i.face == *face);
+                if block.condition(*face).is_none() && !matched {
+                    faces.push((block.id, *face));
+                }
+            }
+        }
+        faces
+    }
+}
+
+// the vertices on the faces of every grid, as their grid and index, binned by
+// position so coincident vertices are found without comparing every pair
+fn bin_face_vertices<P: Point>(grids: &[(usize, &Vertices<P>)]) -> Bins {
+    let mut bins = Bins::new();
+    for (g, (_, vertices)) in grids.iter().enumerate() {
+        let dims = vertices.shape();
+        let last: Vec<usize> = dims.iter().map(|d| d - 1).collect();
+        for index in face_offsets(&last) {
+            if !index.iter().zip(&last).any(|(i, l)| *i == 0 || i == l) {
+                continue;
+            }
+            let position = vertices.point(flat_index(&dims, &index)).unwrap();
+            bins.entry(bin(position)).or_default().push((g, index, position));
+        }
+    }
+    bins
+}
+
+// bins are twice the tolerance wide, so a coincident point lies in the same bin
+// or a neighbouring one
+fn bin(position: [f64; 3]) -> [i64; 3] {
+    position.map(|c| (c / (2.0 * MATCH_TOLERANCE)).floor() as i64)
+}
+
+fn coincident(bins: &Bins, position: [f64; 3]) -> Vec<(usize, Vec<usize>)> {
+    let centre = bin(position);
+    let mut found = Vec::new();
+    for neighbour in face_offsets(&[2, 2, 2]) {
+        let key = [0, 1, 2].map(|d| centre[d] + neighbour[d] as i64 - 1);
+        for (g, index, other) in bins.get(&key).into_iter().flatten() {
+            if norm(sub(*other, position)) < MATCH_TOLERANCE {
+                found.push((*g, index.clone()));
+            }
+        }
+    }
+    found
+}
+
+// leaving the block through its face has to enter the donor through its face
+fn enters(face: BoundaryType, donor_face: BoundaryType, axes: &[usize], signs: &[i32]) -> bool {
+    let normal_sign = if face.at_max() == donor_face.at_max() { -1 } else { 1 };
+    axes[face.axis()] == donor_face.axis() && signs[face.axis()] == normal_sign
+}
+
+fn lies_on(face: BoundaryType, index: &[usize], dims: &[usize]) -> bool {
+    index[face.axis()] == if face.at_max() { dims[face.axis()] - 1 } else { 0 }
+}
+
+// the first corner of each cell against a face, i fastest
+fn face_cells(face: BoundaryType, dims: &[usize]) -> impl Iterator<Item = Vec<usize>> {
+    let (begin, end) = face.range(dims);
+    let extent: Vec<usize> = (0..dims.len()).map(|d| if d == face.axis() { 0 } else { end[d] - begin[d] - 1 }).collect();
+    face_offsets(&extent).collect::<Vec<_>>().into_iter().map(move |offset| {
+        begin.iter().zip(&offset).map(|(b, o)| b + o).collect()
+    })
+}
+
+// the corners of a cell against a face, i fastest
+fn cell_corners(face: BoundaryType, cell: &[usize]) -> impl Iterator<Item = Vec<usize>> + '_ {
+    let unit: Vec<usize> = (0..cell.len()).map(|d| usize::from(d != face.axis())).collect();
+    face_offsets(&unit).collect::<Vec<_>>().into_iter().map(move |offset| {
+        cell.iter().zip(&offset).map(|(c, o)| c + o).collect()
+    })
+}
+
+// a cell collapsed to a point or a line, as at a pole, meets anything that
+// collapses with it, so it is left out of any match
+fn degenerate<P: Point>(vertices: &Vertices<P>, dims: &[usize], corners: &[Vec<usize>]) -> bool {
+    let at = |c: usize| vertices.point(flat_index(dims, &corners[c])).unwrap();
+    let diagonal = sub(at(corners.len() - 1), at(0));
+    if corners.len() == 2 {
+        norm(diagonal) < MATCH_TOLERANCE
+    } else {
+        norm(cross(diagonal, sub(at(2), at(1)))) < MATCH_TOLERANCE.powi(2)
+    }
+}
+
+// splits the matched cells of a face into rectangles, each grown from its first
+// cell along i and then along the next tangent axis, as first and last vertex
+fn rectangles(
+    face: BoundaryType,
+    dims: &[usize],
+    matched: &HashSet<Vec<usize>>,
+    claimed: &mut HashSet<Vec<usize>>,
+) -> Vec<(Vec<usize>, Vec<usize>)> {
+    let tangents: Vec<usize> = (0..dims.len()).filter(|d| *d != face.axis()).collect();
+    let mut rectangles = Vec::new();
+    for cell in face_cells(face, dims) {
+        if !matched.contains(&cell) || claimed.contains(&cell) {
+            continue;
+        }
+        let mut hi = cell.clone();
+        for t in &tangents {
+            loop {
+                let (mut next_lo, mut next_hi) = (cell.clone(), hi.clone());
+                next_lo[*t] = hi[*t] + 1;
+                next_hi[*t] = hi[*t] + 1;
+                if next_hi[*t] + 1 >= dims[*t]
+                    || !cells_between(&next_lo, &next_hi).all(|c| matched.contains(&c) && !claimed.contains(&c))
+                {
+                    break;
+                }
+                hi[*t] += 1;
+            }
+        }
+        claimed.extend(cells_between(&cell, &hi));
+        for t in &tangents {
+            hi[*t] += 1;
+        }
+        rectangles.push((cell, hi));
+    }
+    rectangles
+}
+
+fn cells_between<'a>(lo: &'a [usize], hi: &[usize]) -> impl Iterator<Item = Vec<usize>> + 'a {
+    let extent: Vec<usize> = lo.iter().zip(hi).map(|(l, h)| h - l).collect();
+    face_offsets(&extent).collect::<Vec<_>>().into_iter().map(move |offset| {
+        lo.iter().zip(&offset).map(|(l, o)| l + o).collect()
+    })
+}
+
+// every right-handed way of laying one block's axes along another's, as the
+// donor axis and direction for each axis
+fn orientations(n: usize) -> Vec<(Vec<usize>, Vec<i32>)> {
+    let permutations: Vec<(Vec<usize>, i32)> = match n {
+        2 => vec![(vec![0, 1], 1), (vec![1, 0], -1)],
+        _ => vec![
+            (vec![0, 1, 2], 1), (vec![1, 2, 0], 1), (vec![2, 0, 1], 1),
+            (vec![0, 2, 1], -1), (vec![2, 1, 0], -1), (vec![1, 0, 2], -1),
+        ],
+    };
+    let mut orientations = Vec::new();
+    for (axes, parity) in permutations {
+        for flips in 0..1 << n {
+            let signs: Vec<i32> = (0..n).map(|d| if flips & (1 << d) == 0 { 1 } else { -1 }).collect();
+            if parity * signs.iter().product::<i32>() == 1 {
+                orientations.push((axes.clone(), signs));
+            }
+        }
+    }
+    orientations
+}
+
+// every offset from the first point of a face, i fastest
+fn face_offsets(extent: &[usize]) -> impl Iterator<Item = Vec<usize>> + '_ {
+    let total: usize = extent.iter().map(|e| e + 1).product();
+    (0..total).map(move |mut flat| {
+        extent.iter().map(|e| {
+            let offset = flat % (e + 1);
+            flat /= e + 1;
+            offset
+        }).collect()
+    })
+}
+
+fn flat_index(dims: &[usize], index: &[usize]) -> usize {
+    index.iter().zip(dims).rev().fold(0, |id, (i, n)| id * n + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::block::{Block, BlockBuilder};
+    use crate::mesh::boundary::BoundaryCondition;
+
+    fn square(id: usize, x0: f64, nx: usize, ny: usize) -> Block<Point2D> {
+        BlockBuilder::new_2d(id)
+            .dimensions(Dimensions::Two { nx, ny })
+            .corners(
+                Point2D::new(x0, 0.0),
+                Point2D::new(x0 + 1.0, 0.0),
+                Point2D::new(x0 + 1.0, 1.0),
+                Point2D::new(x0, 1.0),
+            )
+            .condition(BoundaryType::South, BoundaryCondition::Wall)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_abutting_blocks_share_an_interface() {
+        let mut blocks = Blocks::new();
+        blocks.add_block(square(0, 0.0, 3, 4)).unwrap();
+        blocks.add_block(square(1, 1.0, 5, 4)).unwrap();
+
+        let interfaces = blocks.interfaces();
+        assert_eq!(interfaces.len(), 2);
+        let east = &interfaces[0];
+        assert_eq!((east.block, east.face, east.donor_block, east.donor_face), (0, BoundaryType::East, 1, BoundaryType::West));
+        assert_eq!(east.range, (vec![2, 0], vec![2, 3]));
+        assert_eq!(east.donor_range, (vec![0, 0], vec![0, 3]));
+        assert_eq!(east.transform, vec![1, 2]);
+
+        let unassigned = blocks.unassigned_faces(&interfaces);
+        assert!(unassigned.contains(&(0, BoundaryType::West)));
+        assert!(!unassigned.contains(&(0, BoundaryType::South)));
+        assert!(!unassigned.contains(&(1, BoundaryType::West)));
+    }
+
+    #[test]
+    fn test_rotated_neighbour_transform() {
+        // the second block's j axis runs along the first block's i axis, meeting
+        // the first block's north face with its west face
+        let mut blocks = Blocks::new();
+        blocks.add_block(square(0, 0.0, 3, 3)).unwrap();
+        let rotated = BlockBuilder::new_2d(1)
+            .dimensions(Dimensions::Two { nx: 4, ny: 3 })
+            .corners(Point2D::new(1.0, 1.0), Point2D::new(1.0, 2.0), Point2D::new(0.0, 2.0), Point2D::new(0.0, 1.0))
+            .build()
+            .unwrap();
+        blocks.add_block(rotated).unwrap();
+
+        let interfaces = blocks.interfaces();
+        let north = interfaces.iter().find(|i| i.block == 0).unwrap();
+        assert_eq!((north.face, north.donor_face), (BoundaryType::North, BoundaryType::West));
+        assert_eq!(north.transform, vec![-2, 1]);
+        assert_eq!(north.donor_range, (vec![0, 2], vec![0, 0]));
+
+        let back = interfaces.iter().find(|i| i.block == 1).unwrap();
+        assert_eq!(back.transform, vec![2, -1]);
+        assert_eq!(back.donor_range, (vec![2, 2], vec![0, 2]));
+
+        let pairs: Vec<_> = north.vertex_pairs().collect();
+        assert_eq!(pairs[1], (vec![1, 2], vec![0, 1]));
+        assert!(pairs.iter().all(|(index, donor)| back.donor_index(donor) == *index));
+    }
+
+    #[test]
+    fn test_periodic_o_grid_cut() {
+        let (nx, ny) = (9, 3);
+        let mut vertices = Vertices::new_2d(nx, ny);
+        for j in 0..ny {
+            for i in 0..nx {
+                let theta = 2.0 * std::f64::consts::PI * i as f64 / (nx - 1) as f64;
+                let r = 1.0 + j as f64;
+                vertices.add_vertex(Vertex::new_2d(i + j * nx, r * theta.cos(), r * theta.sin())).unwrap();
+            }
+        }
+        let mut blocks = Blocks::new();
+        blocks.add_block(Block::new_2d(0, vertices).unwrap()).unwrap();
+
+        let interfaces = blocks.interfaces();
+        assert_eq!(interfaces.len(), 2);
+        assert_eq!((interfaces[0].face, interfaces[0].donor_face), (BoundaryType::West, BoundaryType::East));
+        assert_eq!(interfaces[0].transform, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_3d_stacked_blocks() {
+        let cube = |id: usize, z0: f64| {
+            let bottom = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(x, y)| Point3D::new(x, y, z0));
+            let top = bottom.clone().map(|p| Point3D::new(p.x(), p.y(), z0 + 1.0));
+            BlockBuilder::new_3d(id).dimensions(Dimensions::Three { nx: 3, ny: 4, nz: 2 }).corners(bottom, top).build().unwrap()
+        };
+        let mut blocks = Blocks::new();
+        blocks.add_block(cube(0, 0.0)).unwrap();
+        blocks.add_block(cube(1, 1.0)).unwrap();
+
+        let interfaces = blocks.interfaces();
+        assert_eq!(interfaces.len(), 2);
+        assert_eq!(interfaces[0].range, (vec![0, 0, 1], vec![2, 3, 1]));
+        assert_eq!(interfaces[0].donor_range, (vec![0, 0, 0], vec![2, 3, 0]));
+        assert_eq!(interfaces[0].transform, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_c_grid_wake_meets_itself() {
+        // the south face runs back along the lower wake, around a diamond body
+        // and out along the upper wake, so the two wake parts of it meet
+        let (wake, body) = (3, 4);
+        let nx = 2 * wake + body + 1;
+        let surface = |i: usize| -> ([f64; 2], [f64; 2]) {
+            let corners = [(1.0, 0.0), (0.5, -0.2), (0.0, 0.0), (0.5, 0.2), (1.0, 0.0)];
+            if i <= wake {
+                ([2.0 - i as f64 / wake as f64, 0.0], [0.0, -1.0])
+            } else if i >= wake + body {
+                ([1.0 + (i - wake - body) as f64 / wake as f64, 0.0], [0.0, 1.0])
+            } else {
+                let (x, y) = corners[i - wake];
+                let r = ((x - 0.5f64).powi(2) + y * y).sqrt();
+                ([x, y], [(x - 0.5) / r, y / r])
+            }
+        };
+        let mut vertices = Vertices::new_2d(nx, 3);
+        for j in 0..3 {
+            for i in 0..nx {
+                let (p, n) = surface(i);
+                let h = 0.5 * j as f64;
+                vertices.add_vertex(Vertex::new_2d(i + j * nx, p[0] + h * n[0], p[1] + h * n[1])).unwrap();
+            }
+        }
+        let mut blocks = Blocks::new();
+        blocks.add_block(Block::new_2d(0, vertices).unwrap()).unwrap();
+
+        let interfaces = blocks.interfaces();
+        assert_eq!(interfaces.len(), 2);
+        let (lower, upper) = (&interfaces[0], &interfaces[1]);
+        assert_eq!((lower.face, lower.donor_face), (BoundaryType::South, BoundaryType::South));
+        assert_eq!(lower.range, (vec![0, 0], vec![3, 0]));
+        assert_eq!(lower.donor_range, (vec![10, 0], vec![7, 0]));
+        assert_eq!(lower.transform, vec![-1, -2]);
+        assert_eq!(upper.range, (vec![7, 0], vec![10, 0]));
+        assert_eq!(upper.donor_range, (vec![3, 0], vec![0, 0]));
+
+        assert!(lower.covers(&[2, 0]) && !lower.covers(&[3, 0]));
+        assert!(lower.vertex_pairs().all(|(index, donor)| upper.donor_index(&donor) == index));
+    }
+
+    #[test]
+    fn test_face_meeting_two_blocks() {
+        let rectangle = |id: usize, (x0, y0): (f64, f64), width: f64, nx: usize| {
+            BlockBuilder::new_2d(id)
+                .dimensions(Dimensions::Two { nx, ny: 2 })
+                .corners(
+                    Point2D::new(x0, y0),
+                    Point2D::new(x0 + width, y0),
+                    Point2D::new(x0 + width, y0 + 1.0),
+                    Point2D::new(x0, y0 + 1.0),
+                )
+                .build()
+                .unwrap()
+        };
+        let mut blocks = Blocks::new();
+        blocks.add_block(rectangle(0, (0.0, 0.0), 2.0, 5)).unwrap();
+        blocks.add_block(rectangle(1, (0.0, 1.0), 1.0, 3)).unwrap();
+        blocks.add_block(rectangle(2, (1.0, 1.0), 1.0, 3)).unwrap();
+
+        let interfaces = blocks.interfaces();
+        let north: Vec<_> = interfaces.iter().filter(|i| i.block == 0).collect();
+        assert_eq!(north.len(), 2);
+        assert_eq!((north[0].range.clone(), north[0].donor_block), ((vec![0, 1], vec![2, 1]), 1));
+        assert_eq!((north[1].range.clone(), north[1].donor_block), ((vec![2, 1], vec![4, 1]), 2));
+        assert_eq!(north[1].donor_range, (vec![0, 0], vec![2, 0]));
+
+        let south = interfaces.iter().find(|i| i.block == 2 && i.face == BoundaryType::South).unwrap();
+        assert_eq!(south.donor_range, (vec![2, 1], vec![4, 1]));
+        assert_eq!(interfaces.len(), 6);
+    }
+}
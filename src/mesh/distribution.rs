@@ -0,0 +1,500 @@
+#![allow(dead_code)]
+
+use std::f64::consts::PI;
+
+use crate::utils::roots::{newton, NEWTON_ITERATIONS, NEWTON_TOLERANCE};
+
+// below this the stretching functions are indistinguishable from uniform
+const UNIFORM_TOLERANCE: f64 = 1e-6;
+
+/// A one-dimensional point distribution, mapping a uniform parameter in [0, 1]
+/// onto a clustered one with both ends fixed.
+pub trait Distribution {
+    fn map(&self, t: f64) -> f64;
+
+    /// Samples `n` parameters running from 0 to 1 inclusive.
+    fn sample(&self, n: usize) -> Vec<f64> {
+        if n < 2 {
+            return vec![0.0; n];
+        }
+        (0..n).map(|k| self.map(k as f64 / (n - 1) as f64)).collect()
+    }
+}
+
+impl<D: Distribution + ?Sized> Distribution for Box<D> {
+    fn map(&self, t: f64) -> f64 {
+        (**self).map(t)
+    }
+}
+
+/// Evenly spaced points.
+pub struct Uniform;
+
+impl Distribution for Uniform {
+    fn map(&self, t: f64) -> f64 {
+        t
+    }
+}
+
+/// Hyperbolic tangent clustering towards both ends, tighter as beta grows.
+pub struct HyperbolicTangent {
+    beta: f64,
+}
+
+impl HyperbolicTangent {
+    pub fn new(beta: f64) -> Result<Self, &'static str> {
+        if beta <= 0.0 {
+            return Err("hyperbolic tangent beta must be positive");
+        }
+        Ok(HyperbolicTangent { beta })
+    }
+
+    /// Solves for the beta that gives cells of `ds_end` at both ends of an edge
+    /// of the given length with `points` points.
+    pub fn from_spacing(length: f64, points: usize, ds_end: f64) -> Result<Self, &'static str> {
+        let (cells, target) = spacing_fraction(length, points, ds_end)?;
+        let first = |beta: f64| 0.5 * (1.0 - (beta * (1.0 - 2.0 / cells)).tanh() / beta.tanh());
+        HyperbolicTangent::new(solve_tanh_beta(first, cells, target)?)
+    }
+}
+
+impl Distribution for HyperbolicTangent {
+    fn map(&self, t: f64) -> f64 {
+        0.5 * (1.0 + (self.beta * (2.0 * t - 1.0)).tanh() / self.beta.tanh())
+    }
+}
+
+/// Hyperbolic tangent clustering towards the end, tighter as beta grows.
+pub struct TopClusteredTangent {
+    beta: f64,
+}
+
+impl TopClusteredTangent {
+    pub fn new(beta: f64) -> Result<Self, &'static str> {
+        if beta <= 0.0 {
+            return Err("hyperbolic tangent beta must be positive");
+        }
+        Ok(TopClusteredTangent { beta })
+    }
+
+    /// Solves for the beta that gives a last cell of `ds_end` on an edge of the
+    /// given length with `points` points.
+    pub fn from_spacing(length: f64, points: usize, ds_end: f64) -> Result<Self, &'static str> {
+        let (cells, target) = spacing_fraction(length, points, ds_end)?;
+        let last = |beta: f64| 1.0 - (beta * (1.0 - 1.0 / cells)).tanh() / beta.tanh();
+        TopClusteredTangent::new(solve_tanh_beta(last, cells, target)?)
+    }
+}
+
+impl Distribution for TopClusteredTangent {
+    fn map(&self, t: f64) -> f64 {
+        (self.beta * t).tanh() / self.beta.tanh()
+    }
+}
+
+/// Cells that grow by a constant ratio from one to the next. The ratio is per
+/// cell, so the distribution is tied to the number of points it is sampled at.
+pub struct Geometric {
+    ratio: f64,
+    points: usize,
+}
+
+impl Geometric {
+    pub fn new(ratio: f64, points: usize) -> Result<Self, &'static str> {
+        if ratio <= 0.0 {
+            return Err("geometric ratio must be positive");
+        }
+        if points < 2 {
+            return Err("geometric distribution requires at least two points");
+        }
+        Ok(Geometric { ratio, points })
+    }
+
+    /// Solves for the growth ratio that gives a first cell of `ds_start` on an
+    /// edge of the given length with `points` points.
+    pub fn from_spacing(length: f64, points: usize, ds_start: f64) -> Result<Self, &'static str> {
+        let (cells, target) = spacing_fraction(length, points, ds_start)?;
+        Geometric::new(solve_growth_exponent(cells, target)?.exp(), points)
+    }
+}
+
+impl Distribution for Geometric {
+    fn map(&self, t: f64) -> f64 {
+        let cells = (self.points - 1) as f64;
+        if (self.ratio - 1.0).abs() < UNIFORM_TOLERANCE {
+            return t;
+        }
+        (self.ratio.powf(cells * t) - 1.0) / (self.ratio.powf(cells) - 1.0)
+    }
+}
+
+/// Exponential stretching, clustered towards the start for positive a and
+/// towards the end for negative a.
+pub struct Exponential {
+    a: f64,
+}
+
+impl Exponential {
+    pub fn new(a: f64) -> Self {
+        Exponential { a }
+    }
+
+    /// Solves for the a that gives a first cell of `ds_start` on an edge of
+    /// the given length with `points` points.
+    pub fn from_spacing(length: f64, points: usize, ds_start: f64) -> Result<Self, &'static str> {
+        let (cells, target) = spacing_fraction(length, points, ds_start)?;
+        // sampled at the points, exponential stretching is a geometric series
+        Ok(Exponential::new(cells * solve_growth_exponent(cells, target)?))
+    }
+}
+
+impl Distribution for Exponential {
+    fn map(&self, t: f64) -> f64 {
+        if self.a.abs() < UNIFORM_TOLERANCE {
+            return t;
+        }
+        (self.a * t).exp_m1() / self.a.exp_m1()
+    }
+}
+
+/// Cosine clustering towards both ends, as at the Chebyshev-Gauss-Lobatto points.
+pub struct Chebyshev;
+
+impl Distribution for Chebyshev {
+    fn map(&self, t: f64) -> f64 {
+        0.5 * (1.0 - (PI * t).cos())
+    }
+}
+
+/// Roberts' stretching. An alpha of 0 clusters towards the end and 0.5 towards
+/// both ends, with beta closer to 1 clustering harder.
+pub struct Roberts {
+    beta: f64,
+    alpha: f64,
+}
+
+impl Roberts {
+    pub fn new(beta: f64, alpha: f64) -> Result<Self, &'static str> {
+        if beta <= 1.0 {
+            return Err("roberts beta must be greater than one");
+        }
+        if !(0.0..=0.5).contains(&alpha) {
+            return Err("roberts alpha must lie between 0 and 0.5");
+        }
+        Ok(Roberts { beta, alpha })
+    }
+}
+
+impl Distribution for Roberts {
+    fn map(&self, t: f64) -> f64 {
+        let (alpha, beta) = (self.alpha, self.beta);
+        let ratio = ((beta + 1.0) / (beta - 1.0)).powf((t - alpha) / (1.0 - alpha));
+        ((2.0 * alpha + beta) * ratio + 2.0 * alpha - beta) / ((2.0 * alpha + 1.0) * (1.0 + ratio))
+    }
+}
+
+/// Vinokur's two-sided stretching, which meets a given slope at each end. The
+/// slopes are relative to a uniform distribution, so 0.1 gives cells a tenth
+/// of the uniform size at that end.
+pub struct Vinokur {
+    a: f64,
+    stretching: VinokurStretching,
+}
+
+enum VinokurStretching {
+    Sinh(f64),
+    Sin(f64),
+    Near(f64),
+}
+
+impl Vinokur {
+    pub fn new(start_slope: f64, end_slope: f64) -> Result<Self, &'static str> {
+        if start_slope <= 0.0 || end_slope <= 0.0 {
+            return Err("vinokur end slopes must be positive");
+        }
+        // the symmetric stretching sets the mean slope, the ratio skews it between the ends
+        let a = (end_slope / start_slope).sqrt();
+        let b = 1.0 / (start_slope * end_slope).sqrt();
+
+        let stretching = if (b - 1.0).abs() < 1e-3 {
+            VinokurStretching::Near(b)
+        } else if b > 1.0 {
+            // sinh(x) / x = b, bracketed by growing the upper bound until it is passed
+            let mut upper: f64 = 1.0;
+            while upper.sinh() / upper < b {
+                upper *= 2.0;
+            }
+            let x = newton(|x| x.sinh() / x - b, |x| (x * x.cosh() - x.sinh()) / (x * x), upper, (1e-8, upper))?;
+            VinokurStretching::Sinh(x)
+        } else {
+            // sin(x) / x = b, with x below pi
+            let x = newton(|x| x.sin() / x - b, |x| (x * x.cos() - x.sin()) / (x * x), 0.5 * PI, (1e-8, PI - 1e-8))?;
+            VinokurStretching::Sin(x)
+        };
+        Ok(Vinokur { a, stretching })
+    }
+
+    /// Solves for the end slopes that give cells of `ds_start` and `ds_end` at
+    /// the two ends of an edge of the given length with `points` points.
+    pub fn from_spacing(length: f64, points: usize, ds_start: f64, ds_end: f64) -> Result<Self, &'static str> {
+        if points < 4 {
+            return Err("spacing at both ends needs at least four points");
+        }
+        let (cells, start) = spacing_fraction(length, points, ds_start)?;
+        let (_, end) = spacing_fraction(length, points, ds_end)?;
+        if start + end >= 1.0 {
+            return Err("spacing can't be met with this many points");
+        }
+
+        // the slopes only fix the spacing in the limit of many points, so newton
+        // iterate on their logs until the end cells themselves are right
+        let h = 1.0 / cells;
+        let residual = |p: [f64; 2]| -> Result<[f64; 2], &'static str> {
+            let vinokur = Vinokur::new(p[0].exp(), p[1].exp())?;
+            Ok([vinokur.map(h).ln() - start.ln(), (1.0 - vinokur.map(1.0 - h)).ln() - end.ln()])
+        };
+
+        let mut p = [(start * cells).ln(), (end * cells).ln()];
+        for _ in 0..NEWTON_ITERATIONS {
+            let r = residual(p)?;
+            if r[0].abs().max(r[1].abs()) < NEWTON_TOLERANCE {
+                return Vinokur::new(p[0].exp(), p[1].exp());
+            }
+
+            let step = 1e-7;
+            let mut jacobian = [[0.0; 2]; 2];
+            for k in 0..2 {
+                let mut shifted = p;
+                shifted[k] += step;
+                let rk = residual(shifted)?;
+                jacobian[0][k] = (rk[0] - r[0]) / step;
+                jacobian[1][k] = (rk[1] - r[1]) / step;
+            }
+            let det = jacobian[0][0] * jacobian[1][1] - jacobian[0][1] * jacobian[1][0];
+            if det.abs() < 1e-14 {
+                return Err("spacing can't be met with this many points");
+            }
+            // damped so a poor first guess can't throw the slopes out of range
+            let dp = [
+                (-(jacobian[1][1] * r[0] - jacobian[0][1] * r[1]) / det).clamp(-1.0, 1.0),
+                (-(jacobian[0][0] * r[1] - jacobian[1][0] * r[0]) / det).clamp(-1.0, 1.0),
+            ];
+            p = [p[0] + dp[0], p[1] + dp[1]];
+        }
+        Err("spacing can't be met with this many points")
+    }
+}
+
+impl Distribution for Vinokur {
+    fn map(&self, t: f64) -> f64 {
+        let u = match self.stretching {
+            VinokurStretching::Sinh(x) => 0.5 * (1.0 + (x * (t - 0.5)).tanh() / (0.5 * x).tanh()),
+            VinokurStretching::Sin(x) => 0.5 * (1.0 + (x * (t - 0.5)).tan() / (0.5 * x).tan()),
+            VinokurStretching::Near(b) => t * (1.0 + 2.0 * (b - 1.0) * (t - 0.5) * (1.0 - t)),
+        };
+        u / (self.a + (1.0 - self.a) * u)
+    }
+}
+
+/// A user-tabulated distribution, linearly interpolated between evenly spaced
+/// entries. The positions can be in any units, they are rescaled onto [0, 1].
+pub struct Tabulated {
+    values: Vec<f64>,
+}
+
+impl Tabulated {
+    pub fn new(positions: &[f64]) -> Result<Self, &'static str> {
+        if positions.len() < 2 {
+            return Err("tabulated distribution requires at least two positions");
+        }
+        if positions.windows(2).any(|pair| pair[1] <= pair[0]) {
+            return Err("tabulated positions must be strictly increasing");
+        }
+        let (first, last) = (positions[0], positions[positions.len() - 1]);
+        Ok(Tabulated { values: positions.iter().map(|p| (p - first) / (last - first)).collect() })
+    }
+}
+
+impl Distribution for Tabulated {
+    fn map(&self, t: f64) -> f64 {
+        let scaled = t.clamp(0.0, 1.0) * (self.values.len() - 1) as f64;
+        let k = (scaled.floor() as usize).min(self.values.len() - 2);
+        let local = scaled - k as f64;
+        self.values[k] + local * (self.values[k + 1] - self.values[k])
+    }
+}
+
+/// Mirrors another distribution, so clustering at the start moves to the end.
+pub struct Reversed<D: Distribution>(pub D);
+
+impl<D: Distribution> Distribution for Reversed<D> {
+    fn map(&self, t: f64) -> f64 {
+        1.0 - self.0.map(1.0 - t)
+    }
+}
+
+// checks a requested cell size against its edge, returning the number of cells
+// and the size as a fraction of the edge length
+fn spacing_fraction(length: f64, points: usize, spacing: f64) -> Result<(f64, f64), &'static str> {
+    if length <= 0.0 {
+        return Err("edge length must be positive");
+    }
+    if points < 2 {
+        return Err("distribution requires at least two points");
+    }
+    if spacing <= 0.0 {
+        return Err("cell spacing must be positive");
+    }
+    if spacing >= length {
+        return Err("spacing can't be met with this many points");
+    }
+    Ok(((points - 1) as f64, spacing / length))
+}
+
+// first cell fraction of a geometric series growing by exp(x) per cell
+fn geometric_first_cell(x: f64, cells: f64) -> f64 {
+    if x.abs() < 1e-12 { 1.0 / cells } else { x.exp_m1() / (cells * x).exp_m1() }
+}
+
+// log of the per cell growth ratio, any first cell shorter than the edge can be met
+fn solve_growth_exponent(cells: f64, target: f64) -> Result<f64, &'static str> {
+    let limit = 700.0 / cells;
+    solve(|x| geometric_first_cell(x, cells).ln() - target.ln(), 0.0, (-limit, limit))
+        .map_err(|_| "spacing can't be met with this many points")
+}
+
+// tanh stretching only ever tightens the end cells, from uniform at beta = 0
+// down to what double precision can still resolve
+fn solve_tanh_beta(cell: impl Fn(f64) -> f64, cells: f64, target: f64) -> Result<f64, &'static str> {
+    if target >= 1.0 / cells {
+        return Err("spacing can't be met with this many points, it is no finer than uniform");
+    }
+    solve(|beta| cell(beta).ln() - target.ln(), 1.0, (1e-6, 15.0))
+        .map_err(|_| "spacing can't be met with this many points")
+}
+
+// newton iteration with a central difference derivative
+fn solve(f: impl Fn(f64) -> f64, guess: f64, bracket: (f64, f64)) -> Result<f64, &'static str> {
+    let h = 1e-7;
+    newton(&f, |x| (f(x + h) - f(x - h)) / (2.0 * h), guess, bracket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> Vec<Box<dyn Distribution>> {
+        vec![
+            Box::new(Uniform),
+            Box::new(HyperbolicTangent::new(2.0).unwrap()),
+            Box::new(TopClusteredTangent::new(2.0).unwrap()),
+            Box::new(Geometric::new(1.1, 21).unwrap()),
+            Box::new(Exponential::new(-3.0)),
+            Box::new(Chebyshev),
+            Box::new(Roberts::new(1.05, 0.0).unwrap()),
+            Box::new(Roberts::new(1.05, 0.5).unwrap()),
+            Box::new(Vinokur::new(0.1, 0.5).unwrap()),
+            Box::new(Vinokur::new(2.0, 0.8).unwrap()),
+            Box::new(Tabulated::new(&[3.0, 3.5, 5.0, 8.0]).unwrap()),
+            Box::new(Reversed(Exponential::new(2.0))),
+        ]
+    }
+
+    #[test]
+    fn test_distributions_fix_ends_and_increase() {
+        for distribution in all() {
+            let params = distribution.sample(21);
+            assert!(params[0].abs() < 1e-12);
+            assert!((params[20] - 1.0).abs() < 1e-12);
+            assert!(params.windows(2).all(|pair| pair[1] > pair[0]));
+        }
+    }
+
+    #[test]
+    fn test_clustering_ends() {
+        let first = |d: &dyn Distribution| d.map(0.05);
+        let last = |d: &dyn Distribution| 1.0 - d.map(0.95);
+
+        let top = TopClusteredTangent::new(2.0).unwrap();
+        assert!(last(&top) < first(&top));
+        let roberts = Roberts::new(1.05, 0.0).unwrap();
+        assert!(last(&roberts) < first(&roberts));
+        let geometric = Geometric::new(1.2, 11).unwrap();
+        assert!(first(&geometric) < last(&geometric));
+        let reversed = Reversed(Geometric::new(1.2, 11).unwrap());
+        assert!(last(&reversed) < first(&reversed));
+    }
+
+    #[test]
+    fn test_vinokur_meets_end_slopes() {
+        for (s0, s1) in [(0.1, 0.5), (2.0, 0.8), (0.05, 0.05), (1.0, 1.0005)] {
+            let vinokur = Vinokur::new(s0, s1).unwrap();
+            let h = 1e-6;
+            assert!((vinokur.map(h) / h - s0).abs() < 1e-3 * s0.max(1.0));
+            assert!(((1.0 - vinokur.map(1.0 - h)) / h - s1).abs() < 1e-3 * s1.max(1.0));
+        }
+    }
+
+    #[test]
+    fn test_geometric_cells_grow_by_ratio() {
+        let params = Geometric::new(1.2, 6).unwrap().sample(6);
+        for k in 1..5 {
+            let ratio = (params[k + 1] - params[k]) / (params[k] - params[k - 1]);
+            assert!((ratio - 1.2).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_tabulated_is_rescaled() {
+        let tabulated = Tabulated::new(&[2.0, 3.0, 6.0]).unwrap();
+        assert!((tabulated.map(0.5) - 0.25).abs() < 1e-12);
+        assert!((tabulated.map(0.75) - 0.625).abs() < 1e-12);
+        assert!(Tabulated::new(&[0.0, 1.0, 1.0]).is_err());
+    }
+
+    fn first_cell(d: &dyn Distribution, length: f64, points: usize) -> f64 {
+        length * d.sample(points)[1]
+    }
+
+    fn last_cell(d: &dyn Distribution, length: f64, points: usize) -> f64 {
+        length * (1.0 - d.sample(points)[points - 2])
+    }
+
+    #[test]
+    fn test_one_sided_spacing_is_met() {
+        let (length, points) = (2.5, 41);
+        for ds in [1e-5, 1e-3, 0.05, 0.2] {
+            let geometric = Geometric::from_spacing(length, points, ds).unwrap();
+            assert!((first_cell(&geometric, length, points) / ds - 1.0).abs() < 1e-8);
+            let exponential = Exponential::from_spacing(length, points, ds).unwrap();
+            assert!((first_cell(&exponential, length, points) / ds - 1.0).abs() < 1e-8);
+        }
+        for ds in [1e-5, 1e-3, 0.05] {
+            let top = TopClusteredTangent::from_spacing(length, points, ds).unwrap();
+            assert!((last_cell(&top, length, points) / ds - 1.0).abs() < 1e-8);
+            let tanh = HyperbolicTangent::from_spacing(length, points, ds).unwrap();
+            assert!((first_cell(&tanh, length, points) / ds - 1.0).abs() < 1e-8);
+            assert!((last_cell(&tanh, length, points) / ds - 1.0).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_vinokur_spacing_is_met() {
+        let (length, points) = (3.0, 61);
+        for (ds_start, ds_end) in [(1e-4, 1e-2), (0.02, 0.2), (0.1, 0.01), (0.05, 0.05)] {
+            let vinokur = Vinokur::from_spacing(length, points, ds_start, ds_end).unwrap();
+            assert!((first_cell(&vinokur, length, points) / ds_start - 1.0).abs() < 1e-8);
+            assert!((last_cell(&vinokur, length, points) / ds_end - 1.0).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_unreachable_spacing_is_rejected() {
+        // tanh can only cluster, so cells coarser than uniform are out of reach
+        assert!(TopClusteredTangent::from_spacing(1.0, 11, 0.2).is_err());
+        assert!(HyperbolicTangent::from_spacing(1.0, 11, 0.1).is_err());
+        assert!(Geometric::from_spacing(1.0, 11, 1.5).is_err());
+        assert!(Vinokur::from_spacing(1.0, 11, 0.6, 0.5).is_err());
+        assert!(Vinokur::from_spacing(1.0, 3, 0.1, 0.1).is_err());
+    }
+}
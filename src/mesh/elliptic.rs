@@ -0,0 +1,581 @@
+#![allow(dead_code)]
+
+use crate::geometry::prelude::*;
+use crate::geometry::vector::{add, dot, norm, scale, sub};
+use crate::mesh::boundary::BoundaryType;
+
+// largest wall source term the sorenson iteration may apply, keeps the early
+// iterations from folding the grid while the interior is still far from converged
+const MAX_WALL_SOURCE: f64 = 2.0;
+
+/// Settings for relaxing a grid towards the solution of the Winslow equations.
+#[derive(Debug, Clone)]
+pub struct EllipticSmoother {
+    pub max_iterations: usize,
+    /// Largest interior point movement in one sweep, relative to the size of
+    /// the grid, below which the grid is taken as converged.
+    pub tolerance: f64,
+    /// Successive over-relaxation factor, 1.0 is plain gauss-seidel.
+    pub relaxation: f64,
+}
+
+impl Default for EllipticSmoother {
+    fn default() -> Self {
+        EllipticSmoother { max_iterations: 1000, tolerance: 1e-6, relaxation: 1.2 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmoothingReport {
+    pub iterations: usize,
+    pub residual: f64,
+    pub converged: bool,
+}
+
+/// Holds the grid orthogonal to one face of the block, with a chosen spacing
+/// between the face and the first line of interior vertices.
+#[derive(Debug, Clone)]
+pub struct WallControl {
+    pub face: BoundaryType,
+    /// Target first cell height, or `None` to keep the spacing of the initial grid.
+    pub spacing: Option<f64>,
+    /// Exponential decay rate of the source terms per grid line away from the face.
+    pub decay: f64,
+    /// Gain applied to the spacing and angle errors when the source terms are
+    /// updated each iteration.
+    pub relaxation: f64,
+}
+
+impl WallControl {
+    pub fn new(face: BoundaryType) -> Self {
+        WallControl { face, spacing: None, decay: 0.3, relaxation: 0.1 }
+    }
+
+    pub fn spacing(mut self, spacing: f64) -> Self {
+        self.spacing = Some(spacing);
+        self
+    }
+
+    pub fn decay(mut self, decay: f64) -> Self {
+        self.decay = decay;
+        self
+    }
+}
+
+/// Source terms for the Poisson form of the elliptic equations.
+#[derive(Debug, Clone, Default)]
+pub struct ControlFunctions {
+    /// Carry the boundary point distribution into the interior with Thomas-Middlecoff
+    /// terms, so clustering along the boundaries is not smoothed away.
+    pub thomas_middlecoff: bool,
+    /// Faces held orthogonal with Sorenson terms, each set up on its own.
+    pub walls: Vec<WallControl>,
+}
+
+impl Vertices<Point2D> {
+    /// Relaxes the interior vertices with the Winslow equations, leaving the
+    /// boundary vertices where they are.
+    pub fn smooth_elliptic(&mut self, smoother: &EllipticSmoother) -> Result<SmoothingReport, &'static str> {
+        let (nx, ny) = self.nx_ny();
+        let mut grid = StructuredGrid::gather(self, [nx, ny, 1])?;
+        let report = grid.relax(smoother, None);
+        grid.scatter(self)?;
+        Ok(report)
+    }
+
+    /// Relaxes the interior vertices with the Poisson equations, using the
+    /// control functions to hold spacing and orthogonality at the boundaries.
+    pub fn smooth_poisson(&mut self, smoother: &EllipticSmoother, controls: &ControlFunctions) -> Result<SmoothingReport, &'static str> {
+        let (nx, ny) = self.nx_ny();
+        let mut grid = StructuredGrid::gather(self, [nx, ny, 1])?;
+        let mut sources = SourceTerms::new(&grid, controls)?;
+        let report = grid.relax(smoother, Some(&mut sources));
+        grid.scatter(self)?;
+        Ok(report)
+    }
+}
+
+impl Vertices<Point3D> {
+    /// Relaxes the interior vertices with the Winslow equations, leaving the
+    /// boundary vertices where they are.
+    pub fn smooth_elliptic(&mut self, smoother: &EllipticSmoother) -> Result<SmoothingReport, &'static str> {
+        let (nx, ny, nz) = self.nx_ny_nz();
+        let mut grid = StructuredGrid::gather(self, [nx, ny, nz])?;
+        let report = grid.relax(smoother, None);
+        grid.scatter(self)?;
+        Ok(report)
+    }
+}
+
+// coordinates copied out of a set of vertices into i-fastest order, with nz = 1 in 2D
+struct StructuredGrid {
+    points: Vec<[f64; 3]>,
+    dims: [usize; 3],
+    ndim: usize,
+}
+
+impl StructuredGrid {
+    fn gather<P: Point>(vertices: &Vertices<P>, dims: [usize; 3]) -> Result<Self, &'static str> {
+        let total = dims[0] * dims[1] * dims[2];
+        if vertices.len() != total {
+            return Err("vertices have not been fully populated");
+        }
+        let ndim = vertices.dimensions();
+        if dims[..ndim].iter().any(|&n| n < 3) {
+            return Err("elliptic smoothing requires at least three vertices in each direction");
+        }
+
+        let points = (0..total).filter_map(|id| vertices.point(id)).collect();
+        Ok(StructuredGrid { points, dims, ndim })
+    }
+
+    fn scatter<P: Coordinates>(&self, vertices: &mut Vertices<P>) -> Result<(), &'static str> {
+        for (id, p) in self.points.iter().enumerate() {
+            vertices.set_coords(id, P::from_coordinates(*p))?;
+        }
+        Ok(())
+    }
+
+    fn strides(&self) -> [usize; 3] {
+        [1, self.dims[0], self.dims[0] * self.dims[1]]
+    }
+
+    // diagonal of the bounding box, used to make the residual independent of scale
+    fn length_scale(&self) -> f64 {
+        let mut min = [f64::MAX; 3];
+        let mut max = [f64::MIN; 3];
+        for p in &self.points {
+            for d in 0..3 {
+                min[d] = min[d].min(p[d]);
+                max[d] = max[d].max(p[d]);
+            }
+        }
+        (0..3).map(|d| (max[d] - min[d]).powi(2)).sum::<f64>().sqrt().max(f64::MIN_POSITIVE)
+    }
+
+    fn relax(&mut self, smoother: &EllipticSmoother, mut sources: Option<&mut SourceTerms>) -> SmoothingReport {
+        let scale = self.length_scale();
+        let [nx, ny, nz] = self.dims;
+        let k_range = if self.ndim == 3 { 1..nz - 1 } else { 0..1 };
+
+        let mut residual = f64::MAX;
+        for iteration in 1..=smoother.max_iterations {
+            // the sources must settle as well as the points before the grid is converged
+            let source_change = match sources.as_deref_mut() {
+                Some(sources) => sources.update(self),
+                None => 0.0,
+            };
+
+            residual = 0.0;
+            for k in k_range.clone() {
+                for j in 1..ny - 1 {
+                    for i in 1..nx - 1 {
+                        let id = i + j * nx + k * nx * ny;
+                        let source = sources.as_ref().map_or([0.0; 3], |s| s.values[id]);
+                        let target = self.winslow_point(id, source);
+                        let current = self.points[id];
+
+                        let mut change = 0.0;
+                        for d in 0..3 {
+                            let delta = smoother.relaxation * (target[d] - current[d]);
+                            self.points[id][d] += delta;
+                            change += delta * delta;
+                        }
+                        residual = f64::max(residual, change.sqrt() / scale);
+                    }
+                }
+            }
+
+            if residual < smoother.tolerance && source_change < smoother.tolerance {
+                return SmoothingReport { iterations: iteration, residual, converged: true };
+            }
+        }
+        SmoothingReport { iterations: smoother.max_iterations, residual, converged: false }
+    }
+
+    // gauss-seidel update for one interior point: the discrete Poisson equations,
+    // sum_mn C_mn r_mn + sum_m C_mm phi_m r_m = 0 where C is the adjugate of the
+    // covariant metric tensor, solved for the point itself. with no sources these
+    // are the Winslow equations
+    fn winslow_point(&self, id: usize, source: [f64; 3]) -> [f64; 3] {
+        let r = &self.points;
+        let s = self.strides();
+        let n = self.ndim;
+
+        let mut tangents = [[0.0; 3]; 3];
+        for m in 0..n {
+            for d in 0..3 {
+                tangents[m][d] = 0.5 * (r[id + s[m]][d] - r[id - s[m]][d]);
+            }
+        }
+        let c = metric_adjugate(&tangents, n);
+
+        let diagonal: f64 = (0..n).map(|m| c[m][m]).sum();
+        if diagonal.abs() < f64::MIN_POSITIVE {
+            return r[id]; // collapsed cell, leave the point alone
+        }
+
+        let mut next = [0.0; 3];
+        for (d, value) in next.iter_mut().enumerate() {
+            let mut sum = 0.0;
+            for m in 0..n {
+                sum += c[m][m] * (r[id + s[m]][d] + r[id - s[m]][d] + source[m] * tangents[m][d]);
+                for l in m + 1..n {
+                    let cross = 0.25 * (r[id + s[m] + s[l]][d] - r[id + s[m] - s[l]][d]
+                        - r[id - s[m] + s[l]][d] + r[id - s[m] - s[l]][d]);
+                    sum += 2.0 * c[m][l] * cross;
+                }
+            }
+            *value = sum / (2.0 * diagonal);
+        }
+        next
+    }
+}
+
+// control function values phi_m at every point, rebuilt each iteration from the
+// fixed thomas-middlecoff part and the sorenson terms decaying away from each wall.
+// the wall terms are driven by the measured first cell height and the angle the
+// grid lines leave the wall at, so they settle once both are met
+struct SourceTerms {
+    values: Vec<[f64; 3]>,
+    base: Vec<[f64; 3]>,
+    walls: Vec<WallState>,
+}
+
+// the source terms held at each point along a wall, tangential then normal
+struct WallState {
+    control: WallControl,
+    face: WallFace,
+    sources: Vec<[f64; 2]>,
+}
+
+// where a face sits in index space: the axis normal to it, the axis along it,
+// which end of the normal axis it is on and the direction into the block
+#[derive(Debug, Clone, Copy)]
+struct WallFace {
+    normal: usize,
+    tangent: usize,
+    index: usize,
+    inward: f64,
+}
+
+impl WallFace {
+    fn new(face: BoundaryType, dims: [usize; 3]) -> Result<Self, &'static str> {
+        let (normal, at_start) = match face {
+            BoundaryType::South => (1, true),
+            BoundaryType::North => (1, false),
+            BoundaryType::West => (0, true),
+            BoundaryType::East => (0, false),
+            _ => return Err("control functions are only available for the faces of 2D blocks"),
+        };
+        let index = if at_start { 0 } else { dims[normal] - 1 };
+        Ok(WallFace { normal, tangent: 1 - normal, index, inward: if at_start { 1.0 } else { -1.0 } })
+    }
+
+    // id of the point `depth` lines into the block at position `along` the face
+    fn id(&self, dims: [usize; 3], along: usize, depth: usize) -> usize {
+        let mut ij = [0; 2];
+        ij[self.tangent] = along;
+        ij[self.normal] = if self.inward > 0.0 { self.index + depth } else { self.index - depth };
+        ij[0] + ij[1] * dims[0]
+    }
+}
+
+impl SourceTerms {
+    fn new(grid: &StructuredGrid, controls: &ControlFunctions) -> Result<Self, &'static str> {
+        if grid.ndim != 2 {
+            return Err("control functions are only available for 2D grids");
+        }
+        let total = grid.points.len();
+        let base = if controls.thomas_middlecoff { thomas_middlecoff(grid) } else { vec![[0.0; 3]; total] };
+
+        let mut walls = Vec::new();
+        for control in &controls.walls {
+            let face = WallFace::new(control.face, grid.dims)?;
+            let mut control = control.clone();
+            if control.spacing.is_none() {
+                // hold on to the spacing the initial grid has half way along the face
+                let mid = grid.dims[face.tangent] / 2;
+                let (p0, p1) = (grid.points[face.id(grid.dims, mid, 0)], grid.points[face.id(grid.dims, mid, 1)]);
+                control.spacing = Some(norm(sub(p1, p0)));
+            }
+            walls.push(WallState { control, face, sources: vec![[0.0; 2]; grid.dims[face.tangent]] });
+        }
+
+        Ok(SourceTerms { values: base.clone(), base, walls })
+    }
+
+    // returns the largest change made to any wall source term
+    fn update(&mut self, grid: &StructuredGrid) -> f64 {
+        let dims = grid.dims;
+        self.values.copy_from_slice(&self.base);
+        let mut largest_change: f64 = 0.0;
+
+        for wall in self.walls.iter_mut() {
+            let (control, face) = (&wall.control, &wall.face);
+            let n_along = dims[face.tangent];
+            let wanted = control.spacing.unwrap();
+            for along in 1..n_along - 1 {
+                let p0 = grid.points[face.id(dims, along, 0)];
+                let p1 = grid.points[face.id(dims, along, 1)];
+                let (prev, next) = (grid.points[face.id(dims, along - 1, 0)], grid.points[face.id(dims, along + 1, 0)]);
+                let (line, tangent) = (sub(p1, p0), sub(next, prev));
+                let actual = norm(line);
+                // cosine between the first grid line and the wall, zero when orthogonal
+                let lean = dot(line, tangent) / (actual * norm(tangent));
+
+                let current = &mut wall.sources[along];
+                let changes = [
+                    -control.relaxation * lean,
+                    -face.inward * control.relaxation * (actual - wanted) / wanted,
+                ];
+                for (value, change) in current.iter_mut().zip(changes) {
+                    let next_value = (*value + change).clamp(-MAX_WALL_SOURCE, MAX_WALL_SOURCE);
+                    largest_change = largest_change.max((next_value - *value).abs());
+                    *value = next_value;
+                }
+            }
+
+            // spread each wall's terms into the block, fading away from the face
+            for depth in 1..dims[face.normal] - 1 {
+                let fade = (-control.decay * (depth - 1) as f64).exp();
+                for (along, wall_source) in wall.sources.iter().enumerate().take(n_along - 1).skip(1) {
+                    let id = face.id(dims, along, depth);
+                    self.values[id][face.tangent] += fade * wall_source[0];
+                    self.values[id][face.normal] += fade * wall_source[1];
+                }
+            }
+        }
+        largest_change
+    }
+}
+
+// phi along the i boundaries and psi along the j boundaries taken from the boundary
+// point distribution, then interpolated linearly across the block
+fn thomas_middlecoff(grid: &StructuredGrid) -> Vec<[f64; 3]> {
+    let [nx, ny, _] = grid.dims;
+    let r = &grid.points;
+    let curvature = |prev: usize, id: usize, next: usize| -> f64 {
+        let d1 = scale(sub(r[next], r[prev]), 0.5);
+        let d2 = add(sub(r[next], scale(r[id], 2.0)), r[prev]);
+        let length = dot(d1, d1);
+        if length > 0.0 { -dot(d1, d2) / length } else { 0.0 }
+    };
+
+    let mut values = vec![[0.0; 3]; nx * ny];
+    for i in 1..nx - 1 {
+        let south = curvature(i - 1, i, i + 1);
+        let top = (ny - 1) * nx;
+        let north = curvature(top + i - 1, top + i, top + i + 1);
+        for j in 0..ny {
+            let t = j as f64 / (ny - 1) as f64;
+            values[i + j * nx][0] = (1.0 - t) * south + t * north;
+        }
+    }
+    for j in 1..ny - 1 {
+        let west = curvature((j - 1) * nx, j * nx, (j + 1) * nx);
+        let east = curvature(j * nx - 1, (j + 1) * nx - 1, (j + 2) * nx - 1);
+        for i in 0..nx {
+            let t = i as f64 / (nx - 1) as f64;
+            values[i + j * nx][1] = (1.0 - t) * west + t * east;
+        }
+    }
+    values
+}
+
+// adjugate of the covariant metric g_mn = r_m . r_n for the first n tangents
+fn metric_adjugate(tangents: &[[f64; 3]; 3], n: usize) -> [[f64; 3]; 3] {
+    let mut g = [[0.0; 3]; 3];
+    for m in 0..n {
+        for l in 0..n {
+            g[m][l] = dot(tangents[m], tangents[l]);
+        }
+    }
+
+    let mut c = [[0.0; 3]; 3];
+    if n == 2 {
+        c[0][0] = g[1][1];
+        c[1][1] = g[0][0];
+        c[0][1] = -g[0][1];
+        c[1][0] = -g[1][0];
+    } else {
+        for (m, row) in c.iter_mut().enumerate() {
+            for (l, value) in row.iter_mut().enumerate() {
+                let (m1, m2) = ((m + 1) % 3, (m + 2) % 3);
+                let (l1, l2) = ((l + 1) % 3, (l + 2) % 3);
+                // g is symmetric so the cofactor and adjugate coincide
+                *value = g[m1][l1] * g[m2][l2] - g[m1][l2] * g[m2][l1];
+            }
+        }
+    }
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // uniform unit square with the interior vertices pushed off their positions
+    fn perturbed_square(n: usize) -> Vertices<Point2D> {
+        let mut vertices = Vertices::new_2d(n, n);
+        for j in 0..n {
+            for i in 0..n {
+                let (mut x, mut y) = (i as f64 / (n - 1) as f64, j as f64 / (n - 1) as f64);
+                if i > 0 && i < n - 1 && j > 0 && j < n - 1 {
+                    x += 0.3 / (n - 1) as f64 * ((i * 7 + j * 3) as f64).sin();
+                    y += 0.3 / (n - 1) as f64 * ((i * 5 + j * 11) as f64).cos();
+                }
+                vertices.add_vertex(Vertex::new_2d(i + j * n, x, y)).unwrap();
+            }
+        }
+        vertices
+    }
+
+    #[test]
+    fn test_smoothing_recovers_uniform_square() {
+        let n = 11;
+        let mut vertices = perturbed_square(n);
+        let report = vertices.smooth_elliptic(&EllipticSmoother::default()).unwrap();
+        assert!(report.converged);
+
+        for vertex in vertices.vertices() {
+            let (i, j) = vertices.vertex_id_to_ij(vertex.get_id()).unwrap();
+            assert!((vertex.get_x() - i as f64 / (n - 1) as f64).abs() < 1e-4);
+            assert!((vertex.get_y() - j as f64 / (n - 1) as f64).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_smoothing_keeps_boundary_fixed() {
+        let mut vertices = Vertices::new_2d(9, 7);
+        let south: Vec<Point2D> = (0..9).map(|i| Point2D::new(i as f64 / 8.0, 0.0)).collect();
+        let north: Vec<Point2D> = (0..9).map(|i| {
+            let x = i as f64 / 8.0;
+            Point2D::new(x, 1.0 - 0.5 * x * x)
+        }).collect();
+        let west: Vec<Point2D> = (0..7).map(|j| Point2D::new(0.0, j as f64 / 6.0)).collect();
+        let east: Vec<Point2D> = (0..7).map(|j| Point2D::new(1.0, 0.5 * j as f64 / 6.0)).collect();
+        vertices.populate_tfi(&south, &north, &west, &east, crate::mesh::tfi::Blending::Linear).unwrap();
+
+        let on_boundary = |id: usize| {
+            let (i, j) = (id % 9, id / 9);
+            i == 0 || i == 8 || j == 0 || j == 6
+        };
+        let before: Vec<(f64, f64)> = vertices.vertices().map(|v| (v.get_x(), v.get_y())).collect();
+
+        let smoother = EllipticSmoother { max_iterations: 50, ..Default::default() };
+        vertices.smooth_elliptic(&smoother).unwrap();
+
+        let mut moved = false;
+        for (vertex, (x, y)) in vertices.vertices().zip(before) {
+            if on_boundary(vertex.get_id()) {
+                assert_eq!((vertex.get_x(), vertex.get_y()), (x, y));
+            } else {
+                moved |= vertex.get_x() != x || vertex.get_y() != y;
+            }
+        }
+        assert!(moved);
+    }
+
+    #[test]
+    fn test_smoothing_3d_cube() {
+        let mut vertices = Vertices::new_3d(5, 5, 5);
+        vertices.populate_uniform();
+        let centre = 2 + 2 * 5 + 2 * 25;
+        vertices.set_coords(centre, Point3D::new(0.6, 0.4, 0.55)).unwrap();
+
+        let report = vertices.smooth_elliptic(&EllipticSmoother::default()).unwrap();
+        assert!(report.converged);
+        let vertex = vertices.get_vertex(centre).unwrap();
+        assert!((vertex.get_x() - 0.5).abs() < 1e-4);
+        assert!((vertex.get_y() - 0.5).abs() < 1e-4);
+        assert!((vertex.get_z() - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_smoothing_rejects_incomplete_vertices() {
+        let mut vertices = Vertices::new_2d(4, 4);
+        vertices.add_vertex(Vertex::new_2d(0, 0.0, 0.0)).unwrap();
+        assert!(vertices.smooth_elliptic(&EllipticSmoother::default()).is_err());
+    }
+
+    // channel under a curved north wall, with the side edges clustered towards the south wall
+    fn curved_channel(nx: usize, ny: usize, cluster: f64) -> Vertices<Point2D> {
+        let wall = |x: f64| 1.0 - 0.3 * x * x;
+        let stretch = |t: f64| if cluster > 0.0 { 1.0 + (cluster * (t - 1.0)).tanh() / cluster.tanh() } else { t };
+        let south: Vec<Point2D> = (0..nx).map(|i| Point2D::new(i as f64 / (nx - 1) as f64, 0.0)).collect();
+        let north: Vec<Point2D> = south.iter().map(|p| Point2D::new(p.x(), wall(p.x()))).collect();
+        let side = |x: f64| -> Vec<Point2D> {
+            (0..ny).map(|j| Point2D::new(x, wall(x) * stretch(j as f64 / (ny - 1) as f64))).collect()
+        };
+        let mut vertices = Vertices::new_2d(nx, ny);
+        vertices.populate_tfi(&south, &north, &side(0.0), &side(1.0), crate::mesh::tfi::Blending::Linear).unwrap();
+        vertices
+    }
+
+    fn point(vertices: &Vertices<Point2D>, id: usize) -> (f64, f64) {
+        let vertex = vertices.get_vertex(id).unwrap();
+        (vertex.get_x(), vertex.get_y())
+    }
+
+    #[test]
+    fn test_sorenson_controls_hold_wall_spacing_and_orthogonality() {
+        let (nx, ny) = (21, 21);
+        let mut vertices = curved_channel(nx, ny, 0.0);
+        let controls = ControlFunctions {
+            thomas_middlecoff: false,
+            walls: vec![
+                WallControl::new(BoundaryType::South).spacing(0.01),
+                WallControl::new(BoundaryType::North).spacing(0.01),
+            ],
+        };
+        let smoother = EllipticSmoother { max_iterations: 3000, ..Default::default() };
+        let report = vertices.smooth_poisson(&smoother, &controls).unwrap();
+        assert!(report.converged);
+
+        // first cell height off the south wall
+        let (x0, y0) = point(&vertices, nx / 2);
+        let (x1, y1) = point(&vertices, nx / 2 + nx);
+        assert!(((x1 - x0).hypot(y1 - y0) - 0.01).abs() < 0.002);
+        assert!((x1 - x0).abs() / (y1 - y0) < 0.05);
+
+        // grid lines leave the curved north wall at right angles
+        let top = (ny - 1) * nx + nx / 2;
+        let (wx0, wy0) = point(&vertices, top - 1);
+        let (wx1, wy1) = point(&vertices, top + 1);
+        let (px, py) = point(&vertices, top);
+        let (qx, qy) = point(&vertices, top - nx);
+        let (tx, ty, lx, ly) = (wx1 - wx0, wy1 - wy0, qx - px, qy - py);
+        let cosine = (tx * lx + ty * ly) / (tx.hypot(ty) * lx.hypot(ly));
+        assert!(cosine.abs() < 0.05);
+        assert!((lx.hypot(ly) - 0.01).abs() < 0.002);
+    }
+
+    #[test]
+    fn test_thomas_middlecoff_keeps_boundary_clustering() {
+        let (nx, ny) = (21, 21);
+        let first_height = |vertices: &Vertices<Point2D>| {
+            let (_, y0) = point(vertices, nx / 2);
+            let (_, y1) = point(vertices, nx / 2 + nx);
+            y1 - y0
+        };
+        let initial = first_height(&curved_channel(nx, ny, 2.0));
+
+        let mut winslow = curved_channel(nx, ny, 2.0);
+        winslow.smooth_elliptic(&EllipticSmoother::default()).unwrap();
+
+        let mut poisson = curved_channel(nx, ny, 2.0);
+        let controls = ControlFunctions { thomas_middlecoff: true, walls: Vec::new() };
+        poisson.smooth_poisson(&EllipticSmoother::default(), &controls).unwrap();
+
+        assert!(first_height(&winslow) > 1.5 * initial);
+        assert!((first_height(&poisson) - initial).abs() < 0.2 * initial);
+    }
+
+    #[test]
+    fn test_control_functions_reject_3d_faces() {
+        let mut vertices = curved_channel(5, 5, 0.0);
+        let controls = ControlFunctions { thomas_middlecoff: false, walls: vec![WallControl::new(BoundaryType::Top)] };
+        assert!(vertices.smooth_poisson(&EllipticSmoother::default(), &controls).is_err());
+    }
+}
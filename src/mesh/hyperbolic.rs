@@ -0,0 +1,385 @@
+#![allow(dead_code)]
+
+use crate::geometry::prelude::*;
+
+// how close the two ends of a wall must be for it to be treated as closed
+const CLOSURE_TOLERANCE: f64 = 1e-10;
+
+/// Settings for marching a grid away from a wall with the hyperbolic
+/// (Steger-Chaussee) equations.
+#[derive(Debug, Clone)]
+pub struct HyperbolicMarch {
+    /// Implicit second difference dissipation applied along each new layer,
+    /// which keeps grid lines from crossing off concave walls.
+    pub dissipation: f64,
+    /// Passes of neighbour averaging applied to the cell areas of each layer.
+    pub area_smoothing: usize,
+}
+
+impl Default for HyperbolicMarch {
+    fn default() -> Self {
+        HyperbolicMarch { dissipation: 0.5, area_smoothing: 2 }
+    }
+}
+
+impl Vertices<Point2D> {
+    /// Marches a grid away from the wall, which becomes the j = 0 line. Each
+    /// entry of `steps` is the distance marched to the next layer, so ny must be
+    /// one more than the number of steps. The grid grows to the left of the wall
+    /// as it is walked in increasing i, so closed bodies should be given
+    /// clockwise. A wall whose ends meet produces a periodic O-grid.
+    pub fn populate_hyperbolic(&mut self, wall: &[Point2D], steps: &[f64], settings: &HyperbolicMarch) -> Result<(), &'static str> {
+        let (nx, ny) = self.nx_ny();
+        if !self.is_empty() {
+            return Err("vertices have already been populated");
+        }
+        if wall.len() != nx {
+            return Err("wall must have one point per vertex column");
+        }
+        if steps.len() + 1 != ny {
+            return Err("there must be one march step per layer above the wall");
+        }
+        if nx < 3 {
+            return Err("hyperbolic marching requires at least three points on the wall");
+        }
+        if steps.iter().any(|&s| s <= 0.0) {
+            return Err("march steps must be positive");
+        }
+
+        let closed = (wall[0].x() - wall[nx - 1].x()).hypot(wall[0].y() - wall[nx - 1].y()) < CLOSURE_TOLERANCE;
+        let mut layer: Vec<[f64; 2]> = wall.iter().map(|p| [p.x(), p.y()]).collect();
+        // every layer is marched before any is stored, so a fold leaves the vertices empty
+        let mut points = layer.clone();
+        for step in steps {
+            let next = march_layer(&layer, *step, closed, settings);
+            for i in 0..nx - 1 {
+                let along = [layer[i + 1][0] - layer[i][0], layer[i + 1][1] - layer[i][1]];
+                let up = [next[i][0] - layer[i][0], next[i][1] - layer[i][1]];
+                if along[0] * up[1] - along[1] * up[0] <= 0.0 {
+                    return Err("hyperbolic march folded the grid, reduce the step size or raise the dissipation");
+                }
+            }
+            points.extend_from_slice(&next);
+            layer = next;
+        }
+
+        for (id, p) in points.iter().enumerate() {
+            self.add_vertex(Vertex::new_2d(id, p[0], p[1]))?;
+        }
+        Ok(())
+    }
+
+    /// Marches a grid away from a wall given as a chain of lines, each starting
+    /// where the previous one ended.
+    pub fn populate_hyperbolic_from_lines(&mut self, wall: &LineCollection<Point2D>, steps: &[f64], settings: &HyperbolicMarch) -> Result<(), &'static str> {
+        let lines = wall.lines();
+        if lines.is_empty() {
+            return Err("wall has no lines");
+        }
+        let mut points = vec![Point2D::new(lines[0].start.get_x(), lines[0].start.get_y())];
+        for pair in lines.windows(2) {
+            let (end, start) = (pair[0].end, pair[1].start);
+            if (end.get_x() - start.get_x()).hypot(end.get_y() - start.get_y()) > CLOSURE_TOLERANCE {
+                return Err("wall lines do not form a connected chain");
+            }
+        }
+        points.extend(lines.iter().map(|l| Point2D::new(l.end.get_x(), l.end.get_y())));
+        self.populate_hyperbolic(&points, steps, settings)
+    }
+}
+
+type Mat2 = [[f64; 2]; 2];
+
+// one implicit step of the linearised hyperbolic equations
+//     r_xi . r_eta = 0,  r_xi x r_eta = dA
+// written in delta form as  dr + C dr_xi - eps dr_xixi = g  with C = B^-1 A,
+// where g is the step along the normal that alone satisfies both equations
+fn march_layer(layer: &[[f64; 2]], step: f64, closed: bool, settings: &HyperbolicMarch) -> Vec<[f64; 2]> {
+    let n = layer.len();
+    // a closed wall repeats its first point at the end, solve for the unique ones
+    let m = if closed { n - 1 } else { n };
+    let index = |i: isize| -> usize {
+        if closed { i.rem_euclid(m as isize) as usize } else { i.clamp(0, n as isize - 1) as usize }
+    };
+
+    let tangents = central_tangents(layer, closed);
+
+    // the area equation holds along the new layer, so size the cells from where a
+    // plain normal step would put it, then smooth them so concave regions do not starve
+    let mut predicted: Vec<[f64; 2]> = layer[..m].iter().zip(&tangents).map(|(p, t)| {
+        let length = t[0].hypot(t[1]);
+        [p[0] - step * t[1] / length, p[1] + step * t[0] / length]
+    }).collect();
+    if closed {
+        predicted.push(predicted[0]);
+    }
+    let mut areas: Vec<f64> = central_tangents(&predicted, closed).iter().map(|t| step * t[0].hypot(t[1])).collect();
+    for _ in 0..settings.area_smoothing {
+        areas = (0..m).map(|i| {
+            if !closed && (i == 0 || i == n - 1) { return areas[i]; }
+            let (prev, next) = (index(i as isize - 1), index(i as isize + 1));
+            0.25 * areas[prev] + 0.5 * areas[i] + 0.25 * areas[next]
+        }).collect();
+    }
+
+    // marching along the left normal with the local area gives the zeroth order step
+    let normal_steps: Vec<[f64; 2]> = tangents.iter().zip(&areas).map(|(t, area)| {
+        let norm = t[0] * t[0] + t[1] * t[1];
+        [-t[1] * area / norm, t[0] * area / norm]
+    }).collect();
+
+    let eps = settings.dissipation;
+    let mut lower = vec![[[0.0; 2]; 2]; m];
+    let mut diag = vec![[[0.0; 2]; 2]; m];
+    let mut upper = vec![[[0.0; 2]; 2]; m];
+    for i in 0..m {
+        if !closed && (i == 0 || i == n - 1) {
+            // open ends march straight along their normal
+            diag[i] = [[1.0, 0.0], [0.0, 1.0]];
+            continue;
+        }
+        let (u, v) = (tangents[i], normal_steps[i]);
+        let a: Mat2 = [[v[0], v[1]], [v[1], -v[0]]];
+        let norm = u[0] * u[0] + u[1] * u[1];
+        let b_inv: Mat2 = [[u[0] / norm, -u[1] / norm], [u[1] / norm, u[0] / norm]];
+        let c = mat_mul(&b_inv, &a);
+
+        lower[i] = [[-0.5 * c[0][0] - eps, -0.5 * c[0][1]], [-0.5 * c[1][0], -0.5 * c[1][1] - eps]];
+        diag[i] = [[1.0 + 2.0 * eps, 0.0], [0.0, 1.0 + 2.0 * eps]];
+        upper[i] = [[0.5 * c[0][0] - eps, 0.5 * c[0][1]], [0.5 * c[1][0], 0.5 * c[1][1] - eps]];
+    }
+
+    let deltas = if closed {
+        solve_periodic(&lower, &diag, &upper, &normal_steps)
+    } else {
+        solve_block_tridiagonal(&lower, &diag, &upper, &normal_steps)
+    };
+
+    let mut next: Vec<[f64; 2]> = (0..m).map(|i| [layer[i][0] + deltas[i][0], layer[i][1] + deltas[i][1]]).collect();
+    if closed {
+        next.push(next[0]);
+    }
+    next
+}
+
+// central differences along a layer, one sided at the ends of an open one
+fn central_tangents(layer: &[[f64; 2]], closed: bool) -> Vec<[f64; 2]> {
+    let n = layer.len();
+    let m = if closed { n - 1 } else { n };
+    (0..m).map(|i| {
+        let (prev, next, span) = if closed {
+            ((i + m - 1) % m, (i + 1) % m, 2.0)
+        } else {
+            let (prev, next) = (i.saturating_sub(1), (i + 1).min(n - 1));
+            (prev, next, (next - prev) as f64)
+        };
+        [(layer[next][0] - layer[prev][0]) / span, (layer[next][1] - layer[prev][1]) / span]
+    }).collect()
+}
+
+fn mat_mul(a: &Mat2, b: &Mat2) -> Mat2 {
+    [
+        [a[0][0] * b[0][0] + a[0][1] * b[1][0], a[0][0] * b[0][1] + a[0][1] * b[1][1]],
+        [a[1][0] * b[0][0] + a[1][1] * b[1][0], a[1][0] * b[0][1] + a[1][1] * b[1][1]],
+    ]
+}
+
+fn mat_vec(a: &Mat2, v: &[f64; 2]) -> [f64; 2] {
+    [a[0][0] * v[0] + a[0][1] * v[1], a[1][0] * v[0] + a[1][1] * v[1]]
+}
+
+fn mat_inv(a: &Mat2) -> Mat2 {
+    let det = a[0][0] * a[1][1] - a[0][1] * a[1][0];
+    [[a[1][1] / det, -a[0][1] / det], [-a[1][0] / det, a[0][0] / det]]
+}
+
+fn mat_sub(a: &Mat2, b: &Mat2) -> Mat2 {
+    [[a[0][0] - b[0][0], a[0][1] - b[0][1]], [a[1][0] - b[1][0], a[1][1] - b[1][1]]]
+}
+
+// block thomas algorithm for 2x2 blocks, the right hand side carries a vector
+// alongside two extra columns so the periodic solve can reuse it
+fn solve_block_tridiagonal_columns(lower: &[Mat2], diag: &[Mat2], upper: &[Mat2], rhs: &[[[f64; 2]; 3]]) -> Vec<[[f64; 2]; 3]> {
+    let n = diag.len();
+    let mut c_prime = vec![[[0.0; 2]; 2]; n];
+    let mut d_prime = vec![[[0.0; 2]; 3]; n];
+
+    for i in 0..n {
+        let (denominator, carried) = if i == 0 {
+            (diag[0], [[0.0; 2]; 3])
+        } else {
+            let carried = rhs_mul(&lower[i], &d_prime[i - 1]);
+            (mat_sub(&diag[i], &mat_mul(&lower[i], &c_prime[i - 1])), carried)
+        };
+        let inverse = mat_inv(&denominator);
+        c_prime[i] = mat_mul(&inverse, &upper[i]);
+        let mut reduced = rhs[i];
+        for (column, carry) in reduced.iter_mut().zip(carried) {
+            column[0] -= carry[0];
+            column[1] -= carry[1];
+        }
+        d_prime[i] = rhs_mul(&inverse, &reduced);
+    }
+
+    let mut x = d_prime.clone();
+    for i in (0..n - 1).rev() {
+        let correction = rhs_mul(&c_prime[i], &x[i + 1]);
+        for (column, carry) in x[i].iter_mut().zip(correction) {
+            column[0] -= carry[0];
+            column[1] -= carry[1];
+        }
+    }
+    x
+}
+
+fn rhs_mul(a: &Mat2, columns: &[[f64; 2]; 3]) -> [[f64; 2]; 3] {
+    columns.map(|column| mat_vec(a, &column))
+}
+
+fn solve_block_tridiagonal(lower: &[Mat2], diag: &[Mat2], upper: &[Mat2], rhs: &[[f64; 2]]) -> Vec<[f64; 2]> {
+    let columns: Vec<[[f64; 2]; 3]> = rhs.iter().map(|r| [*r, [0.0; 2], [0.0; 2]]).collect();
+    solve_block_tridiagonal_columns(lower, diag, upper, &columns).into_iter().map(|c| c[0]).collect()
+}
+
+// periodic block tridiagonal system: the last unknown couples back to the first.
+// solve the open system for the other unknowns in terms of the last, then close
+// the loop with the last row
+fn solve_periodic(lower: &[Mat2], diag: &[Mat2], upper: &[Mat2], rhs: &[[f64; 2]]) -> Vec<[f64; 2]> {
+    let n = diag.len();
+    let last = n - 1;
+
+    let mut columns: Vec<[[f64; 2]; 3]> = rhs[..last].iter().map(|r| [*r, [0.0; 2], [0.0; 2]]).collect();
+    // coupling of the first and second to last rows to the last unknown, moved to the right hand side
+    for (row, block) in [(0, lower[0]), (last - 1, upper[last - 1])] {
+        for c in 0..2 {
+            columns[row][c + 1][0] -= block[0][c];
+            columns[row][c + 1][1] -= block[1][c];
+        }
+    }
+    let mut inner_lower = lower[..last].to_vec();
+    let mut inner_upper = upper[..last].to_vec();
+    inner_lower[0] = [[0.0; 2]; 2];
+    inner_upper[last - 1] = [[0.0; 2]; 2];
+    let solved = solve_block_tridiagonal_columns(&inner_lower, &diag[..last], &inner_upper, &columns);
+
+    // x_i = p_i + Q_i x_last
+    let p = |i: usize| solved[i][0];
+    let q = |i: usize| -> Mat2 { [[solved[i][1][0], solved[i][2][0]], [solved[i][1][1], solved[i][2][1]]] };
+
+    let system = {
+        let from_prev = mat_mul(&lower[last], &q(last - 1));
+        let from_first = mat_mul(&upper[last], &q(0));
+        let mut s = diag[last];
+        for r in 0..2 {
+            for c in 0..2 {
+                s[r][c] += from_prev[r][c] + from_first[r][c];
+            }
+        }
+        s
+    };
+    let known = {
+        let (a, b) = (mat_vec(&lower[last], &p(last - 1)), mat_vec(&upper[last], &p(0)));
+        [rhs[last][0] - a[0] - b[0], rhs[last][1] - a[1] - b[1]]
+    };
+    let x_last = mat_vec(&mat_inv(&system), &known);
+
+    let mut x: Vec<[f64; 2]> = (0..last).map(|i| {
+        let coupled = mat_vec(&q(i), &x_last);
+        [p(i)[0] + coupled[0], p(i)[1] + coupled[1]]
+    }).collect();
+    x.push(x_last);
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_march_from_flat_wall() {
+        let wall: Vec<Point2D> = (0..11).map(|i| Point2D::new(i as f64 / 10.0, 0.0)).collect();
+        let steps = [0.01, 0.02, 0.04, 0.08];
+        let mut vertices = Vertices::new_2d(11, 5);
+        vertices.populate_hyperbolic(&wall, &steps, &HyperbolicMarch::default()).unwrap();
+
+        let mut height = 0.0;
+        for (j, step) in steps.iter().enumerate() {
+            height += step;
+            for i in 0..11 {
+                let vertex = vertices.get_vertex(i + (j + 1) * 11).unwrap();
+                assert!((vertex.get_x() - i as f64 / 10.0).abs() < 1e-12);
+                assert!((vertex.get_y() - height).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_o_grid_around_circle() {
+        // clockwise around the unit circle so the grid grows outwards
+        let nx = 65;
+        let wall: Vec<Point2D> = (0..nx).map(|i| {
+            let theta = -2.0 * std::f64::consts::PI * i as f64 / (nx - 1) as f64;
+            Point2D::new(theta.cos(), theta.sin())
+        }).collect();
+        let steps: Vec<f64> = (0..20).map(|k| 0.01 * 1.2_f64.powi(k)).collect();
+        let mut vertices = Vertices::new_2d(nx, steps.len() + 1);
+        vertices.populate_hyperbolic(&wall, &steps, &HyperbolicMarch::default()).unwrap();
+
+        let outer = 1.0 + steps.iter().sum::<f64>();
+        let ny = steps.len() + 1;
+        for i in 0..nx {
+            let vertex = vertices.get_vertex(i + (ny - 1) * nx).unwrap();
+            let r = vertex.get_x().hypot(vertex.get_y());
+            assert!((r - outer).abs() < 0.01 * outer);
+        }
+
+        // radial grid lines, and the seam stays closed
+        let first = vertices.get_vertex(5 + nx).unwrap();
+        let wall_point = vertices.get_vertex(5).unwrap();
+        let (dx, dy) = (first.get_x() - wall_point.get_x(), first.get_y() - wall_point.get_y());
+        let cross = (dx * wall_point.get_y() - dy * wall_point.get_x()).abs() / dx.hypot(dy);
+        assert!(cross < 1e-3);
+        let (start, end) = (vertices.get_vertex((ny - 1) * nx).unwrap(), vertices.get_vertex(ny * nx - 1).unwrap());
+        assert_eq!((start.get_x(), start.get_y()), (end.get_x(), end.get_y()));
+    }
+
+    #[test]
+    fn test_march_off_concave_wall_does_not_fold() {
+        // inside of a shallow arc, the normals converge
+        let nx = 41;
+        let wall: Vec<Point2D> = (0..nx).map(|i| {
+            let x = -1.0 + 2.0 * i as f64 / (nx - 1) as f64;
+            Point2D::new(x, 0.3 * x * x)
+        }).collect();
+        let steps = vec![0.05; 15];
+        let mut vertices = Vertices::new_2d(nx, steps.len() + 1);
+        assert!(vertices.populate_hyperbolic(&wall, &steps, &HyperbolicMarch::default()).is_ok());
+
+        // marched far past the centre of curvature the layers cross, and nothing is kept
+        let undamped = HyperbolicMarch { dissipation: 0.0, area_smoothing: 0 };
+        let mut vertices = Vertices::new_2d(nx, steps.len() + 1);
+        let error = vertices.populate_hyperbolic(&wall, &[1.0; 15], &undamped).unwrap_err();
+        assert!(error.starts_with("hyperbolic march folded the grid"));
+        assert!(vertices.is_empty());
+    }
+
+    #[test]
+    fn test_march_from_line_chain() {
+        let points: Vec<Vertex<Point2D>> = (0..5).map(|i| Vertex::new_2d(i, i as f64 * 0.25, 0.0)).collect();
+        let mut chain = LineCollection::new();
+        for pair in points.windows(2) {
+            chain.add_line(Line::new_2d(&pair[0], &pair[1])).unwrap();
+        }
+
+        let mut vertices = Vertices::new_2d(5, 3);
+        vertices.populate_hyperbolic_from_lines(&chain, &[0.1, 0.1], &HyperbolicMarch::default()).unwrap();
+        assert_eq!(vertices.len(), 15);
+        assert!((vertices.get_vertex(14).unwrap().get_y() - 0.2).abs() < 1e-12);
+
+        let mut broken = LineCollection::new();
+        broken.add_line(Line::new_2d(&points[0], &points[1])).unwrap();
+        broken.add_line(Line::new_2d(&points[2], &points[3])).unwrap();
+        let mut vertices = Vertices::new_2d(3, 3);
+        assert!(vertices.populate_hyperbolic_from_lines(&broken, &[0.1, 0.1], &HyperbolicMarch::default()).is_err());
+    }
+}
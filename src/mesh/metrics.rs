@@ -0,0 +1,234 @@
+#![allow(dead_code)]
+
+use crate::geometry::prelude::*;
+use crate::geometry::vector::{cross, dot, norm, sub};
+use crate::mesh::nodes::{Node, Nodes};
+
+// relative to the summed face areas, how far a cell's area vectors may miss closing
+const CLOSURE_TOLERANCE: f64 = 1e-10;
+
+const FACES_2D: [Direction; 4] = [Direction::South, Direction::East, Direction::North, Direction::West];
+const FACES_3D: [Direction; 6] = [
+    Direction::South, Direction::East, Direction::North, Direction::West, Direction::Down, Direction::Up,
+];
+
+impl Node<Point2D> {
+    /// The signed area of the cell, positive when its corners run anticlockwise.
+    pub fn area(&self, vertices: &Vertices<Point2D>) -> Option<f64> {
+        let corners = self.corner_points(vertices)?;
+        Some((0..4).map(|k| {
+            let (a, b) = (&corners[k], &corners[(k + 1) % 4]);
+            0.5 * (a.x() * b.y() - b.x() * a.y())
+        }).sum())
+    }
+
+    /// The outward normal of a face scaled by the face's length.
+    pub fn face_area_vector(&self, direction: Direction, vertices: &Vertices<Point2D>) -> Option<[f64; 2]> {
+        let [a, b] = self.face(direction)?;
+        let (a, b) = (vertices.get_vertex(a)?, vertices.get_vertex(b)?);
+        // faces run anticlockwise, so outward is to their right
+        Some([b.get_y() - a.get_y(), a.get_x() - b.get_x()])
+    }
+
+    pub fn face_area(&self, direction: Direction, vertices: &Vertices<Point2D>) -> Option<f64> {
+        let s = self.face_area_vector(direction, vertices)?;
+        Some(s[0].hypot(s[1]))
+    }
+
+    /// The unit outward normal of a face.
+    pub fn face_normal(&self, direction: Direction, vertices: &Vertices<Point2D>) -> Option<[f64; 2]> {
+        let s = self.face_area_vector(direction, vertices)?;
+        let length = s[0].hypot(s[1]);
+        Some([s[0] / length, s[1] / length])
+    }
+
+    /// How far the area vectors of the faces are from summing to zero, relative
+    /// to their total length.
+    pub fn closure_error(&self, vertices: &Vertices<Point2D>) -> Option<f64> {
+        let (mut sum, mut total) = ([0.0; 2], 0.0);
+        for direction in FACES_2D {
+            let s = self.face_area_vector(direction, vertices)?;
+            sum = [sum[0] + s[0], sum[1] + s[1]];
+            total += s[0].hypot(s[1]);
+        }
+        Some(sum[0].hypot(sum[1]) / total)
+    }
+}
+
+impl Node<Point3D> {
+    /// The volume of the hexahedron, split into 24 tetrahedra joining the
+    /// centroid to each face centre and pair of neighbouring face corners. The
+    /// split follows the face area vectors, so the volume is consistent with
+    /// them even when faces are warped.
+    pub fn volume(&self, vertices: &Vertices<Point3D>) -> Option<f64> {
+        let centre = self.centroid(vertices)?.coordinates();
+        let mut volume = 0.0;
+        for direction in FACES_3D {
+            let corners = self.face_points(direction, vertices)?;
+            let face_centre = self.face_midpoint(direction, vertices)?.coordinates();
+            let height = sub(face_centre, centre);
+            for k in 0..4 {
+                let (a, b) = (sub(corners[k], face_centre), sub(corners[(k + 1) % 4], face_centre));
+                volume += dot(cross(a, b), height) / 6.0;
+            }
+        }
+        Some(volume)
+    }
+
+    /// The outward area vector of a quad face, half the cross product of its
+    /// diagonals, which holds for warped faces as well as flat ones.
+    pub fn face_area_vector(&self, direction: Direction, vertices: &Vertices<Point3D>) -> Option<[f64; 3]> {
+        let p = self.face_points(direction, vertices)?;
+        let s = cross(sub(p[2], p[0]), sub(p[3], p[1]));
+        Some([0.5 * s[0], 0.5 * s[1], 0.5 * s[2]])
+    }
+
+    pub fn face_area(&self, direction: Direction, vertices: &Vertices<Point3D>) -> Option<f64> {
+        Some(norm(self.face_area_vector(direction, vertices)?))
+    }
+
+    /// The unit outward normal of a face.
+    pub fn face_normal(&self, direction: Direction, vertices: &Vertices<Point3D>) -> Option<[f64; 3]> {
+        let s = self.face_area_vector(direction, vertices)?;
+        let length = norm(s);
+        Some([s[0] / length, s[1] / length, s[2] / length])
+    }
+
+    /// How far the area vectors of the faces are from summing to zero, relative
+    /// to their total area.
+    pub fn closure_error(&self, vertices: &Vertices<Point3D>) -> Option<f64> {
+        let (mut sum, mut total) = ([0.0; 3], 0.0);
+        for direction in FACES_3D {
+            let s = self.face_area_vector(direction, vertices)?;
+            sum = [sum[0] + s[0], sum[1] + s[1], sum[2] + s[2]];
+            total += norm(s);
+        }
+        Some(norm(sum) / total)
+    }
+
+    fn face_points(&self, direction: Direction, vertices: &Vertices<Point3D>) -> Option<[[f64; 3]; 4]> {
+        let ids = self.face(direction)?;
+        let mut points = [[0.0; 3]; 4];
+        for (point, id) in points.iter_mut().zip(ids) {
+            let v = vertices.get_vertex(id)?;
+            *point = [v.get_x(), v.get_y(), v.get_z()];
+        }
+        Some(points)
+    }
+}
+
+impl Nodes<Point2D> {
+    /// Checks that every cell is closed and has positive area.
+    pub fn check_metrics(&self, vertices: &Vertices<Point2D>) -> Result<(), &'static str> {
+        for node in &self.nodes {
+            let area = node.area(vertices).ok_or("node refers to a vertex that does not exist")?;
+            if area <= 0.0 {
+                return Err("cell has zero or negative area");
+            }
+            if node.closure_error(vertices).ok_or("node refers to a vertex that does not exist")? > CLOSURE_TOLERANCE {
+                return Err("cell face area vectors do not sum to zero");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Nodes<Point3D> {
+    /// Checks that every cell is closed and has positive volume.
+    pub fn check_metrics(&self, vertices: &Vertices<Point3D>) -> Result<(), &'static str> {
+        for node in &self.nodes {
+            let volume = node.volume(vertices).ok_or("node refers to a vertex that does not exist")?;
+            if volume <= 0.0 {
+                return Err("cell has zero or negative volume");
+            }
+            if node.closure_error(vertices).ok_or("node refers to a vertex that does not exist")? > CLOSURE_TOLERANCE {
+                return Err("cell face area vectors do not sum to zero");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::block::BlockBuilder;
+    use crate::mesh::boundary::{Boundary2D, Boundary3D, BoundaryType};
+
+    #[test]
+    fn test_2d_metrics_on_unit_square() {
+        let mut vertices = Vertices::new_2d(3, 3);
+        vertices.populate_uniform();
+        let mut nodes = Nodes::new_2d();
+        nodes.populate(&vertices).unwrap();
+
+        let node = nodes.get_node(0).unwrap();
+        assert!((node.area(&vertices).unwrap() - 0.25).abs() < 1e-12);
+        assert_eq!(node.face_normal(Direction::East, &vertices), Some([1.0, 0.0]));
+        assert_eq!(node.face_normal(Direction::South, &vertices), Some([0.0, -1.0]));
+        assert!((node.face_area(Direction::North, &vertices).unwrap() - 0.5).abs() < 1e-12);
+        assert!(nodes.check_metrics(&vertices).is_ok());
+    }
+
+    #[test]
+    fn test_2d_areas_sum_to_block_area() {
+        let block = BlockBuilder::new_2d(0)
+            .dimensions(Dimensions::Two { nx: 21, ny: 11 })
+            .corners(Point2D::new(0.0, 0.0), Point2D::new(2.0, 0.0), Point2D::new(2.0, 0.6), Point2D::new(0.0, 1.0))
+            .boundary(BoundaryType::North, Boundary2D::function(|x| 1.0 - 0.1 * x * x))
+            .build()
+            .unwrap();
+        let total: f64 = block.nodes.nodes.iter().map(|n| n.area(&block.vertices).unwrap()).sum();
+
+        // the polygon bounded by the discretised north edge, 2 - 0.1 * 8 / 3 in the limit
+        let exact = 2.0 - 0.8 / 3.0;
+        assert!((total - exact).abs() < 1e-3);
+        assert!(block.nodes.check_metrics(&block.vertices).is_ok());
+    }
+
+    #[test]
+    fn test_3d_metrics_on_unit_cube() {
+        let mut vertices = Vertices::new_3d(2, 2, 2);
+        vertices.populate_uniform();
+        let mut nodes = Nodes::new_3d();
+        nodes.populate(&vertices).unwrap();
+
+        let node = nodes.get_node(0).unwrap();
+        assert!((node.volume(&vertices).unwrap() - 1.0).abs() < 1e-12);
+        assert_eq!(node.face_normal(Direction::Up, &vertices), Some([0.0, 0.0, 1.0]));
+        assert_eq!(node.face_normal(Direction::West, &vertices), Some([-1.0, 0.0, 0.0]));
+        assert!((node.face_area(Direction::North, &vertices).unwrap() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_3d_warped_cells_close_and_fill_the_block() {
+        let bottom = [
+            Point3D::new(0.0, 0.0, 0.0),
+            Point3D::new(1.0, 0.0, 0.0),
+            Point3D::new(1.0, 1.0, 0.0),
+            Point3D::new(0.0, 1.0, 0.0),
+        ];
+        let top = [
+            Point3D::new(0.0, 0.0, 1.0),
+            Point3D::new(1.0, 0.0, 1.0),
+            Point3D::new(1.0, 1.0, 1.5),
+            Point3D::new(0.0, 1.0, 1.0),
+        ];
+        let block = BlockBuilder::new_3d(0)
+            .dimensions(Dimensions::Three { nx: 9, ny: 9, nz: 5 })
+            .corners(bottom, top)
+            .boundary(BoundaryType::Top, Boundary3D::function(|x, y| 1.0 + 0.5 * x * y))
+            .build()
+            .unwrap();
+
+        for node in &block.nodes.nodes {
+            assert!(node.closure_error(&block.vertices).unwrap() < 1e-12);
+        }
+        assert!(block.nodes.check_metrics(&block.vertices).is_ok());
+
+        // under z = 1 + xy / 2 over the unit square the volume is 1.125, and
+        // the surface is bilinear across each cell so the faces follow it
+        let total: f64 = block.nodes.nodes.iter().map(|n| n.volume(&block.vertices).unwrap()).sum();
+        assert!((total - 1.125).abs() < 1e-12);
+    }
+}
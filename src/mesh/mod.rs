@@ -1,3 +1,11 @@
 pub mod block;
 pub mod boundary;
-pub mod nodes;
\ No newline at end of file
+pub mod connectivity;
+pub mod distribution;
+pub mod elliptic;
+pub mod hyperbolic;
+pub mod metrics;
+pub mod nodes;
+pub mod quality;
+pub mod tfi;
+pub mod wall_spacing;
\ No newline at end of file
@@ -1,98 +1,302 @@
 #![allow(dead_code)]
 
+use std::marker::PhantomData;
+
 use crate::geometry::prelude::*;
 
-pub struct Node<'a, P: Point> {
-    pub id: usize, 
-    pub north_face: Line<'a, P>,
-    pub south_face: Line<'a, P>,
-    pub east_face:  Line<'a, P>,
-    pub west_face:  Line<'a, P>,
+// neighbour slots follow the order of the direction enum
+fn slot(direction: Direction) -> usize {
+    match direction {
+        Direction::North => 0,
+        Direction::South => 1,
+        Direction::East => 2,
+        Direction::West => 3,
+        Direction::Up => 4,
+        Direction::Down => 5,
+    }
+}
+
+/// A cell of the mesh. It holds the ids of its corner vertices rather than
+/// borrowing them, so it can be stored, moved and sent independently of the
+/// vertices, which are looked up again whenever coordinates are needed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node<P: Point> {
+    pub id: usize,
+    corners: [usize; 8],            // sw, se, ne, nw on the k face, then the k + 1 face in 3D
+    neighbours: [Option<usize>; 6], // none across the block boundary
+    point: PhantomData<P>,
 }
 
-impl<'a, P: Point> Node<'a, P> {
-    pub fn new(id: usize, nf: Line<'a, P>, sf: Line<'a, P>, ef: Line<'a, P>, wf: Line<'a, P>,) -> Self {
-        Node { id, north_face: nf, south_face: sf, east_face: ef, west_face: wf }
+impl<P: Point> Node<P> {
+    /// The id of the cell across the given face, if there is one.
+    pub fn neighbour(&self, direction: Direction) -> Option<usize> {
+        self.neighbours[slot(direction)]
     }
 }
 
-impl<'a, P: Point> Dimensioned for Node<'a, P> {
+impl Dimensioned for Node<Point2D> {
     fn is_2d(&self) -> bool {
-        self.north_face.is_2d()
+        true
     }
 
     fn dimensions(&self) -> usize {
-        self.north_face.dimensions()
+        2
     }
 }
 
-pub struct Nodes<'a, P: Point> {
-    pub nodes: Vec<Node<'a, P>>,
+impl Node<Point2D> {
+    /// Corners are given anticlockwise from the south-west, neighbours in the
+    /// order north, south, east, west.
+    pub fn new_2d(id: usize, corners: [usize; 4], neighbours: [Option<usize>; 4]) -> Self {
+        let [sw, se, ne, nw] = corners;
+        let [n, s, e, w] = neighbours;
+        Node { id, corners: [sw, se, ne, nw, 0, 0, 0, 0], neighbours: [n, s, e, w, None, None], point: PhantomData }
+    }
+
+    /// Corner vertex ids anticlockwise from the south-west.
+    pub fn corners(&self) -> [usize; 4] {
+        [self.corners[0], self.corners[1], self.corners[2], self.corners[3]]
+    }
+
+    /// The two vertex ids of a face, ordered anticlockwise around the cell so
+    /// that the outward normal is on the right of the face.
+    pub fn face(&self, direction: Direction) -> Option<[usize; 2]> {
+        let [sw, se, ne, nw] = self.corners();
+        match direction {
+            Direction::South => Some([sw, se]),
+            Direction::East => Some([se, ne]),
+            Direction::North => Some([ne, nw]),
+            Direction::West => Some([nw, sw]),
+            Direction::Up | Direction::Down => None,
+        }
+    }
+
+    pub fn corner_points(&self, vertices: &Vertices<Point2D>) -> Option<[Point2D; 4]> {
+        let point = |id: usize| vertices.get_vertex(id).map(|v| Point2D::new(v.get_x(), v.get_y()));
+        let [sw, se, ne, nw] = self.corners();
+        Some([point(sw)?, point(se)?, point(ne)?, point(nw)?])
+    }
+
+    pub fn face_midpoint(&self, direction: Direction, vertices: &Vertices<Point2D>) -> Option<Point2D> {
+        let [a, b] = self.face(direction)?;
+        let (a, b) = (vertices.get_vertex(a)?, vertices.get_vertex(b)?);
+        Some(Point2D::new(0.5 * (a.get_x() + b.get_x()), 0.5 * (a.get_y() + b.get_y())))
+    }
+
+    /// The area centroid of the cell, falling back to the mean of the corners
+    /// if the cell has collapsed to no area.
+    pub fn centroid(&self, vertices: &Vertices<Point2D>) -> Option<Point2D> {
+        let corners = self.corner_points(vertices)?;
+        let (mut area, mut cx, mut cy) = (0.0, 0.0, 0.0);
+        for k in 0..4 {
+            let (a, b) = (&corners[k], &corners[(k + 1) % 4]);
+            let cross = a.x() * b.y() - b.x() * a.y();
+            area += 0.5 * cross;
+            cx += (a.x() + b.x()) * cross;
+            cy += (a.y() + b.y()) * cross;
+        }
+        if area.abs() < f64::EPSILON {
+            let mean = |f: fn(&Point2D) -> f64| corners.iter().map(f).sum::<f64>() / 4.0;
+            return Some(Point2D::new(mean(Point2D::x), mean(Point2D::y)));
+        }
+        Some(Point2D::new(cx / (6.0 * area), cy / (6.0 * area)))
+    }
 }
 
-impl<'a, P: Point> Dimensioned for Nodes<'a, P> {
+impl Dimensioned for Node<Point3D> {
     fn is_2d(&self) -> bool {
-        if self.is_empty() {
-            true  // Default to true for empty collections
-        } else {
-            self.nodes.first().unwrap().is_2d()
-        }
+        false
     }
 
     fn dimensions(&self) -> usize {
-        if self.is_empty() {
-            2  // Default to 2D for empty collections
-        } else {
-            self.nodes.first().unwrap().dimensions()
+        3
+    }
+}
+
+impl Node<Point3D> {
+    /// A hexahedral cell. Corners are given as the k face anticlockwise from
+    /// the south-west, then the k + 1 face the same way. Neighbours are in the
+    /// order north, south, east, west, up, down.
+    pub fn new_3d(id: usize, corners: [usize; 8], neighbours: [Option<usize>; 6]) -> Self {
+        Node { id, corners, neighbours, point: PhantomData }
+    }
+
+    pub fn corners(&self) -> [usize; 8] {
+        self.corners
+    }
+
+    /// The four vertex ids of a face, ordered anticlockwise when seen from
+    /// outside the cell so that the right hand rule gives the outward normal.
+    pub fn face(&self, direction: Direction) -> Option<[usize; 4]> {
+        let c = self.corners;
+        Some(match direction {
+            Direction::South => [c[0], c[1], c[5], c[4]],
+            Direction::North => [c[3], c[7], c[6], c[2]],
+            Direction::West => [c[0], c[4], c[7], c[3]],
+            Direction::East => [c[1], c[2], c[6], c[5]],
+            Direction::Down => [c[0], c[3], c[2], c[1]],
+            Direction::Up => [c[4], c[5], c[6], c[7]],
+        })
+    }
+
+    pub fn corner_points(&self, vertices: &Vertices<Point3D>) -> Option<[Point3D; 8]> {
+        let mut points = Vec::with_capacity(8);
+        for id in self.corners {
+            let v = vertices.get_vertex(id)?;
+            points.push(Point3D::new(v.get_x(), v.get_y(), v.get_z()));
         }
+        points.try_into().ok()
+    }
+
+    /// The mean of the four corners of a face.
+    pub fn face_midpoint(&self, direction: Direction, vertices: &Vertices<Point3D>) -> Option<Point3D> {
+        mean_point(&self.face(direction)?, vertices)
+    }
+
+    /// The mean of the eight corners of the cell.
+    pub fn centroid(&self, vertices: &Vertices<Point3D>) -> Option<Point3D> {
+        mean_point(&self.corners, vertices)
     }
 }
 
-impl<'a, P: Point> Nodes<'a, P> {
+fn mean_point(ids: &[usize], vertices: &Vertices<Point3D>) -> Option<Point3D> {
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    for id in ids {
+        let v = vertices.get_vertex(*id)?;
+        x += v.get_x();
+        y += v.get_y();
+        z += v.get_z();
+    }
+    let n = ids.len() as f64;
+    Some(Point3D::new(x / n, y / n, z / n))
+}
+
+/// The cells of a structured block, stored in id order with i fastest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nodes<P: Point> {
+    pub nodes: Vec<Node<P>>,
+}
+
+impl Dimensioned for Nodes<Point2D> {
+    fn is_2d(&self) -> bool {
+        true
+    }
+
+    fn dimensions(&self) -> usize {
+        2
+    }
+}
+
+impl<P: Point> Nodes<P> {
     pub fn is_empty(&self) -> bool {
         self.nodes.is_empty()
     }
 
-    pub fn add_node(&mut self, node: Node<'a, P>) {
-        if self.is_empty() || node.dimensions() == self.dimensions() {
-            self.nodes.push(node);
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Nodes are stored so that a node's id is its index, so they must be
+    /// added in id order.
+    pub fn add_node(&mut self, node: Node<P>) -> Result<(), &'static str> {
+        if node.id != self.nodes.len() {
+            return Err("nodes must be added in id order");
         }
+        self.nodes.push(node);
+        Ok(())
+    }
+
+    pub fn get_node(&self, id: usize) -> Option<&Node<P>> {
+        self.nodes.get(id)
     }
 }
 
-impl<'a> Nodes<'a, Point2D> {
+impl Nodes<Point2D> {
     pub fn new_2d() -> Self {
         Nodes { nodes: Vec::new() }
     }
 
-    pub fn populate(&mut self, vertices: &'a Vertices<Point2D>) -> Result<(), &'static str> {
-        if !vertices.is_2d() {
-            return Err("Cannot populate 2D nodes from non-2D vertices");
+    pub fn populate(&mut self, vertices: &Vertices<Point2D>) -> Result<(), &'static str> {
+        if !self.is_empty() {
+            return Err("nodes have already been populated");
+        }
+        let (nx, ny) = vertices.nx_ny();
+        if nx < 2 || ny < 2 {
+            return Err("nodes require at least two vertices in each direction");
+        }
+        if vertices.len() != nx * ny {
+            return Err("vertices must be fully populated before building nodes");
         }
 
-        let mut node_id: usize = 0;
-        for vertex in vertices.vertices() {
-            // Get adjacent vertices
-            let east_vertex = vertices.get_adjacent_vertex(vertex.get_id(), Direction::East);
-            let north_vertex = vertices.get_adjacent_vertex(vertex.get_id(), Direction::North);
-            
-            // Only create node if we have both east and north vertices
-            if let (Some(east), Some(north)) = (east_vertex, north_vertex) {
-                // Get northeast vertex, required to complete the node
-                let northeast_vertex = vertices
-                    .get_adjacent_vertex(east.get_id(), Direction::North)
-                    .ok_or("Failed to get northeast vertex")?;
-
-                // Construct the bounding lines
-                let south = Line::new_2d(vertex, east);
-                let west = Line::new_2d(vertex, north);
-                let north = Line::new_2d(north, northeast_vertex);
-                let east = Line::new_2d(east, northeast_vertex);
-
-                // Construct and add the node
-                let node = Node::new(node_id, north, south, east, west);
-                self.add_node(node);
-                node_id += 1;
+        let (cx, cy) = (nx - 1, ny - 1);
+        self.nodes.reserve(cx * cy);
+        for j in 0..cy {
+            for i in 0..cx {
+                let id = i + j * cx;
+                let sw = i + j * nx;
+                let neighbours = [
+                    (j + 1 < cy).then(|| id + cx),
+                    (j > 0).then(|| id - cx),
+                    (i + 1 < cx).then(|| id + 1),
+                    (i > 0).then(|| id - 1),
+                ];
+                self.add_node(Node::new_2d(id, [sw, sw + 1, sw + 1 + nx, sw + nx], neighbours))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Dimensioned for Nodes<Point3D> {
+    fn is_2d(&self) -> bool {
+        false
+    }
+
+    fn dimensions(&self) -> usize {
+        3
+    }
+}
+
+impl Nodes<Point3D> {
+    pub fn new_3d() -> Self {
+        Nodes { nodes: Vec::new() }
+    }
+
+    pub fn populate(&mut self, vertices: &Vertices<Point3D>) -> Result<(), &'static str> {
+        if !self.is_empty() {
+            return Err("nodes have already been populated");
+        }
+        let (nx, ny, nz) = vertices.nx_ny_nz();
+        if nx < 2 || ny < 2 || nz < 2 {
+            return Err("nodes require at least two vertices in each direction");
+        }
+        if vertices.len() != nx * ny * nz {
+            return Err("vertices must be fully populated before building nodes");
+        }
+
+        let (cx, cy, cz) = (nx - 1, ny - 1, nz - 1);
+        let (layer, cell_layer) = (nx * ny, cx * cy);
+        self.nodes.reserve(cx * cy * cz);
+        for k in 0..cz {
+            for j in 0..cy {
+                for i in 0..cx {
+                    let id = i + j * cx + k * cell_layer;
+                    let sw = i + j * nx + k * layer;
+                    let bottom = [sw, sw + 1, sw + 1 + nx, sw + nx];
+                    let corners = [
+                        bottom[0], bottom[1], bottom[2], bottom[3],
+                        bottom[0] + layer, bottom[1] + layer, bottom[2] + layer, bottom[3] + layer,
+                    ];
+                    let neighbours = [
+                        (j + 1 < cy).then(|| id + cx),
+                        (j > 0).then(|| id - cx),
+                        (i + 1 < cx).then(|| id + 1),
+                        (i > 0).then(|| id - 1),
+                        (k + 1 < cz).then(|| id + cell_layer),
+                        (k > 0).then(|| id - cell_layer),
+                    ];
+                    self.add_node(Node::new_3d(id, corners, neighbours))?;
+                }
             }
         }
         Ok(())
@@ -112,9 +316,113 @@ mod tests {
         assert!(nodes.populate(&vertices).is_ok());
 
         assert_eq!(nodes.nodes.len(), 4);
-        
+
         for (i, node) in nodes.nodes.iter().enumerate() {
             assert_eq!(node.id, i);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_node_geometry_and_neighbours() {
+        let mut vertices = Vertices::new_2d(4, 3);
+        vertices.populate_uniform();
+        let mut nodes = Nodes::new_2d();
+        nodes.populate(&vertices).unwrap();
+
+        // the middle cell of the bottom row
+        let node = nodes.get_node(1).unwrap();
+        assert_eq!(node.corners(), [1, 2, 6, 5]);
+        assert_eq!(node.face(Direction::East), Some([2, 6]));
+        assert_eq!(node.neighbour(Direction::North), Some(4));
+        assert_eq!(node.neighbour(Direction::South), None);
+        assert_eq!(node.neighbour(Direction::West), Some(0));
+        assert_eq!(node.neighbour(Direction::East), Some(2));
+
+        let centroid = node.centroid(&vertices).unwrap();
+        assert!((centroid.x() - 0.5).abs() < 1e-12 && (centroid.y() - 0.25).abs() < 1e-12);
+        let midpoint = node.face_midpoint(Direction::North, &vertices).unwrap();
+        assert!((midpoint.x() - 0.5).abs() < 1e-12 && (midpoint.y() - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_nodes_outlive_their_vertices() {
+        let nodes = {
+            let mut vertices = Vertices::new_2d(3, 3);
+            vertices.populate_uniform();
+            let mut nodes = Nodes::new_2d();
+            nodes.populate(&vertices).unwrap();
+            nodes
+        };
+        let handle = std::thread::spawn(move || nodes.len());
+        assert_eq!(handle.join().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_populate_3d_nodes() {
+        let mut vertices = Vertices::new_3d(3, 4, 3);
+        vertices.populate_uniform();
+        let mut nodes = Nodes::new_3d();
+        nodes.populate(&vertices).unwrap();
+        assert_eq!(nodes.len(), 2 * 3 * 2);
+
+        // the cell at (1, 1, 0), with the cell above it at k = 1
+        let node = nodes.get_node(3).unwrap();
+        assert_eq!(node.corners()[0], 4);
+        assert_eq!(node.corners()[4], 16);
+        assert_eq!(node.neighbour(Direction::Up), Some(9));
+        assert_eq!(node.neighbour(Direction::Down), None);
+        assert_eq!(node.neighbour(Direction::North), Some(5));
+        assert_eq!(node.neighbour(Direction::East), None);
+
+        // each face's winding points away from the centroid
+        let centroid = node.centroid(&vertices).unwrap();
+        for direction in [Direction::North, Direction::South, Direction::East, Direction::West, Direction::Up, Direction::Down] {
+            let ids = node.face(direction).unwrap();
+            let p: Vec<Point3D> = ids.iter().map(|id| {
+                let v = vertices.get_vertex(*id).unwrap();
+                Point3D::new(v.get_x(), v.get_y(), v.get_z())
+            }).collect();
+            let (a, b) = ([p[2].x() - p[0].x(), p[2].y() - p[0].y(), p[2].z() - p[0].z()], [p[3].x() - p[1].x(), p[3].y() - p[1].y(), p[3].z() - p[1].z()]);
+            let normal = [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]];
+            let mid = node.face_midpoint(direction, &vertices).unwrap();
+            let out = [mid.x() - centroid.x(), mid.y() - centroid.y(), mid.z() - centroid.z()];
+            assert!(normal[0] * out[0] + normal[1] * out[1] + normal[2] * out[2] > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_populate_rejects_incomplete_vertices() {
+        let mut vertices = Vertices::new_2d(3, 3);
+        vertices.add_vertex(Vertex::new_2d(0, 0.0, 0.0)).unwrap();
+        assert!(Nodes::new_2d().populate(&vertices).is_err());
+    }
+
+    #[test]
+    fn test_add_node_rejects_out_of_order_ids() {
+        let mut nodes = Nodes::new_2d();
+        assert_eq!(nodes.add_node(Node::new_2d(1, [0, 1, 4, 3], [None; 4])).unwrap_err(), "nodes must be added in id order");
+        assert!(nodes.add_node(Node::new_2d(0, [0, 1, 4, 3], [None; 4])).is_ok());
+        assert!(nodes.add_node(Node::new_2d(0, [1, 2, 5, 4], [None; 4])).is_err());
+        assert_eq!(nodes.len(), 1);
+    }
+
+    #[test]
+    fn test_populate_scales_linearly() {
+        // the quickest of a few runs, so one slow run on a busy machine doesn't count
+        let time = |n: usize| {
+            let mut vertices = Vertices::new_2d(n, n);
+            vertices.populate_uniform();
+            (0..3).map(|_| {
+                let start = std::time::Instant::now();
+                let mut nodes = Nodes::new_2d();
+                nodes.populate(&vertices).unwrap();
+                assert_eq!(nodes.len(), (n - 1) * (n - 1));
+                start.elapsed().as_secs_f64()
+            }).fold(f64::MAX, f64::min).max(1e-9)
+        };
+        // four times the cells should take about four times as long, where a
+        // search through the vertices for each corner would take sixteen
+        let ratio = time(401) / time(201);
+        assert!(ratio < 10.0, "populating four times the cells took {:.1}x as long", ratio);
+    }
+}
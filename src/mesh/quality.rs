@@ -0,0 +1,345 @@
+#![allow(dead_code)]
+
+use std::fmt;
+
+use crate::geometry::prelude::*;
+use crate::geometry::vector::{cross, dot, norm, sub};
+use crate::mesh::nodes::{Node, Nodes};
+
+const HISTOGRAM_BINS: usize = 10;
+
+const DIRECTIONS_2D: [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
+const DIRECTIONS_3D: [Direction; 6] = [
+    Direction::North, Direction::South, Direction::East, Direction::West, Direction::Up, Direction::Down,
+];
+
+/// Quality measures for a single cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellQuality {
+    pub scaled_jacobian: f64,    // 1 for a square corner, negative once the cell folds
+    pub skewness: f64,           // equiangle, 0 for right angles and 1 when degenerate
+    pub aspect_ratio: f64,       // longest edge over shortest
+    pub non_orthogonality: f64,  // worst angle in degrees between a face normal and the line to the neighbour
+    pub volume_ratio: f64,       // largest ratio of volume, or area in 2D, to any neighbour's
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub edges: Vec<f64>, // one more than the number of bins
+    pub counts: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub histogram: Histogram,
+}
+
+impl Statistics {
+    fn from_values(values: impl Iterator<Item = f64> + Clone) -> Self {
+        let (mut min, mut max, mut sum, mut n) = (f64::MAX, f64::MIN, 0.0, 0);
+        for v in values.clone() {
+            min = min.min(v);
+            max = max.max(v);
+            sum += v;
+            n += 1;
+        }
+        if n == 0 {
+            return Statistics { min: 0.0, max: 0.0, mean: 0.0, histogram: Histogram { edges: Vec::new(), counts: Vec::new() } };
+        }
+
+        let width = (max - min) / HISTOGRAM_BINS as f64;
+        let edges = (0..=HISTOGRAM_BINS).map(|b| min + b as f64 * width).collect();
+        let mut counts = vec![0; HISTOGRAM_BINS];
+        for v in values {
+            let bin = if width > 0.0 { ((v - min) / width) as usize } else { 0 };
+            counts[bin.min(HISTOGRAM_BINS - 1)] += 1;
+        }
+        Statistics { min, max, mean: sum / n as f64, histogram: Histogram { edges, counts } }
+    }
+}
+
+/// Per-cell quality of a block with a summary of each measure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityReport {
+    pub cells: Vec<CellQuality>,
+    pub scaled_jacobian: Statistics,
+    pub skewness: Statistics,
+    pub aspect_ratio: Statistics,
+    pub non_orthogonality: Statistics,
+    pub volume_ratio: Statistics,
+    pub folded: Vec<usize>, // ids of cells with a non-positive scaled jacobian
+}
+
+impl QualityReport {
+    fn new(cells: Vec<CellQuality>) -> Self {
+        let stats = |f: fn(&CellQuality) -> f64| Statistics::from_values(cells.iter().map(f));
+        QualityReport {
+            scaled_jacobian: stats(|c| c.scaled_jacobian),
+            skewness: stats(|c| c.skewness),
+            aspect_ratio: stats(|c| c.aspect_ratio),
+            non_orthogonality: stats(|c| c.non_orthogonality),
+            volume_ratio: stats(|c| c.volume_ratio),
+            folded: cells.iter().enumerate().filter(|(_, c)| c.scaled_jacobian <= 0.0).map(|(id, _)| id).collect(),
+            cells,
+        }
+    }
+
+    pub fn has_folded_cells(&self) -> bool {
+        !self.folded.is_empty()
+    }
+}
+
+impl fmt::Display for QualityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<20}{:>12}{:>12}{:>12}", "metric", "min", "max", "mean")?;
+        for (name, stats) in [
+            ("scaled jacobian", &self.scaled_jacobian),
+            ("skewness", &self.skewness),
+            ("aspect ratio", &self.aspect_ratio),
+            ("non-orthogonality", &self.non_orthogonality),
+            ("volume ratio", &self.volume_ratio),
+        ] {
+            writeln!(f, "{:<20}{:>12.4}{:>12.4}{:>12.4}", name, stats.min, stats.max, stats.mean)?;
+        }
+        write!(f, "{} of {} cells folded", self.folded.len(), self.cells.len())
+    }
+}
+
+impl Nodes<Point2D> {
+    pub fn quality(&self, vertices: &Vertices<Point2D>) -> Result<QualityReport, &'static str> {
+        let missing = "node refers to a vertex that does not exist";
+        let mut corners = Vec::with_capacity(self.len());
+        let mut centroids = Vec::with_capacity(self.len());
+        let mut areas = Vec::with_capacity(self.len());
+        for node in &self.nodes {
+            corners.push(node.corner_points(vertices).ok_or(missing)?.map(|p| [p.x(), p.y(), 0.0]));
+            let centroid = node.centroid(vertices).ok_or(missing)?;
+            centroids.push([centroid.x(), centroid.y(), 0.0]);
+            areas.push(node.area(vertices).ok_or(missing)?);
+        }
+
+        let mut cells = Vec::with_capacity(self.len());
+        for (node, p) in self.nodes.iter().zip(&corners) {
+            let mut jacobian = f64::MAX;
+            let mut angles = Vec::with_capacity(4);
+            for k in 0..4 {
+                let (next, prev) = (sub(p[(k + 1) % 4], p[k]), sub(p[(k + 3) % 4], p[k]));
+                jacobian = jacobian.min(cross(next, prev)[2] / (norm(next) * norm(prev)));
+                angles.push(angle(next, prev));
+            }
+            let edges: Vec<f64> = (0..4).map(|k| norm(sub(p[(k + 1) % 4], p[k]))).collect();
+
+            let mut non_orthogonality: f64 = 0.0;
+            let mut volume_ratio: f64 = 1.0;
+            for direction in DIRECTIONS_2D {
+                let Some(other) = node.neighbour(direction) else { continue };
+                let s = node.face_area_vector(direction, vertices).ok_or(missing)?;
+                let d = sub(centroids[other], centroids[node.id]);
+                non_orthogonality = non_orthogonality.max(angle([s[0], s[1], 0.0], d));
+                volume_ratio = volume_ratio.max(ratio(areas[node.id], areas[other]));
+            }
+
+            cells.push(CellQuality {
+                scaled_jacobian: jacobian,
+                skewness: equiangle_skewness(&angles),
+                aspect_ratio: spread(&edges),
+                non_orthogonality,
+                volume_ratio,
+            });
+        }
+        Ok(QualityReport::new(cells))
+    }
+}
+
+impl Nodes<Point3D> {
+    pub fn quality(&self, vertices: &Vertices<Point3D>) -> Result<QualityReport, &'static str> {
+        let missing = "node refers to a vertex that does not exist";
+        let mut corners = Vec::with_capacity(self.len());
+        let mut centroids = Vec::with_capacity(self.len());
+        let mut volumes = Vec::with_capacity(self.len());
+        for node in &self.nodes {
+            corners.push(node.corner_points(vertices).ok_or(missing)?.map(|p| [p.x(), p.y(), p.z()]));
+            let centroid = node.centroid(vertices).ok_or(missing)?;
+            centroids.push([centroid.x(), centroid.y(), centroid.z()]);
+            volumes.push(node.volume(vertices).ok_or(missing)?);
+        }
+
+        let mut cells = Vec::with_capacity(self.len());
+        for (node, p) in self.nodes.iter().zip(&corners) {
+            let jacobian = hex_scaled_jacobian(p);
+            let angles = hex_face_angles(node, p)?;
+            let edges: Vec<f64> = HEX_EDGES.iter().map(|[a, b]| norm(sub(p[*b], p[*a]))).collect();
+
+            let mut non_orthogonality: f64 = 0.0;
+            let mut volume_ratio: f64 = 1.0;
+            for direction in DIRECTIONS_3D {
+                let Some(other) = node.neighbour(direction) else { continue };
+                let s = node.face_area_vector(direction, vertices).ok_or(missing)?;
+                let d = sub(centroids[other], centroids[node.id]);
+                non_orthogonality = non_orthogonality.max(angle(s, d));
+                volume_ratio = volume_ratio.max(ratio(volumes[node.id], volumes[other]));
+            }
+
+            cells.push(CellQuality {
+                scaled_jacobian: jacobian,
+                skewness: equiangle_skewness(&angles),
+                aspect_ratio: spread(&edges),
+                non_orthogonality,
+                volume_ratio,
+            });
+        }
+        Ok(QualityReport::new(cells))
+    }
+}
+
+// corners of a hex are numbered sw, se, ne, nw at k then k + 1
+const HEX_EDGES: [[usize; 2]; 12] = [
+    [0, 1], [1, 2], [3, 2], [0, 3],
+    [4, 5], [5, 6], [7, 6], [4, 7],
+    [0, 4], [1, 5], [2, 6], [3, 7],
+];
+
+// (i, j, k) offsets of each hex corner
+const HEX_CORNERS: [[usize; 3]; 8] = [
+    [0, 0, 0], [1, 0, 0], [1, 1, 0], [0, 1, 0],
+    [0, 0, 1], [1, 0, 1], [1, 1, 1], [0, 1, 1],
+];
+
+fn hex_corner(ijk: [usize; 3]) -> usize {
+    let base = match (ijk[0], ijk[1]) {
+        (0, 0) => 0,
+        (1, 0) => 1,
+        (1, 1) => 2,
+        _ => 3,
+    };
+    base + 4 * ijk[2]
+}
+
+// the smallest corner determinant of the three unit edges leaving each corner,
+// flipped back to right handed at corners where edges point down an index
+fn hex_scaled_jacobian(p: &[[f64; 3]; 8]) -> f64 {
+    let mut jacobian = f64::MAX;
+    for (c, ijk) in HEX_CORNERS.iter().enumerate() {
+        let mut edges = [[0.0; 3]; 3];
+        for (axis, edge) in edges.iter_mut().enumerate() {
+            let mut other = *ijk;
+            other[axis] = 1 - other[axis];
+            *edge = sub(p[hex_corner(other)], p[c]);
+        }
+        let sign = if (ijk[0] + ijk[1] + ijk[2]) % 2 == 0 { 1.0 } else { -1.0 };
+        let det = dot(cross(edges[0], edges[1]), edges[2]);
+        jacobian = jacobian.min(sign * det / (norm(edges[0]) * norm(edges[1]) * norm(edges[2])));
+    }
+    jacobian
+}
+
+fn hex_face_angles(node: &Node<Point3D>, p: &[[f64; 3]; 8]) -> Result<Vec<f64>, &'static str> {
+    let corners = node.corners();
+    let mut angles = Vec::with_capacity(24);
+    for direction in DIRECTIONS_3D {
+        let face = node.face(direction).ok_or("node has no face in this direction")?;
+        let local = face
+            .iter()
+            .map(|id| corners.iter().position(|c| c == id).ok_or("face vertex is not a corner of its node"))
+            .collect::<Result<Vec<usize>, _>>()?;
+        for k in 0..4 {
+            let here = p[local[k]];
+            angles.push(angle(sub(p[local[(k + 1) % 4]], here), sub(p[local[(k + 3) % 4]], here)));
+        }
+    }
+    Ok(angles)
+}
+
+fn equiangle_skewness(angles: &[f64]) -> f64 {
+    let (min, max) = angles.iter().fold((f64::MAX, f64::MIN), |(lo, hi), a| (lo.min(*a), hi.max(*a)));
+    ((max - 90.0) / 90.0).max((90.0 - min) / 90.0)
+}
+
+fn spread(lengths: &[f64]) -> f64 {
+    let (min, max) = lengths.iter().fold((f64::MAX, f64::MIN), |(lo, hi), l| (lo.min(*l), hi.max(*l)));
+    max / min
+}
+
+fn ratio(a: f64, b: f64) -> f64 {
+    let (a, b) = (a.abs(), b.abs());
+    a.max(b) / a.min(b)
+}
+
+// angle between two vectors in degrees
+fn angle(a: [f64; 3], b: [f64; 3]) -> f64 {
+    (dot(a, b) / (norm(a) * norm(b))).clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nodes_2d(vertices: &Vertices<Point2D>) -> Nodes<Point2D> {
+        let mut nodes = Nodes::new_2d();
+        nodes.populate(vertices).unwrap();
+        nodes
+    }
+
+    #[test]
+    fn test_uniform_grid_is_perfect() {
+        let mut vertices = Vertices::new_2d(5, 5);
+        vertices.populate_uniform();
+        let report = nodes_2d(&vertices).quality(&vertices).unwrap();
+
+        assert!((report.scaled_jacobian.min - 1.0).abs() < 1e-12);
+        assert!(report.skewness.max.abs() < 1e-12);
+        assert!((report.aspect_ratio.max - 1.0).abs() < 1e-12);
+        assert!(report.non_orthogonality.max.abs() < 1e-6);
+        assert!((report.volume_ratio.max - 1.0).abs() < 1e-12);
+        assert!(!report.has_folded_cells());
+        assert_eq!(report.scaled_jacobian.histogram.counts.iter().sum::<usize>(), 16);
+    }
+
+    #[test]
+    fn test_sheared_and_stretched_cells() {
+        // x stretched by 2 and sheared by 45 degrees
+        let mut vertices = Vertices::new_2d(3, 3);
+        for j in 0..3 {
+            for i in 0..3 {
+                vertices.add_vertex(Vertex::new_2d(i + 3 * j, 2.0 * i as f64 + j as f64, j as f64)).unwrap();
+            }
+        }
+        let report = nodes_2d(&vertices).quality(&vertices).unwrap();
+        let cell = &report.cells[0];
+        assert!((cell.scaled_jacobian - 0.5_f64.sqrt()).abs() < 1e-12);
+        assert!((cell.skewness - 0.5).abs() < 1e-12);
+        assert!((cell.aspect_ratio - 2.0 / 2.0_f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_folded_cell_is_flagged() {
+        let mut vertices = Vertices::new_2d(3, 3);
+        vertices.populate_uniform();
+        // drag the centre vertex past the north-east corner, which turns every
+        // cell but the south-west one inside out
+        vertices.set_coords(4, Point2D::new(1.2, 1.2)).unwrap();
+        let report = nodes_2d(&vertices).quality(&vertices).unwrap();
+        assert!(report.has_folded_cells());
+        assert_eq!(report.folded, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_3d_quality() {
+        let mut vertices = Vertices::new_3d(3, 3, 3);
+        vertices.populate_uniform();
+        let mut nodes = Nodes::new_3d();
+        nodes.populate(&vertices).unwrap();
+        let report = nodes.quality(&vertices).unwrap();
+        assert!((report.scaled_jacobian.min - 1.0).abs() < 1e-12);
+        assert!(report.skewness.max.abs() < 1e-12);
+        assert!(report.non_orthogonality.max.abs() < 1e-6);
+
+        vertices.set_coords(13, Point3D::new(1.1, 1.1, 1.1)).unwrap();
+        let report = nodes.quality(&vertices).unwrap();
+        assert_eq!(report.folded, (1..8).collect::<Vec<usize>>());
+        assert!(report.volume_ratio.max > 1.0);
+    }
+}
@@ -0,0 +1,575 @@
+#![allow(dead_code)]
+
+use crate::geometry::prelude::*;
+use crate::geometry::vector::{norm, sub};
+use crate::mesh::distribution::{Distribution, Uniform};
+
+/// How the boundary data is blended into the interior of the patch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Blending {
+    /// Lagrange (bilinear coons) blending between opposite edges.
+    #[default]
+    Linear,
+    /// Cubic hermite blending between the south and north edges, with the grid
+    /// lines leaving both edges along the edge normal.
+    Hermite,
+}
+
+// how far the ends of adjacent edges may be apart and still share a corner
+const CORNER_TOLERANCE: f64 = 1e-8;
+
+impl Vertices<Point2D> {
+    /// Fills the vertices with a transfinite interpolation of the four edges.
+    /// South and north run west to east with nx points, west and east run
+    /// south to north with ny points. The blending parameters follow the arc
+    /// length of the edges, so any clustering along them carries into the
+    /// interior.
+    pub fn populate_tfi(
+        &mut self,
+        south: &[Point2D],
+        north: &[Point2D],
+        west: &[Point2D],
+        east: &[Point2D],
+        blending: Blending,
+    ) -> Result<(), &'static str> {
+        let (nx, ny) = self.nx_ny();
+        if !self.is_empty() {
+            return Err("vertices have already been populated");
+        }
+        if south.len() != nx || north.len() != nx || west.len() != ny || east.len() != ny {
+            return Err("edge lengths do not match the vertex dimensions");
+        }
+        if nx < 2 || ny < 2 {
+            return Err("tfi requires at least two vertices in each direction");
+        }
+
+        let corners = [&south[0], &south[nx - 1], &north[nx - 1], &north[0]];
+        let edge_ends = [&west[0], &east[0], &east[ny - 1], &west[ny - 1]];
+        for (corner, end) in corners.iter().zip(edge_ends.iter()) {
+            if norm(sub(corner.coordinates(), end.coordinates())) > CORNER_TOLERANCE {
+                return Err("edges do not meet at the corners");
+            }
+        }
+
+        let [s_south, s_north, s_west, s_east] = [south, north, west, east]
+            .map(|edge| arc_fractions(&edge.iter().map(Coordinates::coordinates).collect::<Vec<_>>()));
+
+        // outward derivatives for the hermite terms, scaled by the local block height.
+        // at the corners they must follow the side edges, otherwise the mismatch
+        // is spread across the whole of the south and north edges
+        let (t_south, t_north) = match blending {
+            Blending::Linear => (vec![(0.0, 0.0); nx], vec![(0.0, 0.0); nx]),
+            Blending::Hermite => {
+                let mut t_south = edge_normals(south, north);
+                let mut t_north: Vec<(f64, f64)> = edge_normals(north, south)
+                    .into_iter()
+                    .map(|(x, y)| (-x, -y))
+                    .collect();
+                t_south[0] = edge_derivative(west, &s_west, 0);
+                t_south[nx - 1] = edge_derivative(east, &s_east, 0);
+                t_north[0] = edge_derivative(west, &s_west, ny - 2);
+                t_north[nx - 1] = edge_derivative(east, &s_east, ny - 2);
+                (t_south, t_north)
+            }
+        };
+
+        for j in 0..ny {
+            for i in 0..nx {
+                // intersect the lines of constant arc length fraction from each pair of edges
+                let (a, b) = (s_south[i], s_north[i]);
+                let (c, d) = (s_west[j], s_east[j]);
+                let xi = (a + c * (b - a)) / (1.0 - (b - a) * (d - c));
+                let eta = c + xi * (d - c);
+
+                let alpha = [1.0 - xi, xi];
+                let beta = match blending {
+                    Blending::Linear => [1.0 - eta, eta, 0.0, 0.0],
+                    Blending::Hermite => hermite(eta),
+                };
+
+                let blend = |coord: fn(&Point2D) -> f64, deriv: fn((f64, f64)) -> f64| -> f64 {
+                    let eta_projection = beta[0] * coord(&south[i])
+                        + beta[1] * coord(&north[i])
+                        + beta[2] * deriv(t_south[i])
+                        + beta[3] * deriv(t_north[i]);
+                    let xi_projection = alpha[0] * coord(&west[j]) + alpha[1] * coord(&east[j]);
+                    let product = alpha[0] * (beta[0] * coord(&south[0])
+                            + beta[1] * coord(&north[0])
+                            + beta[2] * deriv(t_south[0])
+                            + beta[3] * deriv(t_north[0]))
+                        + alpha[1] * (beta[0] * coord(&south[nx - 1])
+                            + beta[1] * coord(&north[nx - 1])
+                            + beta[2] * deriv(t_south[nx - 1])
+                            + beta[3] * deriv(t_north[nx - 1]));
+                    xi_projection + eta_projection - product
+                };
+                let x = blend(Point2D::x, |t| t.0);
+                let y = blend(Point2D::y, |t| t.1);
+
+                self.add_vertex(Vertex::new_2d(i + j * nx, x, y))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The six bounding faces of a 3D block. Each face is stored with its lower
+/// index running fastest: west/east are ny x nz (j + k * ny), south/north are
+/// nx x nz (i + k * nx) and bottom/top are nx x ny (i + j * nx).
+pub struct BlockFaces<'a> {
+    pub west: &'a [Point3D],
+    pub east: &'a [Point3D],
+    pub south: &'a [Point3D],
+    pub north: &'a [Point3D],
+    pub bottom: &'a [Point3D],
+    pub top: &'a [Point3D],
+}
+
+/// The twelve edges of a 3D block, grouped by the direction they run in.
+/// The i edges sit at (j, k) = (0, 0), (ny - 1, 0), (0, nz - 1), (ny - 1, nz - 1),
+/// the j edges at the same (i, k) positions and the k edges at those (i, j).
+pub struct BlockEdges<'a> {
+    pub i_edges: [Edge3D<'a>; 4],
+    pub j_edges: [Edge3D<'a>; 4],
+    pub k_edges: [Edge3D<'a>; 4],
+}
+
+/// One edge of a 3D block, either sampled at one point per vertex or a curve
+/// to be sampled uniformly in arc length.
+pub enum Edge3D<'a> {
+    Points(&'a [Point3D]),
+    Curve(&'a dyn Curve<Point3D>),
+}
+
+impl Edge3D<'_> {
+    fn sample(&self, n: usize) -> Result<Vec<Point3D>, &'static str> {
+        match self {
+            Edge3D::Points(points) if points.len() != n => Err("edge lengths do not match the vertex dimensions"),
+            Edge3D::Points(points) => Ok(points.to_vec()),
+            Edge3D::Curve(curve) => curve.discretise(&Uniform.sample(n)),
+        }
+    }
+}
+
+impl Vertices<Point3D> {
+    /// Fills the vertices with a transfinite interpolation of the six faces.
+    /// Faces must agree along the edges they share. The blending parameters
+    /// follow the arc length of the twelve block edges.
+    pub fn populate_tfi(&mut self, faces: &BlockFaces) -> Result<(), &'static str> {
+        let (nx, ny, nz) = self.nx_ny_nz();
+        if !self.is_empty() {
+            return Err("vertices have already been populated");
+        }
+        if nx < 2 || ny < 2 || nz < 2 {
+            return Err("tfi requires at least two vertices in each direction");
+        }
+        if faces.west.len() != ny * nz || faces.east.len() != ny * nz
+            || faces.south.len() != nx * nz || faces.north.len() != nx * nz
+            || faces.bottom.len() != nx * ny || faces.top.len() != nx * ny
+        {
+            return Err("face sizes do not match the vertex dimensions");
+        }
+
+        // pull each edge out of both faces that contain it and check they agree
+        let sides = |a: usize| if a == 0 { 0 } else { 1 };
+        let (we, sn, bt) = ([faces.west, faces.east], [faces.south, faces.north], [faces.bottom, faces.top]);
+        let mut i_edges = Vec::new();
+        let mut j_edges = Vec::new();
+        let mut k_edges = Vec::new();
+        for (b, c) in [(0, 0), (ny - 1, 0), (0, nz - 1), (ny - 1, nz - 1)] {
+            let from_sn: Vec<[f64; 3]> = (0..nx).map(|i| sn[sides(b)][i + c * nx].coordinates()).collect();
+            let from_bt: Vec<[f64; 3]> = (0..nx).map(|i| bt[sides(c)][i + b * nx].coordinates()).collect();
+            check_edge(&from_sn, &from_bt)?;
+            i_edges.push(from_sn);
+        }
+        for (a, c) in [(0, 0), (nx - 1, 0), (0, nz - 1), (nx - 1, nz - 1)] {
+            let from_we: Vec<[f64; 3]> = (0..ny).map(|j| we[sides(a)][j + c * ny].coordinates()).collect();
+            let from_bt: Vec<[f64; 3]> = (0..ny).map(|j| bt[sides(c)][a + j * nx].coordinates()).collect();
+            check_edge(&from_we, &from_bt)?;
+            j_edges.push(from_we);
+        }
+        for (a, b) in [(0, 0), (nx - 1, 0), (0, ny - 1), (nx - 1, ny - 1)] {
+            let from_we: Vec<[f64; 3]> = (0..nz).map(|k| we[sides(a)][b + k * ny].coordinates()).collect();
+            let from_sn: Vec<[f64; 3]> = (0..nz).map(|k| sn[sides(b)][a + k * nx].coordinates()).collect();
+            check_edge(&from_we, &from_sn)?;
+            k_edges.push(from_we);
+        }
+
+        let s_i: Vec<Vec<f64>> = i_edges.iter().map(|e| arc_fractions(e)).collect();
+        let s_j: Vec<Vec<f64>> = j_edges.iter().map(|e| arc_fractions(e)).collect();
+        let s_k: Vec<Vec<f64>> = k_edges.iter().map(|e| arc_fractions(e)).collect();
+
+        for k in 0..nz {
+            let zeta_u = k as f64 / (nz - 1) as f64;
+            for j in 0..ny {
+                let eta_u = j as f64 / (ny - 1) as f64;
+                for i in 0..nx {
+                    let xi_u = i as f64 / (nx - 1) as f64;
+
+                    // blend the arc length fractions of the four parallel edges
+                    let xi = bilinear_weights(eta_u, zeta_u).iter().zip(&s_i).map(|(w, s)| w * s[i]).sum::<f64>();
+                    let eta = bilinear_weights(xi_u, zeta_u).iter().zip(&s_j).map(|(w, s)| w * s[j]).sum::<f64>();
+                    let zeta = bilinear_weights(xi_u, eta_u).iter().zip(&s_k).map(|(w, s)| w * s[k]).sum::<f64>();
+
+                    let alpha = [1.0 - xi, xi];
+                    let beta = [1.0 - eta, eta];
+                    let gamma = [1.0 - zeta, zeta];
+
+                    let mut p = [0.0; 3];
+                    for d in 0..3 {
+                        // face projectors
+                        let faces_term = alpha[0] * faces.west[j + k * ny].coordinates()[d]
+                            + alpha[1] * faces.east[j + k * ny].coordinates()[d]
+                            + beta[0] * faces.south[i + k * nx].coordinates()[d]
+                            + beta[1] * faces.north[i + k * nx].coordinates()[d]
+                            + gamma[0] * faces.bottom[i + j * nx].coordinates()[d]
+                            + gamma[1] * faces.top[i + j * nx].coordinates()[d];
+
+                        // edge and corner products
+                        let mut edges_term = 0.0;
+                        let mut corners_term = 0.0;
+                        for (e, (b, c)) in [(0, 0), (1, 0), (0, 1), (1, 1)].into_iter().enumerate() {
+                            edges_term += beta[b] * gamma[c] * i_edges[e][i][d];
+                            edges_term += alpha[b] * gamma[c] * j_edges[e][j][d];
+                            edges_term += alpha[b] * beta[c] * k_edges[e][k][d];
+                            for (a, weight) in alpha.iter().enumerate() {
+                                corners_term += weight * beta[b] * gamma[c] * i_edges[e][a * (nx - 1)][d];
+                            }
+                        }
+
+                        p[d] = faces_term - edges_term + corners_term;
+                    }
+
+                    let id = i + j * nx + k * nx * ny;
+                    self.add_vertex(Vertex::new_3d(id, p[0], p[1], p[2]))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fills the vertices from the twelve block edges, first spanning each face
+    /// with a linear coons patch and then interpolating the volume from them.
+    pub fn populate_tfi_from_edges(&mut self, edges: &BlockEdges) -> Result<(), &'static str> {
+        let (nx, ny, nz) = self.nx_ny_nz();
+        let sample = |group: &[Edge3D; 4], n: usize| -> Result<Vec<Vec<Point3D>>, &'static str> {
+            group.iter().map(|edge| edge.sample(n)).collect()
+        };
+        let (i_e, j_e, k_e) = (sample(&edges.i_edges, nx)?, sample(&edges.j_edges, ny)?, sample(&edges.k_edges, nz)?);
+
+        // each face is spanned by its two pairs of opposite edges
+        let west = coons_patch_3d(&j_e[0], &j_e[2], &k_e[0], &k_e[2])?;
+        let east = coons_patch_3d(&j_e[1], &j_e[3], &k_e[1], &k_e[3])?;
+        let south = coons_patch_3d(&i_e[0], &i_e[2], &k_e[0], &k_e[1])?;
+        let north = coons_patch_3d(&i_e[1], &i_e[3], &k_e[2], &k_e[3])?;
+        let bottom = coons_patch_3d(&i_e[0], &i_e[1], &j_e[0], &j_e[1])?;
+        let top = coons_patch_3d(&i_e[2], &i_e[3], &j_e[2], &j_e[3])?;
+
+        self.populate_tfi(&BlockFaces {
+            west: &west,
+            east: &east,
+            south: &south,
+            north: &north,
+            bottom: &bottom,
+            top: &top,
+        })
+    }
+}
+
+// linear coons patch in space, stored u-fastest. south and north run in u,
+// west and east run in v
+pub(crate) fn coons_patch_3d(south: &[Point3D], north: &[Point3D], west: &[Point3D], east: &[Point3D]) -> Result<Vec<Point3D>, &'static str> {
+    let (nu, nv) = (south.len(), west.len());
+    let [s, n, w, e] = [south, north, west, east].map(|edge| edge.iter().map(Coordinates::coordinates).collect::<Vec<_>>());
+    for (corner, end) in [(&s[0], &w[0]), (&s[nu - 1], &e[0]), (&n[nu - 1], &e[nv - 1]), (&n[0], &w[nv - 1])] {
+        if norm(sub(*corner, *end)) > CORNER_TOLERANCE {
+            return Err("edges do not meet at the corners");
+        }
+    }
+
+    let (s_south, s_north) = (arc_fractions(&s), arc_fractions(&n));
+    let (s_west, s_east) = (arc_fractions(&w), arc_fractions(&e));
+
+    let mut points = Vec::with_capacity(nu * nv);
+    for j in 0..nv {
+        for i in 0..nu {
+            let (a, b) = (s_south[i], s_north[i]);
+            let (c, d) = (s_west[j], s_east[j]);
+            let u = (a + c * (b - a)) / (1.0 - (b - a) * (d - c));
+            let v = c + u * (d - c);
+
+            let p: [f64; 3] = std::array::from_fn(|k| {
+                (1.0 - v) * s[i][k] + v * n[i][k] + (1.0 - u) * w[j][k] + u * e[j][k]
+                    - (1.0 - u) * (1.0 - v) * s[0][k]
+                    - u * (1.0 - v) * s[nu - 1][k]
+                    - u * v * n[nu - 1][k]
+                    - (1.0 - u) * v * n[0][k]
+            });
+            points.push(Point3D::new(p[0], p[1], p[2]));
+        }
+    }
+    Ok(points)
+}
+
+fn check_edge(a: &[[f64; 3]], b: &[[f64; 3]]) -> Result<(), &'static str> {
+    if a.iter().zip(b).any(|(p, q)| norm(sub(*p, *q)) > CORNER_TOLERANCE) {
+        return Err("faces do not agree along a shared edge");
+    }
+    Ok(())
+}
+
+// weights for the edges at (0, 0), (1, 0), (0, 1), (1, 1)
+fn bilinear_weights(u: f64, v: f64) -> [f64; 4] {
+    [(1.0 - u) * (1.0 - v), u * (1.0 - v), (1.0 - u) * v, u * v]
+}
+
+// cubic hermite basis: value at 0, value at 1, slope at 0, slope at 1
+fn hermite(s: f64) -> [f64; 4] {
+    let (s2, s3) = (s * s, s * s * s);
+    [2.0 * s3 - 3.0 * s2 + 1.0, -2.0 * s3 + 3.0 * s2, s3 - 2.0 * s2 + s, s3 - s2]
+}
+
+// cumulative arc length along the edge, normalised to [0, 1]
+pub(crate) fn arc_fractions(edge: &[[f64; 3]]) -> Vec<f64> {
+    let mut s = vec![0.0];
+    for pair in edge.windows(2) {
+        s.push(s.last().unwrap() + norm(sub(pair[1], pair[0])));
+    }
+    let total = *s.last().unwrap();
+    if total > 0.0 {
+        s.iter_mut().for_each(|v| *v /= total);
+    }
+    s
+}
+
+// derivative of the edge with respect to its arc length fraction over segment k
+fn edge_derivative(edge: &[Point2D], s: &[f64], k: usize) -> (f64, f64) {
+    let ds = s[k + 1] - s[k];
+    ((edge[k + 1].x() - edge[k].x()) / ds, (edge[k + 1].y() - edge[k].y()) / ds)
+}
+
+// unit normals to `edge` pointing towards `opposite`, scaled by the distance between them
+fn edge_normals(edge: &[Point2D], opposite: &[Point2D]) -> Vec<(f64, f64)> {
+    let n = edge.len();
+    (0..n).map(|i| {
+        let (prev, next) = (&edge[i.saturating_sub(1)], &edge[(i + 1).min(n - 1)]);
+        let (tx, ty) = (next.x() - prev.x(), next.y() - prev.y());
+        let length = tx.hypot(ty);
+        let (mut nx, mut ny) = (-ty / length, tx / length);
+
+        let (dx, dy) = (opposite[i].x() - edge[i].x(), opposite[i].y() - edge[i].y());
+        if nx * dx + ny * dy < 0.0 {
+            nx = -nx;
+            ny = -ny;
+        }
+        let height = dx.hypot(dy);
+        (nx * height, ny * height)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn straight(start: (f64, f64), end: (f64, f64), n: usize) -> Vec<Point2D> {
+        (0..n).map(|k| {
+            let t = k as f64 / (n - 1) as f64;
+            Point2D::new(start.0 + t * (end.0 - start.0), start.1 + t * (end.1 - start.1))
+        }).collect()
+    }
+
+    #[test]
+    fn test_linear_tfi_reproduces_parallelogram() {
+        let (nx, ny) = (5, 4);
+        let south = straight((0.0, 0.0), (2.0, 0.0), nx);
+        let north = straight((1.0, 3.0), (3.0, 3.0), nx);
+        let west = straight((0.0, 0.0), (1.0, 3.0), ny);
+        let east = straight((2.0, 0.0), (3.0, 3.0), ny);
+
+        let mut vertices = Vertices::new_2d(nx, ny);
+        vertices.populate_tfi(&south, &north, &west, &east, Blending::Linear).unwrap();
+
+        for vertex in vertices.vertices() {
+            let (i, j) = vertices.vertex_id_to_ij(vertex.get_id()).unwrap();
+            let (xi, eta) = (i as f64 / 4.0, j as f64 / 3.0);
+            assert!((vertex.get_x() - (2.0 * xi + eta)).abs() < 1e-12);
+            assert!((vertex.get_y() - 3.0 * eta).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_tfi_fills_annulus_sector() {
+        // quarter annulus, the walls are circular arcs rather than graphs of x
+        let (nx, ny) = (9, 5);
+        let arc = |r: f64| -> Vec<Point2D> {
+            (0..nx).map(|k| {
+                let theta = std::f64::consts::FRAC_PI_2 * (1.0 - k as f64 / (nx - 1) as f64);
+                Point2D::new(r * theta.cos(), r * theta.sin())
+            }).collect()
+        };
+        let (south, north) = (arc(1.0), arc(2.0));
+        let west = straight((0.0, 1.0), (0.0, 2.0), ny);
+        let east = straight((1.0, 0.0), (2.0, 0.0), ny);
+
+        for blending in [Blending::Linear, Blending::Hermite] {
+            let mut vertices = Vertices::new_2d(nx, ny);
+            vertices.populate_tfi(&south, &north, &west, &east, blending).unwrap();
+            assert_eq!(vertices.len(), nx * ny);
+
+            for vertex in vertices.vertices() {
+                let r = vertex.get_x().hypot(vertex.get_y());
+                assert!((1.0 - 1e-12..=2.0 + 1e-12).contains(&r));
+            }
+        }
+    }
+
+    #[test]
+    fn test_hermite_tfi_is_orthogonal_at_south_edge() {
+        // skewed side walls, linear blending follows them off the south edge
+        let (nx, ny) = (11, 41);
+        let south = straight((0.0, 0.0), (1.0, 0.0), nx);
+        let north = straight((0.5, 1.0), (1.5, 1.0), nx);
+        let west = straight((0.0, 0.0), (0.5, 1.0), ny);
+        let east = straight((1.0, 0.0), (1.5, 1.0), ny);
+
+        let skew = |blending: Blending| {
+            let mut vertices = Vertices::new_2d(nx, ny);
+            vertices.populate_tfi(&south, &north, &west, &east, blending).unwrap();
+            let wall = vertices.get_vertex(5).unwrap();
+            let above = vertices.get_vertex(5 + nx).unwrap();
+            (above.get_x() - wall.get_x()).abs() / (above.get_y() - wall.get_y())
+        };
+        assert!((skew(Blending::Linear) - 0.5).abs() < 1e-12);
+        assert!(skew(Blending::Hermite) < 0.05);
+    }
+
+    #[test]
+    fn test_tfi_rejects_mismatched_edges() {
+        let mut vertices = Vertices::new_2d(3, 3);
+        let south = straight((0.0, 0.0), (1.0, 0.0), 3);
+        let north = straight((0.0, 1.0), (1.0, 1.0), 3);
+        let west = straight((0.0, 0.0), (0.0, 1.0), 3);
+        let east = straight((1.0, 0.5), (1.0, 1.0), 3);
+        assert!(vertices.populate_tfi(&south, &north, &west, &east, Blending::Linear).is_err());
+    }
+
+    // samples the map over the boundary of the unit cube in computational space
+    fn faces_of(map: impl Fn(f64, f64, f64) -> Point3D, nx: usize, ny: usize, nz: usize) -> [Vec<Point3D>; 6] {
+        let t = |k: usize, n: usize| k as f64 / (n - 1) as f64;
+        let face = |nu: usize, nv: usize, f: &dyn Fn(f64, f64) -> Point3D| -> Vec<Point3D> {
+            (0..nv).flat_map(|v| (0..nu).map(move |u| (u, v))).map(|(u, v)| f(t(u, nu), t(v, nv))).collect()
+        };
+        [
+            face(ny, nz, &|v, w| map(0.0, v, w)),
+            face(ny, nz, &|v, w| map(1.0, v, w)),
+            face(nx, nz, &|u, w| map(u, 0.0, w)),
+            face(nx, nz, &|u, w| map(u, 1.0, w)),
+            face(nx, ny, &|u, v| map(u, v, 0.0)),
+            face(nx, ny, &|u, v| map(u, v, 1.0)),
+        ]
+    }
+
+    #[test]
+    fn test_3d_tfi_from_edges_reproduces_skewed_hexahedron() {
+        let (nx, ny, nz) = (4, 3, 5);
+        let map = |u: f64, v: f64, w: f64| Point3D::new(2.0 * u + 0.5 * v, v + 0.25 * w, 3.0 * w + 0.5 * u);
+        let t = |k: usize, n: usize| k as f64 / (n - 1) as f64;
+        let i_edge = |v: f64, w: f64| (0..nx).map(|k| map(t(k, nx), v, w)).collect::<Vec<_>>();
+        let j_edge = |u: f64, w: f64| (0..ny).map(|k| map(u, t(k, ny), w)).collect::<Vec<_>>();
+        let k_edge = |u: f64, v: f64| (0..nz).map(|k| map(u, v, t(k, nz))).collect::<Vec<_>>();
+        let ends = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)];
+        let i_edges = ends.map(|(a, b)| i_edge(a, b));
+        let j_edges = ends.map(|(a, b)| j_edge(a, b));
+        let k_edges = ends.map(|(a, b)| k_edge(a, b));
+
+        let mut vertices = Vertices::new_3d(nx, ny, nz);
+        vertices.populate_tfi_from_edges(&BlockEdges {
+            i_edges: [0, 1, 2, 3].map(|e| Edge3D::Points(&i_edges[e])),
+            j_edges: [0, 1, 2, 3].map(|e| Edge3D::Points(&j_edges[e])),
+            k_edges: [0, 1, 2, 3].map(|e| Edge3D::Points(&k_edges[e])),
+        }).unwrap();
+        assert_eq!(vertices.len(), nx * ny * nz);
+
+        for vertex in vertices.vertices() {
+            let (i, j, k) = vertices.vertex_id_to_ijk(vertex.get_id()).unwrap();
+            let expected = map(t(i, nx), t(j, ny), t(k, nz));
+            assert!((vertex.get_x() - expected.x()).abs() < 1e-12);
+            assert!((vertex.get_y() - expected.y()).abs() < 1e-12);
+            assert!((vertex.get_z() - expected.z()).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_3d_tfi_from_curved_edges() {
+        // quarter annulus of unit height with arcs for its i edges
+        let (nx, ny, nz) = (9, 5, 3);
+        let at = |theta: f64, r: f64, z: f64| Point3D::new(r * theta.cos(), r * theta.sin(), z);
+        let quarter = std::f64::consts::FRAC_PI_2;
+        let arcs = [(1.0, 0.0), (2.0, 0.0), (1.0, 1.0), (2.0, 1.0)]
+            .map(|(r, z)| CircularArc::through(&at(0.0, r, z), &at(0.5 * quarter, r, z), &at(quarter, r, z)).unwrap());
+        let radials = [(0.0, 0.0), (quarter, 0.0), (0.0, 1.0), (quarter, 1.0)]
+            .map(|(theta, z)| Segment::new(&at(theta, 1.0, z), &at(theta, 2.0, z)));
+        let verticals = [(0.0, 1.0), (quarter, 1.0), (0.0, 2.0), (quarter, 2.0)]
+            .map(|(theta, r)| Segment::new(&at(theta, r, 0.0), &at(theta, r, 1.0)));
+
+        let mut vertices = Vertices::new_3d(nx, ny, nz);
+        vertices.populate_tfi_from_edges(&BlockEdges {
+            i_edges: [0, 1, 2, 3].map(|e| Edge3D::Curve(&arcs[e])),
+            j_edges: [0, 1, 2, 3].map(|e| Edge3D::Curve(&radials[e])),
+            k_edges: [0, 1, 2, 3].map(|e| Edge3D::Curve(&verticals[e])),
+        }).unwrap();
+
+        for vertex in vertices.vertices() {
+            let (i, j, k) = vertices.vertex_id_to_ijk(vertex.get_id()).unwrap();
+            let expected = at(quarter * i as f64 / (nx - 1) as f64, 1.0 + j as f64 / (ny - 1) as f64, k as f64 / (nz - 1) as f64);
+            assert!((vertex.get_x() - expected.x()).abs() < 1e-9);
+            assert!((vertex.get_y() - expected.y()).abs() < 1e-9);
+            assert!((vertex.get_z() - expected.z()).abs() < 1e-9);
+        }
+
+        // a sampled edge must still have one point per vertex
+        let short = [at(0.0, 1.0, 0.0), at(0.0, 2.0, 0.0)];
+        let result = vertices.populate_tfi_from_edges(&BlockEdges {
+            i_edges: [0, 1, 2, 3].map(|e| Edge3D::Curve(&arcs[e])),
+            j_edges: [0, 1, 2, 3].map(|_| Edge3D::Points(&short)),
+            k_edges: [0, 1, 2, 3].map(|e| Edge3D::Curve(&verticals[e])),
+        });
+        assert_eq!(result.unwrap_err(), "edge lengths do not match the vertex dimensions");
+    }
+
+    #[test]
+    fn test_3d_tfi_fills_swept_annulus_sector() {
+        // quarter annulus swept in z under a sloping top face
+        let (nx, ny, nz) = (9, 5, 4);
+        let map = |u: f64, v: f64, w: f64| {
+            let (theta, r) = (std::f64::consts::FRAC_PI_2 * u, 1.0 + v);
+            let x = r * theta.cos();
+            Point3D::new(x, r * theta.sin(), w * (1.0 + 0.2 * x))
+        };
+        let [west, east, south, north, bottom, top] = faces_of(map, nx, ny, nz);
+
+        let mut vertices = Vertices::new_3d(nx, ny, nz);
+        vertices.populate_tfi(&BlockFaces {
+            west: &west, east: &east, south: &south, north: &north, bottom: &bottom, top: &top,
+        }).unwrap();
+
+        for vertex in vertices.vertices() {
+            let r = vertex.get_x().hypot(vertex.get_y());
+            assert!((1.0 - 1e-12..=2.0 + 1e-12).contains(&r));
+            assert!(vertex.get_z() >= -1e-12 && vertex.get_z() <= 1.0 + 0.2 * vertex.get_x() + 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_3d_tfi_rejects_inconsistent_faces() {
+        let (nx, ny, nz) = (3, 3, 3);
+        let [west, east, south, north, bottom, _] = faces_of(Point3D::new, nx, ny, nz);
+        let top = vec![Point3D::new(0.0, 0.0, 2.0); nx * ny];
+
+        let mut vertices = Vertices::new_3d(nx, ny, nz);
+        let result = vertices.populate_tfi(&BlockFaces {
+            west: &west, east: &east, south: &south, north: &north, bottom: &bottom, top: &top,
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,103 @@
+#![allow(dead_code)]
+
+/// Flat plate skin friction correlations, giving the local coefficient at a
+/// distance along the plate from its Reynolds number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkinFriction {
+    Blasius,     // laminar
+    Prandtl,     // turbulent, one-seventh power law, Re below 1e7
+    Schlichting, // turbulent, Re below 1e9
+    White,       // turbulent, fitted up to Re of 1e10
+}
+
+impl SkinFriction {
+    pub fn coefficient(&self, reynolds: f64) -> f64 {
+        match self {
+            SkinFriction::Blasius => 0.664 / reynolds.sqrt(),
+            SkinFriction::Prandtl => 0.0592 * reynolds.powf(-0.2),
+            SkinFriction::Schlichting => (2.0 * reynolds.log10() - 0.65).powf(-2.3),
+            SkinFriction::White => 0.455 / (0.06 * reynolds).ln().powi(2),
+        }
+    }
+}
+
+/// Freestream conditions in consistent units, SI unless the mesh says otherwise.
+#[derive(Debug, Clone)]
+pub struct Freestream {
+    pub velocity: f64,
+    pub density: f64,
+    pub viscosity: f64, // dynamic
+}
+
+impl Freestream {
+    pub fn new(velocity: f64, density: f64, viscosity: f64) -> Self {
+        Freestream { velocity, density, viscosity }
+    }
+
+    pub fn reynolds(&self, length: f64) -> f64 {
+        self.density * self.velocity * length / self.viscosity
+    }
+
+    /// Estimates the first cell height that puts the wall cell at the target y+,
+    /// from the skin friction a flat plate of the reference length would have
+    /// at its end. The result is meant to go straight into a distribution's
+    /// `from_spacing`.
+    pub fn wall_spacing(&self, reference_length: f64, y_plus: f64, correlation: SkinFriction) -> Result<f64, &'static str> {
+        if self.velocity <= 0.0 || self.density <= 0.0 || self.viscosity <= 0.0 {
+            return Err("freestream velocity, density and viscosity must be positive");
+        }
+        if reference_length <= 0.0 {
+            return Err("reference length must be positive");
+        }
+        if y_plus <= 0.0 {
+            return Err("target y+ must be positive");
+        }
+
+        let skin_friction = correlation.coefficient(self.reynolds(reference_length));
+        if !skin_friction.is_finite() || skin_friction <= 0.0 {
+            return Err("reynolds number is outside the range of the skin friction correlation");
+        }
+        let wall_shear = 0.5 * skin_friction * self.density * self.velocity.powi(2);
+        let friction_velocity = (wall_shear / self.density).sqrt();
+        Ok(y_plus * self.viscosity / (self.density * friction_velocity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::distribution::{Distribution, TopClusteredTangent};
+
+    fn air() -> Freestream {
+        Freestream::new(10.0, 1.2, 1.8e-5)
+    }
+
+    #[test]
+    fn test_wall_spacing_for_flat_plate() {
+        // Re = 6.7e5, Cf = 4.03e-3, u_tau = 0.449 m/s
+        let spacing = air().wall_spacing(1.0, 1.0, SkinFriction::Schlichting).unwrap();
+        assert!((spacing / 3.343e-5 - 1.0).abs() < 1e-3);
+
+        // y scales linearly with y+, and the thinner laminar shear wants coarser cells
+        let thirty = air().wall_spacing(1.0, 30.0, SkinFriction::Schlichting).unwrap();
+        assert!((thirty / spacing - 30.0).abs() < 1e-9);
+        assert!(air().wall_spacing(1.0, 1.0, SkinFriction::Blasius).unwrap() > spacing);
+    }
+
+    #[test]
+    fn test_wall_spacing_feeds_distribution() {
+        let spacing = air().wall_spacing(1.0, 1.0, SkinFriction::White).unwrap();
+        let distribution = TopClusteredTangent::from_spacing(0.1, 81, spacing).unwrap();
+        let params = distribution.sample(81);
+        assert!((0.1 * (1.0 - params[79]) / spacing - 1.0).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_wall_spacing_rejects_bad_conditions() {
+        assert!(Freestream::new(0.0, 1.2, 1.8e-5).wall_spacing(1.0, 1.0, SkinFriction::Prandtl).is_err());
+        assert!(air().wall_spacing(-1.0, 1.0, SkinFriction::Prandtl).is_err());
+        assert!(air().wall_spacing(1.0, 0.0, SkinFriction::Prandtl).is_err());
+        // schlichting's log fit goes negative for creeping flow
+        assert!(Freestream::new(1.0, 1.0, 1.0).wall_spacing(1.0, 1.0, SkinFriction::Schlichting).is_err());
+    }
+}
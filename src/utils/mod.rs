@@ -1 +1,2 @@
-pub mod plotting;
\ No newline at end of file
+pub mod plotting;
+pub mod roots;
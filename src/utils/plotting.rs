@@ -118,25 +118,24 @@ pub fn plot_vertices_3d(vertices: &Vertices<Point3D>, filename: &str) -> Result<
     Ok(())
 }
 
-pub fn plot_nodes_2d(nodes: &Nodes<'_, Point2D>, filename: &str, draw_numbers: bool) -> Result<(), Box<dyn std::error::Error>> {
+pub fn plot_nodes_2d(nodes: &Nodes<Point2D>, vertices: &Vertices<Point2D>, filename: &str, draw_numbers: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // look up the corners of every node once, they are needed for the bounds and the drawing
+    let cells: Vec<[Point2D; 4]> = nodes.nodes
+        .iter()
+        .map(|node| node.corner_points(vertices).ok_or("node refers to a vertex that does not exist"))
+        .collect::<Result<_, _>>()?;
+
     // find bounds first
     let mut min_x = f64::MAX;
     let mut max_x = f64::MIN;
     let mut min_y = f64::MAX;
     let mut max_y = f64::MIN;
 
-    for node in &nodes.nodes {
-        for vertex in [
-            node.north_face.start, node.north_face.end,
-            node.south_face.start, node.south_face.end,
-            node.east_face.start, node.east_face.end,
-            node.west_face.start, node.west_face.end,
-        ] {
-            min_x = min_x.min(vertex.get_x());
-            max_x = max_x.max(vertex.get_x());
-            min_y = min_y.min(vertex.get_y());
-            max_y = max_y.max(vertex.get_y());
-        }
+    for point in cells.iter().flatten() {
+        min_x = min_x.min(point.x());
+        max_x = max_x.max(point.x());
+        min_y = min_y.min(point.y());
+        max_y = max_y.max(point.y());
     }
 
     // calculate padding
@@ -174,32 +173,24 @@ pub fn plot_nodes_2d(nodes: &Nodes<'_, Point2D>, filename: &str, draw_numbers: b
         .axis_style(ShapeStyle::from(&WHITE.mix(0.0))) // Hide axes
         .draw()?;
 
-    for node in &nodes.nodes {
-        // get a slice of the four lines comprising the current node
-        let lines: [(&Vertex<Point2D>, &Vertex<Point2D>); 4] = [
-            (node.north_face.start, node.north_face.end),
-            (node.south_face.start, node.south_face.end),
-            (node.east_face.start, node.east_face.end),
-            (node.west_face.start, node.west_face.end),
-        ];
-
-        for (start, end) in lines {
-            // iterate over the slice of lines and draw them
+    for (node, corners) in nodes.nodes.iter().zip(&cells) {
+        // draw the four faces, each running between consecutive corners
+        for k in 0..4 {
+            let (start, end) = (&corners[k], &corners[(k + 1) % 4]);
             chart.draw_series(LineSeries::new(
                 vec![
-                    (start.get_x(), start.get_y()),
-                    (end.get_x(), end.get_y()),
+                    (start.x(), start.y()),
+                    (end.x(), end.y()),
                 ],
                 &BLACK,
             ))?;
         }
 
         if draw_numbers {
-            let center_x = (node.north_face.start.get_x() + node.south_face.end.get_x()) / 2.0;
-            let center_y = (node.north_face.start.get_y() + node.south_face.end.get_y()) / 2.0;
-            
+            let center = node.centroid(vertices).ok_or("node refers to a vertex that does not exist")?;
+
             chart.draw_series(PointSeries::of_element(
-                vec![(center_x, center_y)],
+                vec![(center.x(), center.y())],
                 1,
                 &RED,
                 &|coord, _size, _style| {
@@ -243,6 +234,6 @@ mod tests {
         let mut nodes = Nodes::new_2d();
         nodes.populate(&vertices).expect("failed to populate nodes");
         
-        plot_nodes_2d(&nodes, "2d-nodes.png", false).expect("failed to plot nodes");
+        plot_nodes_2d(&nodes, &vertices, "2d-nodes.png", false).expect("failed to plot nodes");
     }
 }
\ No newline at end of file
@@ -0,0 +1,30 @@
+#![allow(dead_code)]
+
+pub(crate) const NEWTON_TOLERANCE: f64 = 1e-12;
+pub(crate) const NEWTON_ITERATIONS: usize = 100;
+
+// newton iteration kept inside a bracket, falling back to bisection whenever a
+// step would leave it. f must change sign across the bracket
+pub(crate) fn newton(f: impl Fn(f64) -> f64, df: impl Fn(f64) -> f64, guess: f64, bracket: (f64, f64)) -> Result<f64, &'static str> {
+    let (mut low, mut high) = bracket;
+    let low_sign = f(low).signum();
+    if low_sign == f(high).signum() {
+        return Err("no solution lies within the bracket");
+    }
+
+    let mut x = guess.clamp(low, high);
+    for _ in 0..NEWTON_ITERATIONS {
+        let value = f(x);
+        if value.abs() < NEWTON_TOLERANCE {
+            return Ok(x);
+        }
+        if value.signum() == low_sign { low = x } else { high = x }
+
+        let step = x - value / df(x);
+        x = if step > low && step < high { step } else { 0.5 * (low + high) };
+        if high - low < NEWTON_TOLERANCE * x.abs().max(1.0) {
+            return Ok(x);
+        }
+    }
+    Err("newton iteration did not converge")
+}